@@ -0,0 +1,108 @@
+//! Differential fuzzing of the constant product pool arithmetic.
+//!
+//! Every function in `pallet_liquidity_pool::math` is compared with an arbitrary-precision
+//! reference, and the trade quotes are checked to never lower the pool's constant product
+//! and to round trip in the pool's favour.
+//!
+//!     cargo +nightly fuzz run amm_math
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use num_bigint::BigUint;
+use pallet_liquidity_pool::math;
+use sp_arithmetic::{PerThing, Perquintill};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    amount: u128,
+    other_amount: u128,
+    reserve_a: u128,
+    reserve_b: u128,
+    total_shares: u128,
+    fee_parts: u64,
+    protocol_share_parts: u64,
+}
+
+fn big(value: u128) -> BigUint {
+    BigUint::from(value)
+}
+
+fn narrow(value: BigUint) -> Option<u128> {
+    u128::try_from(value).ok()
+}
+
+fn reference_amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+    let fee_amount = big(amount_in) * big(fee.deconstruct() as u128) / big(Perquintill::ACCURACY as u128);
+    let amount_in_after_fee = big(amount_in) - fee_amount;
+    let denominator = big(reserve_in) + &amount_in_after_fee;
+    if denominator == BigUint::from(0u8) {
+        return None;
+    }
+    narrow(amount_in_after_fee * big(reserve_out) / denominator)
+}
+
+fn reference_amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+    let retained = big(fee.left_from_one().deconstruct() as u128);
+    if amount_out >= reserve_out || retained == BigUint::from(0u8) {
+        return None;
+    }
+    let amount_in_after_fee = narrow(big(reserve_in) * big(amount_out) / big(reserve_out - amount_out) + 1u8)?;
+    let scaled = big(amount_in_after_fee) * big(Perquintill::ACCURACY as u128);
+    narrow((scaled + &retained - 1u8) / retained)
+}
+
+fn reference_share(amount: u128, total_shares: u128, reserve: u128) -> Option<u128> {
+    if reserve == 0 {
+        return None;
+    }
+    narrow(big(amount) * big(total_shares) / big(reserve))
+}
+
+fuzz_target!(|input: Input| {
+    let Input { amount, other_amount, reserve_a, reserve_b, total_shares, fee_parts, protocol_share_parts } = input;
+    let fee = Perquintill::from_parts(fee_parts % (Perquintill::ACCURACY + 1));
+    let protocol_share = Perquintill::from_parts(protocol_share_parts % (Perquintill::ACCURACY + 1));
+
+    // Exact input quotes, and the constant product they leave behind
+    let amount_out = math::amount_out(amount, reserve_a, reserve_b, fee);
+    assert_eq!(amount_out, reference_amount_out(amount, reserve_a, reserve_b, fee));
+    if let Some(amount_out) = amount_out {
+        let amount_in_after_fee = amount - fee.mul_floor(amount);
+        assert!(amount_out <= reserve_b);
+        assert!(
+            (big(reserve_a) + big(amount_in_after_fee)) * big(reserve_b - amount_out) >= big(reserve_a) * big(reserve_b)
+        );
+    }
+
+    // Exact output quotes always buy at least the requested output
+    let amount_in = math::amount_in(amount, reserve_a, reserve_b, fee);
+    assert_eq!(amount_in, reference_amount_in(amount, reserve_a, reserve_b, fee));
+    if let Some(amount_in) = amount_in {
+        let received = math::amount_out(amount_in, reserve_a, reserve_b, fee);
+        assert!(received.map_or(false, |received| received >= amount));
+    }
+
+    // Shares never fail, and a deposit or withdrawal never claims more than its proportion
+    let initial = math::initial_shares(amount, other_amount);
+    assert_eq!(Some(initial), narrow((big(amount) * big(other_amount)).sqrt()));
+
+    let shares = math::shares_for_deposit(amount, other_amount, reserve_a, reserve_b, total_shares);
+    let expected = reference_share(amount, total_shares, reserve_a)
+        .and_then(|shares_a| Some(shares_a.min(reference_share(other_amount, total_shares, reserve_b)?)));
+    assert_eq!(shares, expected);
+
+    let withdrawal = math::withdrawal_for_shares(amount, reserve_a, reserve_b, total_shares);
+    let expected = reference_share(amount, reserve_a, total_shares)
+        .and_then(|amount_a| Some((amount_a, reference_share(amount, reserve_b, total_shares)?)));
+    assert_eq!(withdrawal, expected);
+    if let (Some((amount_a, amount_b)), true) = (withdrawal, amount <= total_shares) {
+        assert!(amount_a <= reserve_a && amount_b <= reserve_b);
+    }
+
+    // A zap never swaps more than it is given
+    if let Some(swap) = math::optimal_zap_amount(reserve_a, amount, fee, protocol_share) {
+        assert!(swap <= amount);
+    }
+});
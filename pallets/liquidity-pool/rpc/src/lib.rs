@@ -0,0 +1,261 @@
+//! JSON-RPC extension for `pallet-liquidity-pool`.
+//!
+//! Thin wrapper over [`LiquidityPoolRuntimeApi`]. Balances cross the RPC boundary as
+//! [`NumberOrHex`] so `u128` amounts survive JavaScript clients unchanged.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{traits::Block as BlockT, FixedPointNumber};
+
+pub use pallet_liquidity_pool_runtime_api::LiquidityPoolApi as LiquidityPoolRuntimeApi;
+use pallet_liquidity_pool_runtime_api::EpochStats;
+
+/// Volume and fee aggregates of a pool over one epoch, as returned over RPC
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolEpochStats {
+    pub epoch: u32,
+    pub volume_a: NumberOrHex,
+    pub volume_b: NumberOrHex,
+    pub fees_a: NumberOrHex,
+    pub fees_b: NumberOrHex,
+    pub trades: u32,
+}
+
+impl<Balance: Into<NumberOrHex>> From<EpochStats<Balance>> for PoolEpochStats {
+    fn from(stats: EpochStats<Balance>) -> Self {
+        Self {
+            epoch: stats.epoch,
+            volume_a: stats.volume_a.into(),
+            volume_b: stats.volume_b.into(),
+            fees_a: stats.fees_a.into(),
+            fees_b: stats.fees_b.into(),
+            trades: stats.trades,
+        }
+    }
+}
+
+#[rpc(client, server)]
+pub trait LiquidityPoolApi<BlockHash, AccountId, AssetId> {
+    /// Output of swapping an exact `amount_in` along `path`
+    #[method(name = "liquidityPool_quotePriceExactIn")]
+    fn quote_price_exact_in(
+        &self,
+        path: Vec<AssetId>,
+        amount_in: NumberOrHex,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Input needed to receive an exact `amount_out` at the end of `path`
+    #[method(name = "liquidityPool_quotePriceExactOut")]
+    fn quote_price_exact_out(
+        &self,
+        path: Vec<AssetId>,
+        amount_out: NumberOrHex,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Reserves of the pool for `(asset_a, asset_b)`, in the order given
+    #[method(name = "liquidityPool_getReserves")]
+    fn get_reserves(
+        &self,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<(NumberOrHex, NumberOrHex)>>;
+
+    /// LP share balance of `who` in the pool for `(asset_a, asset_b)`
+    #[method(name = "liquidityPool_getLpBalance")]
+    fn get_lp_balance(
+        &self,
+        who: AccountId,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<NumberOrHex>;
+
+    /// Time-weighted average price of `asset_in` in `asset_out` over at least `window` blocks,
+    /// as a fixed-point number with 18 decimals
+    #[method(name = "liquidityPool_consult")]
+    fn consult(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        window: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<NumberOrHex>>;
+
+    /// Volume and fee aggregates of the pool for `(asset_a, asset_b)` over its most recent
+    /// epochs with trades, oldest first, in the asset order given
+    #[method(name = "liquidityPool_getPoolStats")]
+    fn get_pool_stats(
+        &self,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<PoolEpochStats>>;
+}
+
+/// Error codes returned by the liquidity pool RPC
+pub enum Error {
+    /// The runtime API call failed
+    RuntimeError,
+    /// An amount did not fit the runtime's balance type
+    InvalidAmount,
+}
+
+impl From<Error> for i32 {
+    fn from(e: Error) -> i32 {
+        match e {
+            Error::RuntimeError => 1,
+            Error::InvalidAmount => 2,
+        }
+    }
+}
+
+/// Implementation of the liquidity pool RPC
+pub struct LiquidityPool<C, Block> {
+    client: Arc<C>,
+    _marker: PhantomData<Block>,
+}
+
+impl<C, Block> LiquidityPool<C, Block> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client, _marker: Default::default() }
+    }
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        Error::RuntimeError.into(),
+        "Unable to query the liquidity pool runtime API.",
+        Some(format!("{:?}", e)),
+    ))
+    .into()
+}
+
+fn to_balance<Balance: TryFrom<u128>>(amount: NumberOrHex) -> RpcResult<Balance> {
+    amount
+        .into_u256()
+        .try_into()
+        .ok()
+        .and_then(|value: u128| Balance::try_from(value).ok())
+        .ok_or_else(|| {
+            CallError::Custom(ErrorObject::owned(
+                Error::InvalidAmount.into(),
+                "Amount does not fit the runtime balance type.",
+                Some(format!("{:?}", amount)),
+            ))
+            .into()
+        })
+}
+
+#[async_trait]
+impl<C, Block, AccountId, AssetId, Balance>
+    LiquidityPoolApiServer<<Block as BlockT>::Hash, AccountId, AssetId> for LiquidityPool<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: LiquidityPoolRuntimeApi<Block, AccountId, AssetId, Balance>,
+    AccountId: Codec + Send + Sync + 'static,
+    AssetId: Codec + Send + Sync + 'static,
+    Balance: Codec + Copy + TryFrom<u128> + Into<NumberOrHex> + Send + Sync + 'static,
+{
+    fn quote_price_exact_in(
+        &self,
+        path: Vec<AssetId>,
+        amount_in: NumberOrHex,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let amount_in: Balance = to_balance(amount_in)?;
+
+        api.quote_price_exact_in(at, path, amount_in)
+            .map(|quote| quote.map(Into::into))
+            .map_err(runtime_error)
+    }
+
+    fn quote_price_exact_out(
+        &self,
+        path: Vec<AssetId>,
+        amount_out: NumberOrHex,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        let amount_out: Balance = to_balance(amount_out)?;
+
+        api.quote_price_exact_out(at, path, amount_out)
+            .map(|quote| quote.map(Into::into))
+            .map_err(runtime_error)
+    }
+
+    fn get_reserves(
+        &self,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<(NumberOrHex, NumberOrHex)>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.get_reserves(at, asset_a, asset_b)
+            .map(|reserves| reserves.map(|(a, b)| (a.into(), b.into())))
+            .map_err(runtime_error)
+    }
+
+    fn get_lp_balance(
+        &self,
+        who: AccountId,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<NumberOrHex> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.get_lp_balance(at, who, asset_a, asset_b)
+            .map(Into::into)
+            .map_err(runtime_error)
+    }
+
+    fn consult(
+        &self,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        window: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<NumberOrHex>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.consult(at, asset_in, asset_out, window)
+            .map(|price| price.map(|price| price.into_inner().into()))
+            .map_err(runtime_error)
+    }
+
+    fn get_pool_stats(
+        &self,
+        asset_a: AssetId,
+        asset_b: AssetId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<PoolEpochStats>> {
+        let api = self.client.runtime_api();
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+        api.get_pool_stats(at, asset_a, asset_b)
+            .map(|history| history.into_iter().map(Into::into).collect())
+            .map_err(runtime_error)
+    }
+}
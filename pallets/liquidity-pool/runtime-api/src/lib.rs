@@ -0,0 +1,45 @@
+//! Runtime API for `pallet-liquidity-pool`.
+//!
+//! Every quote is computed by the pallet's own integer swap path, so the numbers
+//! returned here are exactly what `swap` and the routed swap calls would execute
+//! against the state of the queried block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_arithmetic::FixedU128;
+use sp_std::vec::Vec;
+
+pub use pallet_liquidity_pool::EpochStats;
+
+sp_api::decl_runtime_apis! {
+    pub trait LiquidityPoolApi<AccountId, AssetId, Balance>
+    where
+        AccountId: Codec,
+        AssetId: Codec,
+        Balance: Codec,
+    {
+        /// Output of swapping an exact `amount_in` of `path[0]` along `path`,
+        /// or `None` if the route cannot be traded
+        fn quote_price_exact_in(path: Vec<AssetId>, amount_in: Balance) -> Option<Balance>;
+
+        /// Input of `path[0]` needed to receive an exact `amount_out` of the last asset in `path`,
+        /// or `None` if the route cannot be traded
+        fn quote_price_exact_out(path: Vec<AssetId>, amount_out: Balance) -> Option<Balance>;
+
+        /// Reserves of the pool for `(asset_a, asset_b)`, in the order the assets were given
+        fn get_reserves(asset_a: AssetId, asset_b: AssetId) -> Option<(Balance, Balance)>;
+
+        /// LP share balance of `who` in the pool for `(asset_a, asset_b)`
+        fn get_lp_balance(who: AccountId, asset_a: AssetId, asset_b: AssetId) -> Balance;
+
+        /// Time-weighted average price of `asset_in` in units of `asset_out` over at least
+        /// the last `window` blocks, from the pool's cumulative price accumulators
+        fn consult(asset_in: AssetId, asset_out: AssetId, window: u32) -> Option<FixedU128>;
+
+        /// Volume and fee aggregates of the pair `(asset_a, asset_b)`, StableSwap trades between
+        /// them included, over its most recent epochs with trades, oldest first, with `_a` and
+        /// `_b` fields in the order given
+        fn get_pool_stats(asset_a: AssetId, asset_b: AssetId) -> Vec<EpochStats<Balance>>;
+    }
+}
@@ -0,0 +1,622 @@
+//! Benchmarks for `pallet-liquidity-pool`.
+
+use super::*;
+use crate::Pallet as LiquidityPool;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller, BenchmarkError};
+use frame_support::{
+    assert_ok,
+    traits::{fungibles::{Create, Inspect, Mutate}, Currency, EnsureOrigin, Get},
+    BoundedVec,
+};
+use frame_system::RawOrigin;
+use sp_runtime::{traits::SaturatedConversion, PerThing};
+use sp_std::prelude::*;
+
+const SEED: u32 = 0;
+
+/// A large balance that still leaves headroom for the share and swap products
+fn big_balance<T: Config>() -> BalanceOf<T> {
+    BalanceOf::<T>::from(u32::MAX).saturating_mul(1_000u32.into())
+}
+
+/// Create `count` fresh assets and fund `who` with all of them and the native currency
+fn setup_assets<T: Config>(who: &T::AccountId, count: u32) -> Vec<T::AssetId> {
+    let amount = big_balance::<T>();
+    T::Currency::make_free_balance_be(who, amount);
+    (0..count)
+        .map(|i| {
+            let asset = T::BenchmarkHelper::asset_id(i);
+            assert_ok!(T::Assets::create(asset, who.clone(), true, 1u32.into()));
+            assert_ok!(T::Assets::mint_into(asset, who, amount));
+            asset
+        })
+        .collect()
+}
+
+/// Register and seed a pool so that later calls hit the non-initial code paths
+fn setup_pool<T: Config>(who: &T::AccountId, asset_a: T::AssetId, asset_b: T::AssetId) {
+    let deposit = big_balance::<T>() / 1_000u32.into();
+    assert_ok!(LiquidityPool::<T>::create_pool(
+        RawOrigin::Signed(who.clone()).into(),
+        asset_a,
+        asset_b,
+    ));
+    assert_ok!(LiquidityPool::<T>::add_liquidity(
+        RawOrigin::Signed(who.clone()).into(),
+        asset_a,
+        asset_b,
+        deposit,
+        deposit,
+        0u32.into(),
+        None,
+    ));
+}
+
+/// Fill a pool's observation buffer so the next oracle update has to evict the oldest entry
+fn fill_price_observations<T: Config>(who: &T::AccountId, asset_a: T::AssetId, asset_b: T::AssetId) {
+    for _ in 0..T::MaxPriceObservations::get() {
+        let next = frame_system::Pallet::<T>::block_number() + 1u32.into();
+        frame_system::Pallet::<T>::set_block_number(next);
+        assert_ok!(LiquidityPool::<T>::swap(
+            RawOrigin::Signed(who.clone()).into(),
+            asset_a,
+            asset_b,
+            1_000u32.into(),
+            0u32.into(),
+            None,
+        ));
+    }
+    let next = frame_system::Pallet::<T>::block_number() + 1u32.into();
+    frame_system::Pallet::<T>::set_block_number(next);
+}
+
+/// Pools along `path`, each seeded, with a path of `n` assets
+fn setup_path<T: Config>(who: &T::AccountId, n: u32) -> SwapPathOf<T> {
+    let assets = setup_assets::<T>(who, n);
+    for hop in assets.windows(2) {
+        setup_pool::<T>(who, hop[0], hop[1]);
+    }
+    assets.try_into().expect("path length within MaxSwapPathLength")
+}
+
+/// Register a concentrated pool at price 1 with a position around it, returning the position id
+fn setup_position<T: Config>(who: &T::AccountId, asset_a: T::AssetId, asset_b: T::AssetId) -> PositionId {
+    let deposit = big_balance::<T>() / 1_000u32.into();
+    assert_ok!(LiquidityPool::<T>::create_concentrated_pool(
+        RawOrigin::Signed(who.clone()).into(),
+        asset_a,
+        asset_b,
+        10,
+        0,
+    ));
+    let position_id = NextPositionId::<T>::get();
+    assert_ok!(LiquidityPool::<T>::open_position(
+        RawOrigin::Signed(who.clone()).into(),
+        asset_a,
+        asset_b,
+        -1_000,
+        1_000,
+        deposit,
+        deposit,
+    ));
+    position_id
+}
+
+/// Register a concentrated pool at price 1 whose price has to cross `t` initialized ticks when
+/// asset_a is sold down to tick `-10 * t - 5`, returning the pair and the input that gets there
+fn setup_tick_ladder<T: Config>(
+    who: &T::AccountId,
+    t: u32,
+) -> Result<((T::AssetId, T::AssetId), BalanceOf<T>), BenchmarkError> {
+    let assets = setup_assets::<T>(who, 2);
+    let pair = LiquidityPool::<T>::canonical_pair(assets[0], assets[1]);
+    let deposit = big_balance::<T>() / 10u32.into();
+    let rungs = t as i32;
+    assert_ok!(LiquidityPool::<T>::create_concentrated_pool(
+        RawOrigin::Signed(who.clone()).into(),
+        pair.0,
+        pair.1,
+        10,
+        0,
+    ));
+    assert_ok!(LiquidityPool::<T>::open_position(
+        RawOrigin::Signed(who.clone()).into(),
+        pair.0,
+        pair.1,
+        -10 * (rungs + 2),
+        10,
+        deposit,
+        deposit,
+    ));
+
+    // The rungs hold next to no liquidity, so size the input against the wide position alone
+    let state = ConcentratedPools::<T>::get(pair).ok_or(BenchmarkError::Stop("pool not created"))?;
+    let amount_net = concentrated::amount_a_delta(
+        concentrated::sqrt_price_at_tick(-10 * rungs - 5),
+        state.sqrt_price,
+        state.liquidity.saturated_into::<u128>(),
+        true,
+    )
+    .ok_or(BenchmarkError::Stop("input out of range"))?;
+
+    for rung in 1..=rungs {
+        assert_ok!(LiquidityPool::<T>::open_position(
+            RawOrigin::Signed(who.clone()).into(),
+            pair.0,
+            pair.1,
+            -10 * rung,
+            10,
+            1_000u32.into(),
+            1_000u32.into(),
+        ));
+    }
+
+    let (_, pool) = LiquidityPool::<T>::resolve_pool(pair.0, pair.1)?;
+    let amount_in = pool.fee_tier.left_from_one().saturating_reciprocal_mul_ceil(amount_net);
+    Ok((pair, amount_in.saturated_into()))
+}
+
+/// Register a four-asset StableSwap pool seeded with a balanced deposit
+fn setup_stable_pool<T: Config>(who: &T::AccountId) -> Result<(StablePoolId, Vec<T::AssetId>), BenchmarkError> {
+    let assets = setup_assets::<T>(who, MAX_STABLE_ASSETS);
+    let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    let pool_id = NextStablePoolId::<T>::get();
+    assert_ok!(LiquidityPool::<T>::create_stable_pool(origin, assets.clone().try_into().unwrap(), 100));
+    let deposit = big_balance::<T>() / 1_000u32.into();
+    assert_ok!(LiquidityPool::<T>::add_stable_liquidity(
+        RawOrigin::Signed(who.clone()).into(),
+        pool_id,
+        vec![deposit; assets.len()].try_into().unwrap(),
+        0u32.into(),
+    ));
+    Ok((pool_id, assets))
+}
+
+/// A funded farm on a seeded pool emitting `MaxRewardAssets` rewards for ten blocks of
+/// `who` staking half of their shares
+fn setup_farm<T: Config>(who: &T::AccountId) -> Result<Vec<T::AssetId>, BenchmarkError> {
+    let assets = setup_assets::<T>(who, 2 + T::MaxRewardAssets::get());
+    setup_pool::<T>(who, assets[0], assets[1]);
+    let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    let now = frame_system::Pallet::<T>::block_number();
+    for reward_asset in &assets[2..] {
+        assert_ok!(T::Assets::mint_into(*reward_asset, &LiquidityPool::<T>::farm_account_id(), big_balance::<T>()));
+        assert_ok!(LiquidityPool::<T>::set_reward_schedule(
+            origin.clone(),
+            assets[0],
+            assets[1],
+            *reward_asset,
+            1_000u32.into(),
+            now,
+            now + 1_000u32.into(),
+        ));
+    }
+    let amount = LiquidityPool::<T>::lp_balance(who.clone(), assets[0], assets[1]) / 2u32.into();
+    assert_ok!(LiquidityPool::<T>::stake(RawOrigin::Signed(who.clone()).into(), assets[0], assets[1], amount));
+    frame_system::Pallet::<T>::set_block_number(now + 10u32.into());
+    Ok(assets)
+}
+
+/// Seed a pool and fill its book with `count` limit orders of `who` that cannot fill yet.
+/// Returns the pool's assets and the id of the last order.
+fn setup_orders<T: Config>(who: &T::AccountId, count: u32) -> (Vec<T::AssetId>, Option<OrderId>) {
+    let assets = setup_assets::<T>(who, 2);
+    setup_pool::<T>(who, assets[0], assets[1]);
+    let amount = big_balance::<T>() / 1_000_000u32.into();
+    let mut last = None;
+    for _ in 0..count {
+        last = Some(NextOrderId::<T>::get());
+        assert_ok!(LiquidityPool::<T>::place_order(
+            RawOrigin::Signed(who.clone()).into(),
+            assets[0],
+            assets[1],
+            amount,
+            amount * 2u32.into(),
+            OrderKind::Limit,
+            None,
+        ));
+    }
+    (assets, last)
+}
+
+benchmarks! {
+    create_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1])
+    verify {
+        assert!(LiquidityPool::<T>::resolve_pool(assets[0], assets[1]).is_ok());
+    }
+
+    add_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let amount = big_balance::<T>() / 10_000u32.into();
+        let total_before = TotalLiquidity::<T>::get(LiquidityPool::<T>::canonical_pair(assets[0], assets[1]));
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], amount, amount, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+    verify {
+        let pair = LiquidityPool::<T>::canonical_pair(assets[0], assets[1]);
+        assert!(TotalLiquidity::<T>::get(pair) > total_before);
+    }
+
+    remove_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let liquidity = LiquidityPool::<T>::lp_balance(caller.clone(), assets[0], assets[1]) / 2u32.into();
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1], liquidity, 0u32.into(), 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+    verify {
+        assert!(!LiquidityPool::<T>::lp_balance(caller, assets[0], assets[1]).is_zero());
+    }
+
+    swap {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let amount = big_balance::<T>() / 100_000u32.into();
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], amount, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+
+    concentrated_swap_hop {
+        let t in 0 .. T::MaxTicks::get().saturating_sub(2);
+        let caller: T::AccountId = whitelisted_caller();
+        let (pair, amount_in) = setup_tick_ladder::<T>(&caller, t)?;
+    }: swap(RawOrigin::Signed(caller), pair.0, pair.1, amount_in, 0u32.into(), None)
+    verify {
+        let tick = ConcentratedPools::<T>::get(pair).expect("pool exists").tick;
+        let rungs = t as i32;
+        assert!(tick < -10 * rungs && tick >= -10 * (rungs + 1));
+    }
+
+    swap_exact_tokens_for_tokens {
+        let n in 2 .. T::MaxSwapPathLength::get();
+        let caller: T::AccountId = whitelisted_caller();
+        let path = setup_path::<T>(&caller, n);
+        let amount = big_balance::<T>() / 100_000u32.into();
+    }: _(RawOrigin::Signed(caller), path, amount, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+
+    swap_tokens_for_exact_tokens {
+        let n in 2 .. T::MaxSwapPathLength::get();
+        let caller: T::AccountId = whitelisted_caller();
+        let path = setup_path::<T>(&caller, n);
+        let amount = big_balance::<T>() / 1_000_000u32.into();
+    }: _(RawOrigin::Signed(caller), path, amount, big_balance::<T>(), Some(frame_system::Pallet::<T>::block_number()))
+
+    claim_protocol_fees {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        let trader: T::AccountId = account("trader", 0, SEED);
+        let amount = big_balance::<T>() / 100_000u32.into();
+        T::Currency::make_free_balance_be(&trader, big_balance::<T>());
+        for asset in &assets {
+            assert_ok!(T::Assets::mint_into(*asset, &trader, amount));
+        }
+        assert_ok!(LiquidityPool::<T>::swap(RawOrigin::Signed(trader.clone()).into(), assets[0], assets[1], amount, 0u32.into(), None));
+        assert_ok!(LiquidityPool::<T>::swap(RawOrigin::Signed(trader).into(), assets[1], assets[0], amount, 0u32.into(), None));
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1])
+
+    create_concentrated_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], 10, 0)
+    verify {
+        let pair = LiquidityPool::<T>::canonical_pair(assets[0], assets[1]);
+        assert!(ConcentratedPools::<T>::contains_key(pair));
+    }
+
+    open_position {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_position::<T>(&caller, assets[0], assets[1]);
+        let amount = big_balance::<T>() / 10_000u32.into();
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], -500, 500, amount, amount)
+    verify {
+        assert_eq!(NextPositionId::<T>::get(), 2);
+    }
+
+    increase_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        let position_id = setup_position::<T>(&caller, assets[0], assets[1]);
+        let amount = big_balance::<T>() / 10_000u32.into();
+    }: _(RawOrigin::Signed(caller), position_id, amount, amount)
+
+    decrease_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        let position_id = setup_position::<T>(&caller, assets[0], assets[1]);
+        let liquidity = Positions::<T>::get(position_id).unwrap().liquidity;
+    }: _(RawOrigin::Signed(caller), position_id, liquidity, 0u32.into(), 0u32.into())
+    verify {
+        assert!(Positions::<T>::get(position_id).unwrap().liquidity.is_zero());
+    }
+
+    collect_fees {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        let position_id = setup_position::<T>(&caller, assets[0], assets[1]);
+        let amount = big_balance::<T>() / 100_000u32.into();
+        assert_ok!(LiquidityPool::<T>::swap(RawOrigin::Signed(caller.clone()).into(), assets[0], assets[1], amount, 0u32.into(), None));
+        assert_ok!(LiquidityPool::<T>::swap(RawOrigin::Signed(caller.clone()).into(), assets[1], assets[0], amount, 0u32.into(), None));
+    }: _(RawOrigin::Signed(caller), position_id)
+
+    create_stable_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, MAX_STABLE_ASSETS);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let assets: BoundedVec<_, _> = assets.try_into().unwrap();
+    }: _<T::RuntimeOrigin>(origin, assets, 100)
+    verify {
+        assert!(StablePools::<T>::contains_key(0));
+    }
+
+    add_stable_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, assets) = setup_stable_pool::<T>(&caller)?;
+        // An unbalanced deposit pays the imbalance fee on every asset
+        let amount = big_balance::<T>() / 10_000u32.into();
+        let amounts: Vec<BalanceOf<T>> = (0..assets.len() as u32).map(|i| amount * (i + 1).into()).collect();
+    }: _(RawOrigin::Signed(caller), pool_id, amounts.try_into().unwrap(), 0u32.into())
+
+    remove_stable_liquidity {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, assets) = setup_stable_pool::<T>(&caller)?;
+        let lp_asset = StablePools::<T>::get(pool_id).unwrap().lp_asset;
+        let liquidity = T::Assets::balance(lp_asset, &caller) / 2u32.into();
+        let min_amounts = vec![BalanceOf::<T>::zero(); assets.len()];
+    }: _(RawOrigin::Signed(caller), pool_id, liquidity, min_amounts.try_into().unwrap())
+
+    stable_swap {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, assets) = setup_stable_pool::<T>(&caller)?;
+        let amount = big_balance::<T>() / 100_000u32.into();
+    }: _(RawOrigin::Signed(caller), pool_id, assets[0], assets[1], amount, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+
+    ramp_amplification {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, _) = setup_stable_pool::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let future_block = frame_system::Pallet::<T>::block_number() + T::MinRampDuration::get();
+    }: _<T::RuntimeOrigin>(origin, pool_id, 1_000, future_block)
+
+    stop_amplification_ramp {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, _) = setup_stable_pool::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let future_block = frame_system::Pallet::<T>::block_number() + T::MinRampDuration::get();
+        assert_ok!(LiquidityPool::<T>::ramp_amplification(origin.clone(), pool_id, 1_000, future_block));
+    }: _<T::RuntimeOrigin>(origin, pool_id)
+
+    claim_stable_protocol_fees {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, assets) = setup_stable_pool::<T>(&caller)?;
+        let amount = big_balance::<T>() / 100_000u32.into();
+        for hop in assets.windows(2) {
+            assert_ok!(LiquidityPool::<T>::stable_swap(
+                RawOrigin::Signed(caller.clone()).into(),
+                pool_id,
+                hop[0],
+                hop[1],
+                amount,
+                0u32.into(),
+                None,
+            ));
+        }
+        assert_ok!(LiquidityPool::<T>::stable_swap(
+            RawOrigin::Signed(caller.clone()).into(),
+            pool_id,
+            assets[assets.len() - 1],
+            assets[0],
+            amount,
+            0u32.into(),
+            None,
+        ));
+    }: _(RawOrigin::Signed(caller), pool_id)
+
+    add_liquidity_single_asset {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let before = LiquidityPool::<T>::lp_balance(caller.clone(), assets[0], assets[1]);
+        let amount = big_balance::<T>() / 10_000u32.into();
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1], amount, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+    verify {
+        assert!(LiquidityPool::<T>::lp_balance(caller, assets[0], assets[1]) > before);
+    }
+
+    remove_liquidity_single_asset {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let liquidity = LiquidityPool::<T>::lp_balance(caller.clone(), assets[0], assets[1]) / 2u32.into();
+        let before = LiquidityPool::<T>::asset_balance(assets[0], &caller);
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1], liquidity, 0u32.into(), Some(frame_system::Pallet::<T>::block_number()))
+    verify {
+        assert!(LiquidityPool::<T>::asset_balance(assets[0], &caller) > before);
+    }
+
+    pause_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    }: _<T::RuntimeOrigin>(origin, assets[0], assets[1])
+    verify {
+        assert!(PausedPools::<T>::contains_key(LiquidityPool::<T>::canonical_pair(assets[0], assets[1])));
+    }
+
+    unpause_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        assert_ok!(LiquidityPool::<T>::pause_pool(origin.clone(), assets[0], assets[1]));
+    }: _<T::RuntimeOrigin>(origin, assets[0], assets[1])
+    verify {
+        assert!(!PausedPools::<T>::contains_key(LiquidityPool::<T>::canonical_pair(assets[0], assets[1])));
+    }
+
+    set_pool_fee {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let fee_tier = T::MaxPoolFee::get();
+    }: _<T::RuntimeOrigin>(origin, assets[0], assets[1], fee_tier)
+    verify {
+        let pair = LiquidityPool::<T>::canonical_pair(assets[0], assets[1]);
+        assert_eq!(Pools::<T>::get(pair).unwrap().fee_tier, fee_tier);
+    }
+
+    pause_stable_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, _) = setup_stable_pool::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    }: _<T::RuntimeOrigin>(origin, pool_id)
+    verify {
+        assert!(PausedStablePools::<T>::contains_key(pool_id));
+    }
+
+    unpause_stable_pool {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, _) = setup_stable_pool::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        assert_ok!(LiquidityPool::<T>::pause_stable_pool(origin.clone(), pool_id));
+    }: _<T::RuntimeOrigin>(origin, pool_id)
+    verify {
+        assert!(!PausedStablePools::<T>::contains_key(pool_id));
+    }
+
+    set_stable_pool_fee {
+        let caller: T::AccountId = whitelisted_caller();
+        let (pool_id, _) = setup_stable_pool::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let fee_tier = T::MaxPoolFee::get();
+    }: _<T::RuntimeOrigin>(origin, pool_id, fee_tier)
+    verify {
+        assert_eq!(StablePools::<T>::get(pool_id).unwrap().fee_tier, fee_tier);
+    }
+
+    allow_asset {
+        let asset = T::BenchmarkHelper::asset_id(0);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        assert_ok!(LiquidityPool::<T>::deny_asset(origin.clone(), asset));
+    }: _<T::RuntimeOrigin>(origin, asset)
+    verify {
+        assert!(!DeniedAssets::<T>::contains_key(asset));
+    }
+
+    deny_asset {
+        let asset = T::BenchmarkHelper::asset_id(0);
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    }: _<T::RuntimeOrigin>(origin, asset)
+    verify {
+        assert!(DeniedAssets::<T>::contains_key(asset));
+    }
+
+    set_reward_schedule {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_farm::<T>(&caller)?;
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let end = frame_system::Pallet::<T>::block_number() + 2_000u32.into();
+        let reward_asset = assets[assets.len() - 1];
+    }: _<T::RuntimeOrigin>(origin, assets[0], assets[1], reward_asset, 2_000u32.into(), 0u32.into(), end)
+
+    stake {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_farm::<T>(&caller)?;
+        let amount = LiquidityPool::<T>::lp_balance(caller.clone(), assets[0], assets[1]);
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1], amount)
+    verify {
+        assert!(LiquidityPool::<T>::lp_balance(caller, assets[0], assets[1]).is_zero());
+    }
+
+    unstake {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_farm::<T>(&caller)?;
+        let pair = LiquidityPool::<T>::canonical_pair(assets[0], assets[1]);
+        let amount = Stakes::<T>::get(pair, &caller);
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1], amount)
+    verify {
+        assert!(Stakes::<T>::get(pair, &caller).is_zero());
+    }
+
+    claim_rewards {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_farm::<T>(&caller)?;
+        let before = LiquidityPool::<T>::asset_balance(assets[2], &caller);
+    }: _(RawOrigin::Signed(caller.clone()), assets[0], assets[1])
+    verify {
+        assert!(LiquidityPool::<T>::asset_balance(assets[2], &caller) > before);
+    }
+
+    flash_swap {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        // Borrow both assets and repay each twice over, so every fee path is taken
+        let amount = big_balance::<T>() / 100_000u32.into();
+        let repay = amount * 2u32.into();
+        let call: <T as Config>::RuntimeCall = frame_system::Call::<T>::remark { remark: vec![] }.into();
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], amount, amount, repay, repay, Box::new(call))
+
+    place_order {
+        let caller: T::AccountId = whitelisted_caller();
+        let (assets, _) = setup_orders::<T>(&caller, T::MaxOrdersPerPool::get() - 1);
+        let amount = big_balance::<T>() / 1_000_000u32.into();
+        let order_id = NextOrderId::<T>::get();
+    }: _(RawOrigin::Signed(caller), assets[0], assets[1], amount, amount * 2u32.into(), OrderKind::Limit, None)
+    verify {
+        assert!(Orders::<T>::contains_key(order_id));
+    }
+
+    cancel_order {
+        let caller: T::AccountId = whitelisted_caller();
+        let (_, last) = setup_orders::<T>(&caller, T::MaxOrdersPerPool::get());
+        let order_id = last.ok_or(BenchmarkError::Weightless)?;
+    }: _(RawOrigin::Signed(caller), order_id)
+    verify {
+        assert!(!Orders::<T>::contains_key(order_id));
+    }
+
+    fill_order {
+        let caller: T::AccountId = whitelisted_caller();
+        let (assets, last) = setup_orders::<T>(&caller, 1);
+        fill_price_observations::<T>(&caller, assets[0], assets[1]);
+        let order_id = last.ok_or(BenchmarkError::Weightless)?;
+        // Lower the limit so the order fills at the current price
+        let mut order = Orders::<T>::get(order_id).ok_or(BenchmarkError::Weightless)?;
+        order.min_amount_out = 1u32.into();
+        let amount_out = LiquidityPool::<T>::order_quote(&order).ok_or(BenchmarkError::Weightless)?;
+    }: {
+        assert_ok!(LiquidityPool::<T>::fill_order(order_id, &order, amount_out));
+    }
+    verify {
+        assert!(!Orders::<T>::contains_key(order_id));
+    }
+
+    release_held_shares {
+        let caller: T::AccountId = whitelisted_caller();
+        let assets = setup_assets::<T>(&caller, 2);
+        setup_pool::<T>(&caller, assets[0], assets[1]);
+        let (pair, pool) = LiquidityPool::<T>::resolve_pool(assets[0], assets[1])?;
+        let holder: T::AccountId = account("holder", 0, SEED);
+        let shares: BalanceOf<T> = 1_000u32.into();
+        assert_ok!(T::Assets::mint_into(pool.lp_asset, &LiquidityPool::<T>::account_id(), shares));
+        HeldShares::<T>::insert((pair.0, pair.1, holder.clone()), shares);
+        T::Currency::make_free_balance_be(&holder, big_balance::<T>());
+        let origin = T::PoolAdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+    }: _<T::RuntimeOrigin>(origin, assets[0], assets[1], holder.clone())
+    verify {
+        assert_eq!(T::Assets::balance(pool.lp_asset, &holder), shares);
+    }
+
+    impl_benchmark_test_suite!(LiquidityPool, crate::mock::new_test_ext(), crate::mock::Test);
+}
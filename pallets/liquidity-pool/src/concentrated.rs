@@ -0,0 +1,460 @@
+//! Concentrated liquidity for `pallet-liquidity-pool`.
+//!
+//! A concentrated pool prices asset_a in units of asset_b of its canonical pair. Prices are
+//! quantised into ticks, `price(tick) = 1.0001^tick`, and the pool tracks the square root of
+//! the current price. Positions provide liquidity `L` between two ticks only, and within a
+//! range without initialized ticks the pool behaves like a constant-product pool with
+//! reserves `x = L / sqrt(P)` of asset_a and `y = L * sqrt(P)` of asset_b.
+//!
+//! A pool has at most `MaxTicks` initialized ticks. The account whose position initializes a
+//! tick reserves `TickDeposit` for it until no position is bounded by the tick any more, so
+//! filling a pool's ticks with dust positions locks up a deposit per tick.
+//!
+//! Fees are accounted per unit of liquidity: `fee_growth_global` only ever grows, every
+//! initialized tick remembers the growth on its far side, and a position's earnings are its
+//! liquidity times the growth inside its range since it last settled. Fee growth values wrap
+//! on overflow like the oracle accumulators; only differences are meaningful.
+
+use super::*;
+use frame_support::{
+    ensure,
+    pallet_prelude::{DispatchError, DispatchResult},
+    sp_runtime::{
+        traits::{CheckedAdd, CheckedSub, Saturating, Zero},
+        FixedPointNumber, FixedU128, PerThing, Perquintill, SaturatedConversion,
+    },
+    traits::{Get, ReservableCurrency},
+    RuntimeDebug,
+};
+use crate::math::mul_div;
+
+/// Lowest tick a position may use; its price is about 5.4e-20
+pub const MIN_TICK: i32 = -443_636;
+
+/// Highest tick a position may use; its price is about 1.9e19
+pub const MAX_TICK: i32 = 443_636;
+
+/// sqrt(1.0001) in `FixedU128` precision, the sqrt price ratio between neighbouring ticks
+const SQRT_TICK_BASE: u128 = 1_000_049_998_750_062_496;
+
+/// Outcome of running a swap through a concentrated pool
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct ConcentratedSwap<Balance> {
+    /// Amount paid out of the pool
+    pub amount_out: Balance,
+    /// Total trading fee taken from the input
+    pub fee_amount: Balance,
+    /// Number of initialized ticks the price moved across
+    pub ticks_crossed: u32,
+}
+
+/// Square root price of a tick
+pub fn sqrt_price_at_tick(tick: i32) -> FixedU128 {
+    let sqrt_price = FixedU128::from_inner(SQRT_TICK_BASE).saturating_pow(tick.unsigned_abs() as usize);
+    if tick < 0 {
+        sqrt_price.reciprocal().unwrap_or_else(FixedU128::zero)
+    } else {
+        sqrt_price
+    }
+}
+
+/// The highest tick whose square root price does not exceed `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: FixedU128) -> i32 {
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        // Round the midpoint up so the search always makes progress
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid) <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+/// Amount of asset_a held by liquidity `L` between two square root prices:
+/// `L * (upper - lower) / (lower * upper)`
+pub fn amount_a_delta(lower: FixedU128, upper: FixedU128, liquidity: u128, round_up: bool) -> Option<u128> {
+    let (lower, upper) = (lower.into_inner(), upper.into_inner());
+    let spread = upper.checked_sub(lower)?;
+    let scaled = mul_div(liquidity, spread, upper, round_up)?;
+    mul_div(scaled, FixedU128::accuracy(), lower, round_up)
+}
+
+/// Amount of asset_b held by liquidity `L` between two square root prices:
+/// `L * (upper - lower)`
+pub fn amount_b_delta(lower: FixedU128, upper: FixedU128, liquidity: u128, round_up: bool) -> Option<u128> {
+    let spread = upper.into_inner().checked_sub(lower.into_inner())?;
+    mul_div(liquidity, spread, FixedU128::accuracy(), round_up)
+}
+
+/// Largest liquidity that `amount_a` and `amount_b` can back over `[lower, upper]` at the
+/// current square root price
+pub fn liquidity_for_amounts(
+    sqrt_price: FixedU128,
+    lower: FixedU128,
+    upper: FixedU128,
+    amount_a: u128,
+    amount_b: u128,
+) -> Option<u128> {
+    // L = amount_a * lower * upper / (upper - lower)
+    let for_a = |lower: FixedU128, upper: FixedU128| -> Option<u128> {
+        let spread = upper.into_inner().checked_sub(lower.into_inner())?;
+        let scaled = mul_div(amount_a, lower.into_inner(), spread, false)?;
+        mul_div(scaled, upper.into_inner(), FixedU128::accuracy(), false)
+    };
+    // L = amount_b / (upper - lower)
+    let for_b = |lower: FixedU128, upper: FixedU128| -> Option<u128> {
+        let spread = upper.into_inner().checked_sub(lower.into_inner())?;
+        mul_div(amount_b, FixedU128::accuracy(), spread, false)
+    };
+
+    if sqrt_price <= lower {
+        for_a(lower, upper)
+    } else if sqrt_price < upper {
+        Some(for_a(sqrt_price, upper)?.min(for_b(lower, sqrt_price)?))
+    } else {
+        for_b(lower, upper)
+    }
+}
+
+/// Amounts of (asset_a, asset_b) backing liquidity `L` over `[lower, upper]` at the current
+/// square root price
+pub fn amounts_for_liquidity(
+    sqrt_price: FixedU128,
+    lower: FixedU128,
+    upper: FixedU128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<(u128, u128)> {
+    if sqrt_price <= lower {
+        Some((amount_a_delta(lower, upper, liquidity, round_up)?, 0))
+    } else if sqrt_price < upper {
+        Some((
+            amount_a_delta(sqrt_price, upper, liquidity, round_up)?,
+            amount_b_delta(lower, sqrt_price, liquidity, round_up)?,
+        ))
+    } else {
+        Some((0, amount_b_delta(lower, upper, liquidity, round_up)?))
+    }
+}
+
+/// Square root price after adding `amount` of asset_a at liquidity `L`, which lowers the
+/// price: `L / (L / sqrt_price + amount)`, rounded up in the pool's favour
+fn sqrt_price_after_a_in(sqrt_price: FixedU128, liquidity: u128, amount: u128) -> Option<FixedU128> {
+    let virtual_a = mul_div(liquidity, FixedU128::accuracy(), sqrt_price.into_inner(), false)?;
+    let inner = mul_div(liquidity, FixedU128::accuracy(), virtual_a.checked_add(amount)?, true)?;
+    Some(FixedU128::from_inner(inner))
+}
+
+/// Square root price after adding `amount` of asset_b at liquidity `L`, which raises the
+/// price: `sqrt_price + amount / L`, rounded down in the pool's favour
+fn sqrt_price_after_b_in(sqrt_price: FixedU128, liquidity: u128, amount: u128) -> Option<FixedU128> {
+    let step = mul_div(amount, FixedU128::accuracy(), liquidity, false)?;
+    Some(FixedU128::from_inner(sqrt_price.into_inner().checked_add(step)?))
+}
+
+pub(crate) fn wrapping_add(a: FixedU128, b: FixedU128) -> FixedU128 {
+    FixedU128::from_inner(a.into_inner().wrapping_add(b.into_inner()))
+}
+
+pub(crate) fn wrapping_sub(a: FixedU128, b: FixedU128) -> FixedU128 {
+    FixedU128::from_inner(a.into_inner().wrapping_sub(b.into_inner()))
+}
+
+impl<T: Config> Pallet<T> {
+    /// Check that a position range lies on the pool's tick spacing and within the tick bounds
+    pub(crate) fn ensure_valid_range(tick_spacing: u32, tick_lower: i32, tick_upper: i32) -> DispatchResult {
+        let spacing = i32::try_from(tick_spacing).map_err(|_| Error::<T>::InvalidTickRange)?;
+        ensure!(spacing > 0, Error::<T>::InvalidTickRange);
+        ensure!(tick_lower < tick_upper, Error::<T>::InvalidTickRange);
+        ensure!(tick_lower >= MIN_TICK && tick_upper <= MAX_TICK, Error::<T>::InvalidTickRange);
+        ensure!(tick_lower % spacing == 0 && tick_upper % spacing == 0, Error::<T>::InvalidTickRange);
+        Ok(())
+    }
+
+    /// Fee growth per unit of liquidity earned inside `[tick_lower, tick_upper)`, for
+    /// (asset_a, asset_b)
+    pub(crate) fn fee_growth_inside(
+        pair: (T::AssetId, T::AssetId),
+        state: &ConcentratedPoolOf<T>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> (FixedU128, FixedU128) {
+        let lower = Ticks::<T>::get(pair, tick_lower).unwrap_or_default();
+        let upper = Ticks::<T>::get(pair, tick_upper).unwrap_or_default();
+
+        let inside = |global: FixedU128, lower_outside: FixedU128, upper_outside: FixedU128| {
+            let below = if state.tick >= tick_lower { lower_outside } else { wrapping_sub(global, lower_outside) };
+            let above = if state.tick < tick_upper { upper_outside } else { wrapping_sub(global, upper_outside) };
+            wrapping_sub(wrapping_sub(global, below), above)
+        };
+
+        (
+            inside(state.fee_growth_global_a, lower.fee_growth_outside_a, upper.fee_growth_outside_a),
+            inside(state.fee_growth_global_b, lower.fee_growth_outside_b, upper.fee_growth_outside_b),
+        )
+    }
+
+    /// Add or remove liquidity at one boundary tick of a position, initializing the tick and
+    /// reserving its deposit from `owner` when it gains its first liquidity. Returns whether
+    /// the tick is left without liquidity, in which case the caller clears it once fees are
+    /// settled.
+    fn update_tick(
+        pair: (T::AssetId, T::AssetId),
+        state: &ConcentratedPoolOf<T>,
+        owner: &T::AccountId,
+        tick: i32,
+        liquidity: BalanceOf<T>,
+        is_lower: bool,
+        is_add: bool,
+    ) -> Result<bool, DispatchError> {
+        let mut info = match Ticks::<T>::get(pair, tick) {
+            Some(info) => info,
+            None => {
+                // Growth below the current tick is attributed to the outside of a new tick
+                let mut info = TickInfo::default();
+                if tick <= state.tick {
+                    info.fee_growth_outside_a = state.fee_growth_global_a;
+                    info.fee_growth_outside_b = state.fee_growth_global_b;
+                }
+                InitializedTicks::<T>::try_mutate(pair, |ticks| -> DispatchResult {
+                    let index = ticks.binary_search(&tick).unwrap_or_else(|index| index);
+                    ticks.try_insert(index, tick).map_err(|_| Error::<T>::TooManyTicks)?;
+                    Ok(())
+                })?;
+                let deposit = T::TickDeposit::get();
+                T::Currency::reserve(owner, deposit)?;
+                TickDeposits::<T>::insert(pair, tick, (owner.clone(), deposit));
+                info
+            },
+        };
+
+        let apply = |value: BalanceOf<T>| -> Result<BalanceOf<T>, DispatchError> {
+            if is_add {
+                value.checked_add(&liquidity).ok_or_else(|| Error::<T>::CalculationError.into())
+            } else {
+                value.checked_sub(&liquidity).ok_or_else(|| Error::<T>::CalculationError.into())
+            }
+        };
+        info.liquidity_gross = apply(info.liquidity_gross)?;
+        if is_lower {
+            info.liquidity_lower = apply(info.liquidity_lower)?;
+        } else {
+            info.liquidity_upper = apply(info.liquidity_upper)?;
+        }
+
+        let cleared = info.liquidity_gross.is_zero();
+        Ticks::<T>::insert(pair, tick, info);
+        Ok(cleared)
+    }
+
+    /// Remove a tick that no longer bounds any position and return its deposit
+    fn clear_tick(pair: (T::AssetId, T::AssetId), tick: i32) {
+        Ticks::<T>::remove(pair, tick);
+        InitializedTicks::<T>::mutate(pair, |ticks| ticks.retain(|t| *t != tick));
+        if let Some((depositor, deposit)) = TickDeposits::<T>::take(pair, tick) {
+            T::Currency::unreserve(&depositor, deposit);
+        }
+    }
+
+    /// Settle a position's fees and apply a liquidity change to it, its boundary ticks and
+    /// the pool's active liquidity. A zero `liquidity` only settles fees.
+    pub(crate) fn modify_position(
+        position: &mut PositionOf<T>,
+        state: &mut ConcentratedPoolOf<T>,
+        liquidity: BalanceOf<T>,
+        is_add: bool,
+    ) -> DispatchResult {
+        let pair = position.pool;
+        let (mut clear_lower, mut clear_upper) = (false, false);
+        if !liquidity.is_zero() {
+            let owner = &position.owner;
+            clear_lower = Self::update_tick(pair, state, owner, position.tick_lower, liquidity, true, is_add)?;
+            clear_upper = Self::update_tick(pair, state, owner, position.tick_upper, liquidity, false, is_add)?;
+
+            if position.tick_lower <= state.tick && state.tick < position.tick_upper {
+                state.liquidity = if is_add {
+                    state.liquidity.checked_add(&liquidity)
+                } else {
+                    state.liquidity.checked_sub(&liquidity)
+                }
+                .ok_or(Error::<T>::CalculationError)?;
+            }
+        }
+
+        // Credit the fees earned by the old liquidity since the last settlement
+        let (inside_a, inside_b) = Self::fee_growth_inside(pair, state, position.tick_lower, position.tick_upper);
+        let held = position.liquidity.saturated_into::<u128>();
+        let earned_a = wrapping_sub(inside_a, position.fee_growth_inside_a_last).saturating_mul_int(held);
+        let earned_b = wrapping_sub(inside_b, position.fee_growth_inside_b_last).saturating_mul_int(held);
+        position.tokens_owed_a = position.tokens_owed_a.saturating_add(earned_a.saturated_into());
+        position.tokens_owed_b = position.tokens_owed_b.saturating_add(earned_b.saturated_into());
+        position.fee_growth_inside_a_last = inside_a;
+        position.fee_growth_inside_b_last = inside_b;
+
+        position.liquidity = if is_add {
+            position.liquidity.checked_add(&liquidity)
+        } else {
+            position.liquidity.checked_sub(&liquidity)
+        }
+        .ok_or(Error::<T>::CalculationError)?;
+
+        if clear_lower {
+            Self::clear_tick(pair, position.tick_lower);
+        }
+        if clear_upper {
+            Self::clear_tick(pair, position.tick_upper);
+        }
+        ConcentratedPools::<T>::insert(pair, state.clone());
+        Ok(())
+    }
+
+    /// Run an exact-input swap through a concentrated pool, crossing initialized ticks as the
+    /// price moves. With `commit` unset this only quotes; otherwise the pool state, crossed
+    /// ticks, reserves and protocol fees are written.
+    pub(crate) fn concentrated_swap(
+        pair: (T::AssetId, T::AssetId),
+        fee_tier: Perquintill,
+        asset_in: T::AssetId,
+        amount_in: BalanceOf<T>,
+        commit: bool,
+    ) -> Result<ConcentratedSwap<BalanceOf<T>>, DispatchError> {
+        let mut state = ConcentratedPools::<T>::get(pair).ok_or(Error::<T>::PoolDoesNotExist)?;
+        let ticks = InitializedTicks::<T>::get(pair);
+        // Selling asset_a moves the price down, selling asset_b moves it up
+        let a_for_b = asset_in == pair.0;
+
+        let mut remaining = amount_in.saturated_into::<u128>();
+        let mut amount_out = 0u128;
+        let mut amount_to_reserve = 0u128;
+        let mut fee_amount = 0u128;
+        let mut protocol_fee = 0u128;
+        let mut ticks_crossed = 0u32;
+
+        while remaining > 0 {
+            let next_tick = if a_for_b {
+                ticks.iter().rev().find(|tick| **tick <= state.tick).copied()
+            } else {
+                ticks.iter().find(|tick| **tick > state.tick).copied()
+            };
+            let target = sqrt_price_at_tick(next_tick.unwrap_or(if a_for_b { MIN_TICK } else { MAX_TICK }));
+            let liquidity = state.liquidity.saturated_into::<u128>();
+
+            let (next_sqrt_price, reached) = if liquidity.is_zero() {
+                // Nothing to trade against in this range, skip straight to the next tick
+                (target, true)
+            } else {
+                // The fee is charged on the part of the input the step actually uses
+                let remaining_less_fee = remaining.saturating_sub(fee_tier.mul_ceil(remaining));
+                let to_target = if a_for_b {
+                    amount_a_delta(target, state.sqrt_price, liquidity, true)
+                } else {
+                    amount_b_delta(state.sqrt_price, target, liquidity, true)
+                }
+                .ok_or(Error::<T>::CalculationError)?;
+
+                let (step_in, next_sqrt_price, reached) = if remaining_less_fee >= to_target {
+                    (to_target, target, true)
+                } else {
+                    let next = if a_for_b {
+                        sqrt_price_after_a_in(state.sqrt_price, liquidity, remaining_less_fee)
+                    } else {
+                        sqrt_price_after_b_in(state.sqrt_price, liquidity, remaining_less_fee)
+                    }
+                    .ok_or(Error::<T>::CalculationError)?;
+                    (remaining_less_fee, next, false)
+                };
+
+                let step_fee = if reached {
+                    let gross = fee_tier.left_from_one().saturating_reciprocal_mul_ceil(step_in);
+                    gross.saturating_sub(step_in).min(remaining.saturating_sub(step_in))
+                } else {
+                    remaining.saturating_sub(step_in)
+                };
+                let step_out = if a_for_b {
+                    amount_b_delta(next_sqrt_price, state.sqrt_price, liquidity, false)
+                } else {
+                    amount_a_delta(state.sqrt_price, next_sqrt_price, liquidity, false)
+                }
+                .ok_or(Error::<T>::CalculationError)?;
+
+                // The protocol's share is set aside, the rest accrues to in-range liquidity
+                let step_protocol_fee = T::ProtocolFeeShare::get().mul_floor(step_fee);
+                let growth = FixedU128::checked_from_rational(step_fee.saturating_sub(step_protocol_fee), liquidity)
+                    .ok_or(Error::<T>::CalculationError)?;
+                if a_for_b {
+                    state.fee_growth_global_a = wrapping_add(state.fee_growth_global_a, growth);
+                } else {
+                    state.fee_growth_global_b = wrapping_add(state.fee_growth_global_b, growth);
+                }
+
+                remaining = remaining.saturating_sub(step_in.saturating_add(step_fee));
+                amount_out = amount_out.checked_add(step_out).ok_or(Error::<T>::CalculationError)?;
+                amount_to_reserve = amount_to_reserve.saturating_add(step_in);
+                fee_amount = fee_amount.saturating_add(step_fee);
+                protocol_fee = protocol_fee.saturating_add(step_protocol_fee);
+                (next_sqrt_price, reached)
+            };
+
+            state.sqrt_price = next_sqrt_price;
+            if !reached {
+                state.tick = tick_at_sqrt_price(next_sqrt_price);
+                continue;
+            }
+
+            // Cross the tick at the end of the range, or stop at the price bounds
+            let tick = match next_tick {
+                Some(tick) => tick,
+                None => break,
+            };
+            ticks_crossed = ticks_crossed.saturating_add(1);
+            let mut info = Ticks::<T>::get(pair, tick).unwrap_or_default();
+            info.fee_growth_outside_a = wrapping_sub(state.fee_growth_global_a, info.fee_growth_outside_a);
+            info.fee_growth_outside_b = wrapping_sub(state.fee_growth_global_b, info.fee_growth_outside_b);
+            state.liquidity = if a_for_b {
+                state.liquidity.saturating_sub(info.liquidity_lower).saturating_add(info.liquidity_upper)
+            } else {
+                state.liquidity.saturating_add(info.liquidity_lower).saturating_sub(info.liquidity_upper)
+            };
+            state.tick = if a_for_b { tick.saturating_sub(1) } else { tick };
+            if commit {
+                Ticks::<T>::insert(pair, tick, info);
+            }
+        }
+
+        // Partial fills are not supported: the whole input has to find liquidity
+        ensure!(remaining.is_zero() && amount_out > 0, Error::<T>::InsufficientLiquidity);
+
+        let amount_out = Self::to_balance(amount_out)?;
+        let fee_amount = Self::to_balance(fee_amount)?;
+        if commit {
+            let amount_to_reserve = Self::to_balance(amount_to_reserve)?;
+            let protocol_fee = Self::to_balance(protocol_fee)?;
+            if !protocol_fee.is_zero() {
+                ProtocolFees::<T>::mutate(pair, asset_in, |accrued| {
+                    *accrued = accrued.saturating_add(protocol_fee);
+                });
+            }
+            LiquidityPools::<T>::try_mutate(pair, |reserves| -> DispatchResult {
+                let (reserve_in, reserve_out) = if a_for_b {
+                    (&mut reserves.0, &mut reserves.1)
+                } else {
+                    (&mut reserves.1, &mut reserves.0)
+                };
+                *reserve_in = reserve_in.checked_add(&amount_to_reserve).ok_or(Error::<T>::CalculationError)?;
+                *reserve_out = reserve_out.checked_sub(&amount_out).ok_or(Error::<T>::InsufficientLiquidity)?;
+                Ok(())
+            })?;
+            ConcentratedPools::<T>::insert(pair, state);
+        }
+
+        Ok(ConcentratedSwap { amount_out, fee_amount, ticks_crossed })
+    }
+
+    /// Convert an amount computed in `u128` back into the balance type
+    pub(crate) fn to_balance(amount: u128) -> Result<BalanceOf<T>, DispatchError> {
+        BalanceOf::<T>::try_from(amount).map_err(|_| Error::<T>::CalculationError.into())
+    }
+}
@@ -0,0 +1,111 @@
+//! Transaction validity checks for `pallet-liquidity-pool`.
+//!
+//! `CheckSwapDeadline` rejects trades and liquidity changes whose `deadline` has already
+//! passed while they are still in the transaction pool, so a held-back transaction is
+//! dropped instead of being included at a stale price, and without paying for block weight.
+//! Valid transactions get a longevity that ends at their deadline.
+//!
+//! Every call that trades against the current reserves takes a deadline: direct, routed and
+//! StableSwap swaps, liquidity changes including the single-asset zaps, and `place_order`,
+//! whose `expires_at` doubles as its deadline. Concentrated liquidity positions and flash
+//! swaps are bounded by their own minimum amounts and repayment checks instead.
+
+use crate::{Call, Config};
+use codec::{Decode, Encode};
+use frame_support::traits::IsSubType;
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{DispatchInfoOf, SaturatedConversion, Saturating, SignedExtension},
+    transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction},
+};
+use sp_std::{fmt, marker::PhantomData};
+
+/// Reject liquidity pool calls whose `deadline` has passed before they are included
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckSwapDeadline<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckSwapDeadline<T> {
+    /// Create a new `CheckSwapDeadline` extension
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// The deadline a call carries, if it is one of the calls that take one
+    fn deadline(call: &<T as frame_system::Config>::RuntimeCall) -> Option<BlockNumberFor<T>>
+    where
+        <T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+    {
+        match call.is_sub_type()? {
+            Call::swap { deadline, .. } |
+            Call::swap_exact_tokens_for_tokens { deadline, .. } |
+            Call::swap_tokens_for_exact_tokens { deadline, .. } |
+            Call::stable_swap { deadline, .. } |
+            Call::add_liquidity { deadline, .. } |
+            Call::remove_liquidity { deadline, .. } |
+            Call::add_liquidity_single_asset { deadline, .. } |
+            Call::remove_liquidity_single_asset { deadline, .. } => *deadline,
+            // An order that has expired can no longer be placed, so it leaves the pool too
+            Call::place_order { expires_at, .. } => *expires_at,
+            _ => None,
+        }
+    }
+}
+
+impl<T: Config + Send + Sync> Default for CheckSwapDeadline<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckSwapDeadline<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CheckSwapDeadline")
+    }
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckSwapDeadline<T>
+where
+    <T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+    const IDENTIFIER: &'static str = "CheckSwapDeadline";
+    type AccountId = T::AccountId;
+    type Call = <T as frame_system::Config>::RuntimeCall;
+    type AdditionalSigned = ();
+    type Pre = ();
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        _who: &Self::AccountId,
+        call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> TransactionValidity {
+        let deadline = match Self::deadline(call) {
+            Some(deadline) => deadline,
+            None => return Ok(ValidTransaction::default()),
+        };
+        let now = frame_system::Pallet::<T>::block_number();
+        if now > deadline {
+            return Err(InvalidTransaction::Stale.into())
+        }
+        // Drop the transaction from the pool once the deadline block has been built
+        let longevity = deadline.saturating_sub(now).saturating_add(1u32.into()).saturated_into::<u64>();
+        Ok(ValidTransaction { longevity, ..Default::default() })
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        self.validate(who, call, info, len).map(|_| ())
+    }
+}
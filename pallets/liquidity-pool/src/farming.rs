@@ -0,0 +1,123 @@
+//! Liquidity mining for `pallet-liquidity-pool`.
+//!
+//! Every pool can run a farm: LP shares staked in it earn the reward assets governance
+//! schedules for the pool, pro rata by stake. Each schedule keeps a reward-per-share
+//! accumulator that grows by `reward_per_block * blocks / total_staked` whenever the farm is
+//! touched, and a staker's earnings are their stake times the growth since they last settled.
+//! Stakers settle before every change to their stake, so a stake only earns for the blocks
+//! it was actually staked. Blocks in which nothing is staked emit nothing; their rewards stay
+//! in the farm account.
+
+use super::*;
+use frame_support::{
+    pallet_prelude::{DispatchError, DispatchResult},
+    sp_runtime::{
+        traits::{AccountIdConversion, CheckedAdd, Saturating},
+        FixedPointNumber, FixedU128, SaturatedConversion,
+    },
+    traits::Get,
+    BoundedVec,
+};
+use sp_std::prelude::*;
+
+impl<T: Config> Pallet<T> {
+    /// The account holding staked LP shares and the rewards farms pay out. Governance funds
+    /// reward schedules by transferring the reward assets here.
+    pub fn farm_account_id() -> T::AccountId {
+        T::PalletId::get().into_sub_account_truncating(b"farm")
+    }
+
+    /// Bring every schedule of a farm up to `now`
+    pub(crate) fn accrue_farm(
+        pair: (T::AssetId, T::AssetId),
+        schedules: &mut BoundedVec<RewardScheduleOf<T>, T::MaxRewardAssets>,
+        now: T::BlockNumber,
+    ) -> DispatchResult {
+        let total_staked = TotalStaked::<T>::get(pair).saturated_into::<u128>();
+        for schedule in schedules.iter_mut() {
+            let from = schedule.last_update.max(schedule.start);
+            let to = now.min(schedule.end);
+            if to > from && total_staked > 0 {
+                let blocks = to.saturating_sub(from).saturated_into::<u128>();
+                let emitted = schedule
+                    .reward_per_block
+                    .saturated_into::<u128>()
+                    .checked_mul(blocks)
+                    .ok_or(Error::<T>::CalculationError)?;
+                let per_share =
+                    FixedU128::checked_from_rational(emitted, total_staked).ok_or(Error::<T>::CalculationError)?;
+                schedule.reward_per_share = schedule
+                    .reward_per_share
+                    .checked_add(&per_share)
+                    .ok_or(Error::<T>::CalculationError)?;
+            }
+            schedule.last_update = schedule.last_update.max(now);
+        }
+        Ok(())
+    }
+
+    /// Unclaimed reward of a staker in one schedule, including what is not settled yet
+    fn earned(
+        schedule: &RewardScheduleOf<T>,
+        reward: &StakerReward<BalanceOf<T>>,
+        staked: BalanceOf<T>,
+    ) -> Result<BalanceOf<T>, DispatchError> {
+        let earned = schedule
+            .reward_per_share
+            .saturating_sub(reward.paid_per_share)
+            .saturating_mul_int(staked.saturated_into::<u128>());
+        reward
+            .pending
+            .checked_add(&Self::to_balance(earned)?)
+            .ok_or_else(|| Error::<T>::CalculationError.into())
+    }
+
+    /// Accrue a farm and move everything `who` earned so far into their pending rewards.
+    /// Returns the farm's schedules as of now.
+    pub(crate) fn settle_staker(
+        pair: (T::AssetId, T::AssetId),
+        who: &T::AccountId,
+    ) -> Result<BoundedVec<RewardScheduleOf<T>, T::MaxRewardAssets>, DispatchError> {
+        let mut schedules = Farms::<T>::get(pair);
+        if schedules.is_empty() {
+            return Ok(schedules);
+        }
+        Self::accrue_farm(pair, &mut schedules, frame_system::Pallet::<T>::block_number())?;
+
+        let staked = Stakes::<T>::get(pair, who);
+        for schedule in schedules.iter() {
+            StakerRewards::<T>::try_mutate((pair, who.clone(), schedule.reward_asset), |reward| -> DispatchResult {
+                reward.pending = Self::earned(schedule, reward, staked)?;
+                reward.paid_per_share = schedule.reward_per_share;
+                Ok(())
+            })?;
+        }
+        Farms::<T>::insert(pair, &schedules);
+        Ok(schedules)
+    }
+
+    /// Rewards `who` could claim from the farm of `(asset_a, asset_b)` right now
+    pub fn pending_rewards(
+        who: &T::AccountId,
+        asset_a: T::AssetId,
+        asset_b: T::AssetId,
+    ) -> Vec<(T::AssetId, BalanceOf<T>)> {
+        let pair = match Self::resolve_pool(asset_a, asset_b) {
+            Ok((pair, _)) => pair,
+            Err(_) => return Vec::new(),
+        };
+        let mut schedules = Farms::<T>::get(pair);
+        if Self::accrue_farm(pair, &mut schedules, frame_system::Pallet::<T>::block_number()).is_err() {
+            return Vec::new();
+        }
+
+        let staked = Stakes::<T>::get(pair, who);
+        schedules
+            .iter()
+            .filter_map(|schedule| {
+                let reward = StakerRewards::<T>::get((pair, who.clone(), schedule.reward_asset));
+                Self::earned(schedule, &reward, staked).ok().map(|amount| (schedule.reward_asset, amount))
+            })
+            .collect()
+    }
+}
@@ -2,20 +2,64 @@
 
 pub use pallet::*;
 
+pub mod concentrated;
+pub mod extension;
+pub mod farming;
+pub mod math;
+pub mod migration;
+pub mod orders;
+pub mod stableswap;
+pub mod stats;
+pub mod weights;
+pub use extension::CheckSwapDeadline;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use sp_runtime::FixedU128;
+
+/// Manipulation-resistant pool prices for other pallets and contracts
+pub trait PriceOracle<AssetId, BlockNumber> {
+    /// Time-weighted average price of `asset_in` in units of `asset_out` over at least
+    /// the last `window` blocks, or `None` if there is not enough history
+    fn consult(asset_in: AssetId, asset_out: AssetId, window: BlockNumber) -> Option<FixedU128>;
+}
+
+impl<T: Config> PriceOracle<T::AssetId, T::BlockNumber> for Pallet<T> {
+    fn consult(asset_in: T::AssetId, asset_out: T::AssetId, window: T::BlockNumber) -> Option<FixedU128> {
+        Pallet::<T>::consult(asset_in, asset_out, window)
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
     use frame_support::{
-        dispatch::DispatchResultWithPostInfo,
+        dispatch::{DispatchResultWithPostInfo, GetDispatchInfo, PostDispatchInfo},
         pallet_prelude::*,
-        traits::{Currency, ReservableCurrency, Get, ExistenceRequirement},
-        Parameter, 
+        traits::{
+            fungibles::{
+                self, Create as FungiblesCreate, Inspect as FungiblesInspect, Mutate as FungiblesMutate,
+                Transfer as FungiblesTransfer,
+            },
+            Currency, ReservableCurrency, Get, ExistenceRequirement,
+        },
+        Parameter, PalletId,
         sp_runtime::{
-            traits::{AtLeast32BitUnsigned, CheckedDiv, CheckedMul, MaybeSerializeDeserialize, Member, Zero},
-            FixedPointNumber, FixedU128, Perquintill,
+            traits::{AccountIdConversion, AtLeast32BitUnsigned, CheckedAdd, CheckedDiv, CheckedSub, Dispatchable, MaybeSerializeDeserialize, Member, One, Saturating, Zero},
+            FixedPointNumber, FixedU128, PerThing, Perbill, Perquintill, SaturatedConversion,
         },
     };
+    use sp_core::U256;
     use frame_system::pallet_prelude::*;
     use sp_std::prelude::*;
+    use crate::{concentrated, math, weights::WeightInfo};
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
@@ -23,31 +67,394 @@ pub mod pallet {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         
-        /// The currency type this pallet will be using
+        /// The runtime's call type, dispatched as the callback of a flash swap
+        type RuntimeCall: Parameter
+            + Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>
+            + GetDispatchInfo
+            + From<frame_system::Call<Self>>;
+        
+        /// The native currency, used whenever an asset id equals `NativeAssetId`
         type Currency: ReservableCurrency<Self::AccountId>;
         
         /// Asset ID type
-        type AssetId: Parameter + Member + Copy + MaybeSerializeDeserialize + Ord + Default + AtLeast32BitUnsigned;
+        type AssetId: Parameter + Member + Copy + MaybeSerializeDeserialize + MaxEncodedLen + Ord + Default + AtLeast32BitUnsigned;
+        
+        /// The assets provider holding every non-native pooled asset and the pools' LP assets
+        type Assets: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = BalanceOf<Self>>
+            + fungibles::Create<Self::AccountId>
+            + fungibles::Mutate<Self::AccountId>
+            + fungibles::Transfer<Self::AccountId>;
+        
+        /// The asset id that stands for the native currency
+        #[pallet::constant]
+        type NativeAssetId: Get<Self::AssetId>;
+        
+        /// The pallet id, used to derive the account holding all pool reserves
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
         
-        /// The custom fee applied to all trades (0.369%)
+        /// The custom fee applied to all trades (0.369%), used as the fee tier of new pools
         #[pallet::constant]
         type TradingFee: Get<Perquintill>;
         
+        /// The first asset id handed out to pool LP assets. Ids from here upwards are created
+        /// by this pallet and must not be used for any other asset.
+        #[pallet::constant]
+        type LpAssetIdStart: Get<Self::AssetId>;
+        
         /// The minimum liquidity required for a new pool
         #[pallet::constant]
         type MinimumLiquidity: Get<BalanceOf<Self>>;
+        
+        /// The maximum number of assets in a routed swap path (hops + 1)
+        #[pallet::constant]
+        type MaxSwapPathLength: Get<u32>;
+        
+        /// The share of every trading fee set aside for the protocol instead of LPs
+        #[pallet::constant]
+        type ProtocolFeeShare: Get<Perbill>;
+        
+        /// The account that claimed protocol fees are paid to, e.g. the treasury account
+        type FeeDestination: Get<Self::AccountId>;
+        
+        /// The maximum number of price observations kept per pool for TWAP queries
+        #[pallet::constant]
+        type MaxPriceObservations: Get<u32>;
+        
+        /// The maximum number of initialized ticks per concentrated liquidity pool
+        #[pallet::constant]
+        type MaxTicks: Get<u32>;
+        
+        /// Native currency reserved from the account whose position initializes a tick of a
+        /// concentrated liquidity pool, returned once no position is bounded by the tick
+        #[pallet::constant]
+        type TickDeposit: Get<BalanceOf<Self>>;
+        
+        /// Origin allowed to create StableSwap pools, ramp their amplification, pause pools,
+        /// set pool fees, allow or deny assets and release LP shares held by the migration
+        type PoolAdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        
+        /// The highest amplification coefficient a StableSwap pool may use
+        #[pallet::constant]
+        type MaxAmplification: Get<u32>;
+        
+        /// The shortest time over which a StableSwap amplification ramp may run
+        #[pallet::constant]
+        type MinRampDuration: Get<Self::BlockNumber>;
+        
+        /// The lowest fee tier `PoolAdminOrigin` may set on a pool
+        #[pallet::constant]
+        type MinPoolFee: Get<Perquintill>;
+        
+        /// The highest fee tier `PoolAdminOrigin` may set on a pool
+        #[pallet::constant]
+        type MaxPoolFee: Get<Perquintill>;
+        
+        /// The maximum number of reward assets a pool's farm can emit at once
+        #[pallet::constant]
+        type MaxRewardAssets: Get<u32>;
+        
+        /// Native currency reserved from the owner of every resting limit order until it is
+        /// filled, cancelled or expires
+        #[pallet::constant]
+        type OrderDeposit: Get<BalanceOf<Self>>;
+        
+        /// The maximum number of resting limit orders per pool
+        #[pallet::constant]
+        type MaxOrdersPerPool: Get<u32>;
+        
+        /// The number of blocks over which pool volume and fees are aggregated
+        #[pallet::constant]
+        type StatsEpochLength: Get<Self::BlockNumber>;
+        
+        /// The number of most recent epochs whose aggregates are kept per pool
+        #[pallet::constant]
+        type MaxStatsEpochs: Get<u32>;
+        
+        /// Weight information for extrinsics in this pallet
+        type WeightInfo: WeightInfo;
+        
+        /// Provides asset ids for benchmarks
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper<Self::AssetId>;
+    }
+
+    /// Benchmark setup hooks that depend on the runtime
+    #[cfg(feature = "runtime-benchmarks")]
+    pub trait BenchmarkHelper<AssetId> {
+        /// The `i`-th pooled asset id used by benchmarks. Must not be the native asset id
+        /// and must not overlap the LP asset id range.
+        fn asset_id(i: u32) -> AssetId;
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    impl<AssetId: From<u32>> BenchmarkHelper<AssetId> for () {
+        fn asset_id(i: u32) -> AssetId {
+            (i + 1).into()
+        }
     }
 
     pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
     
+    /// Route of a multi-hop swap, from the asset paid in to the asset paid out
+    pub type SwapPathOf<T> = BoundedVec<<T as Config>::AssetId, <T as Config>::MaxSwapPathLength>;
+    
     /// Nourish Chain Trading Fee set to 0.369%
     pub const NOURISH_CHAIN_FEE: Perquintill = Perquintill::from_parts(3_690_000_000_000_000); // 0.369%
 
+    /// Registry record of a pool, keyed by its canonical (sorted) asset pair
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct PoolInfo<AccountId, BlockNumber, AssetId> {
+        /// Account that created the pool
+        pub creator: AccountId,
+        /// Block at which the pool was created
+        pub created_at: BlockNumber,
+        /// Fee charged on every trade through the pool
+        pub fee_tier: Perquintill,
+        /// Asset id of the pool's LP share token
+        pub lp_asset: AssetId,
+    }
+
+    /// Uniswap-v2-style cumulative prices of a pool. Each price is summed once per block,
+    /// weighted by the number of blocks it held, before the reserves change. The sums wrap
+    /// on overflow; only differences between two readings are meaningful.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+    pub struct PriceCumulative<BlockNumber> {
+        /// Running sum of reserve_b / reserve_a, the price of asset_a in asset_b
+        pub price_a_cumulative: FixedU128,
+        /// Running sum of reserve_a / reserve_b, the price of asset_b in asset_a
+        pub price_b_cumulative: FixedU128,
+        /// Block at which the sums were last advanced
+        pub last_update: BlockNumber,
+    }
+
+    /// Price state of a concentrated liquidity pool
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct ConcentratedPool<Balance> {
+        /// Square root of the price of asset_a in asset_b
+        pub sqrt_price: FixedU128,
+        /// The highest tick at or below the current price
+        pub tick: i32,
+        /// Spacing that every position's boundary ticks must be a multiple of
+        pub tick_spacing: u32,
+        /// Liquidity of the positions whose range contains the current price
+        pub liquidity: Balance,
+        /// Fees in asset_a earned per unit of liquidity over the pool's lifetime
+        pub fee_growth_global_a: FixedU128,
+        /// Fees in asset_b earned per unit of liquidity over the pool's lifetime
+        pub fee_growth_global_b: FixedU128,
+    }
+
+    /// An initialized tick of a concentrated liquidity pool
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+    pub struct TickInfo<Balance> {
+        /// Liquidity of all positions bounded by this tick
+        pub liquidity_gross: Balance,
+        /// Liquidity of the positions starting at this tick, activated when it is crossed upwards
+        pub liquidity_lower: Balance,
+        /// Liquidity of the positions ending at this tick, deactivated when it is crossed upwards
+        pub liquidity_upper: Balance,
+        /// Fee growth in asset_a on the other side of this tick from the current price
+        pub fee_growth_outside_a: FixedU128,
+        /// Fee growth in asset_b on the other side of this tick from the current price
+        pub fee_growth_outside_b: FixedU128,
+    }
+
+    /// A range position in a concentrated liquidity pool
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct Position<AccountId, AssetId, Balance> {
+        /// Account that owns the position
+        pub owner: AccountId,
+        /// Canonical pair of the pool the position belongs to
+        pub pool: (AssetId, AssetId),
+        /// Lower boundary tick, inclusive
+        pub tick_lower: i32,
+        /// Upper boundary tick, exclusive
+        pub tick_upper: i32,
+        /// Liquidity provided over the range
+        pub liquidity: Balance,
+        /// Fee growth inside the range in asset_a when the position last settled
+        pub fee_growth_inside_a_last: FixedU128,
+        /// Fee growth inside the range in asset_b when the position last settled
+        pub fee_growth_inside_b_last: FixedU128,
+        /// Settled asset_a fees not yet collected
+        pub tokens_owed_a: Balance,
+        /// Settled asset_b fees not yet collected
+        pub tokens_owed_b: Balance,
+    }
+
+    /// Identifier of a concentrated liquidity position
+    pub type PositionId = u64;
+
+    /// Identifier of a StableSwap pool
+    pub type StablePoolId = u32;
+
+    /// The most assets a StableSwap pool can hold
+    pub const MAX_STABLE_ASSETS: u32 = 4;
+
+    /// Amplification coefficient of a StableSwap pool, moving linearly from `initial_a` at
+    /// `initial_block` to `future_a` at `future_block`
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct AmplificationRamp<BlockNumber> {
+        pub initial_a: u32,
+        pub future_a: u32,
+        pub initial_block: BlockNumber,
+        pub future_block: BlockNumber,
+    }
+
+    /// A StableSwap pool of 2 to `MAX_STABLE_ASSETS` pegged assets
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct StablePool<AssetId, Balance, BlockNumber> {
+        /// The pooled assets, sorted and distinct
+        pub assets: BoundedVec<AssetId, ConstU32<MAX_STABLE_ASSETS>>,
+        /// Reserve of each asset, in the order of `assets`
+        pub reserves: BoundedVec<Balance, ConstU32<MAX_STABLE_ASSETS>>,
+        /// Fee charged on every trade and on unbalanced deposits
+        pub fee_tier: Perquintill,
+        /// Asset id of the pool's LP share token
+        pub lp_asset: AssetId,
+        /// Amplification coefficient and any ramp in progress
+        pub amplification: AmplificationRamp<BlockNumber>,
+    }
+
+    /// Emission of one reward asset to the LP shares staked in a pool's farm:
+    /// `reward_per_block` from `start` until `end`, shared pro rata by stake
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct RewardSchedule<AssetId, Balance, BlockNumber> {
+        /// Asset paid out as reward
+        pub reward_asset: AssetId,
+        /// Reward emitted per block while the schedule runs
+        pub reward_per_block: Balance,
+        /// First block of the emission
+        pub start: BlockNumber,
+        /// Block the emission ends at, exclusive
+        pub end: BlockNumber,
+        /// Reward emitted per staked LP share since the farm started
+        pub reward_per_share: FixedU128,
+        /// Block `reward_per_share` was last brought up to date
+        pub last_update: BlockNumber,
+    }
+
+    /// A staker's settled position in one reward asset of a farm
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+    pub struct StakerReward<Balance> {
+        /// `reward_per_share` of the schedule when the staker last settled
+        pub paid_per_share: FixedU128,
+        /// Settled reward not yet claimed
+        pub pending: Balance,
+    }
+
+    /// Trading activity of a pool over one epoch of `StatsEpochLength` blocks. Volume counts
+    /// every unit moved into or out of the pool by a trade, so a trade from asset_a to asset_b
+    /// adds its input to `volume_a` and its output to `volume_b`.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+    pub struct EpochStats<Balance> {
+        /// The epoch, i.e. the block number divided by `StatsEpochLength`
+        pub epoch: u32,
+        /// asset_a traded into or out of the pool
+        pub volume_a: Balance,
+        /// asset_b traded into or out of the pool
+        pub volume_b: Balance,
+        /// Trading fees charged in asset_a, the protocol's share included
+        pub fees_a: Balance,
+        /// Trading fees charged in asset_b, the protocol's share included
+        pub fees_b: Balance,
+        /// Number of trades, counting every hop of a routed swap
+        pub trades: u32,
+    }
+
+    /// Identifier of a limit order
+    pub type OrderId = u64;
+
+    /// The condition under which a resting order fills
+    #[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub enum OrderKind {
+        /// Fill as soon as the pool pays at least `min_amount_out` for `amount_in`
+        Limit,
+        /// Fill once the spot price of `asset_in` in `asset_out` falls to or below the trigger
+        /// price, as long as the pool still pays at least `min_amount_out`
+        StopLoss(FixedU128),
+    }
+
+    /// A resting order selling `amount_in` of `asset_in`, held in escrow until it fills
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct Order<AccountId, AssetId, Balance, BlockNumber> {
+        /// Account that placed the order and receives its proceeds
+        pub owner: AccountId,
+        /// Asset sold
+        pub asset_in: AssetId,
+        /// Asset bought
+        pub asset_out: AssetId,
+        /// Amount of `asset_in` sold, all at once
+        pub amount_in: Balance,
+        /// The least amount of `asset_out` the order accepts
+        pub min_amount_out: Balance,
+        /// When the order fills
+        pub kind: OrderKind,
+        /// Last block the order may fill in, if any
+        pub expires_at: Option<BlockNumber>,
+        /// Native currency reserved from the owner while the order rests
+        pub deposit: Balance,
+    }
+
+    pub type OrderOf<T> = Order<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::AssetId,
+        BalanceOf<T>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+
+    pub type RewardScheduleOf<T> = RewardSchedule<
+        <T as Config>::AssetId,
+        BalanceOf<T>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+
+    pub type StablePoolOf<T> = StablePool<
+        <T as Config>::AssetId,
+        BalanceOf<T>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+
+    pub type ConcentratedPoolOf<T> = ConcentratedPool<BalanceOf<T>>;
+
+    pub type PositionOf<T> = Position<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::AssetId,
+        BalanceOf<T>,
+    >;
+
+    pub type PoolInfoOf<T> = PoolInfo<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::BlockNumber,
+        <T as Config>::AssetId,
+    >;
+
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
-    /// Storage for liquidity pools. Maps (asset_a, asset_b) -> (reserve_a, reserve_b)
+    /// Registered pools. Maps canonical (asset_a, asset_b) with asset_a < asset_b -> PoolInfo
+    #[pallet::storage]
+    #[pallet::getter(fn pools)]
+    pub type Pools<T: Config> = StorageMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        PoolInfoOf<T>, 
+        OptionQuery
+    >;
+
+    /// The asset id that the next created pool will use for its LP asset
+    #[pallet::storage]
+    #[pallet::getter(fn next_lp_asset_id)]
+    pub type NextLpAssetId<T: Config> = StorageValue<_, T::AssetId, OptionQuery>;
+
+    /// Storage for liquidity pools. Maps canonical (asset_a, asset_b) -> (reserve_a, reserve_b)
     #[pallet::storage]
     #[pallet::getter(fn liquidity_pools)]
     pub type LiquidityPools<T: Config> = StorageMap<
@@ -58,18 +465,43 @@ pub mod pallet {
         ValueQuery
     >;
 
-    /// Storage for liquidity tokens. Maps (asset_a, asset_b, account) -> liquidity_tokens
+    /// Protocol fees accrued per pool and not yet claimed. Maps (pool, asset) -> amount.
+    /// These are held by the pallet account but are not part of the pool reserves.
     #[pallet::storage]
-    #[pallet::getter(fn liquidity_tokens)]
-    pub type LiquidityTokens<T: Config> = StorageMap<
+    #[pallet::getter(fn protocol_fees)]
+    pub type ProtocolFees<T: Config> = StorageDoubleMap<
         _, 
         Blake2_128Concat, 
-        (T::AssetId, T::AssetId, T::AccountId), 
+        (T::AssetId, T::AssetId), 
+        Blake2_128Concat, 
+        T::AssetId, 
         BalanceOf<T>, 
         ValueQuery
     >;
 
-    /// Total supply of liquidity tokens for each pool
+    /// Cumulative prices of each pool, advanced on the first reserve change of every block
+    #[pallet::storage]
+    #[pallet::getter(fn price_cumulatives)]
+    pub type PriceCumulatives<T: Config> = StorageMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        PriceCumulative<T::BlockNumber>, 
+        OptionQuery
+    >;
+
+    /// Recent cumulative price snapshots of each pool, oldest first, at most one per block
+    #[pallet::storage]
+    #[pallet::getter(fn price_observations)]
+    pub type PriceObservations<T: Config> = StorageMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        BoundedVec<PriceCumulative<T::BlockNumber>, T::MaxPriceObservations>, 
+        ValueQuery
+    >;
+
+    /// Total supply of each pool's LP asset, kept equal to the asset's total issuance
     #[pallet::storage]
     #[pallet::getter(fn total_liquidity)]
     pub type TotalLiquidity<T: Config> = StorageMap<
@@ -80,20 +512,352 @@ pub mod pallet {
         ValueQuery
     >;
 
+    /// Price state of the pools created as concentrated liquidity pools. Their `LiquidityPools`
+    /// reserves hold the assets backing positions, excluding uncollected fees.
+    #[pallet::storage]
+    #[pallet::getter(fn concentrated_pools)]
+    pub type ConcentratedPools<T: Config> = StorageMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        ConcentratedPoolOf<T>, 
+        OptionQuery
+    >;
+
+    /// Initialized ticks of each concentrated liquidity pool
+    #[pallet::storage]
+    #[pallet::getter(fn ticks)]
+    pub type Ticks<T: Config> = StorageDoubleMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        Twox64Concat, 
+        i32, 
+        TickInfo<BalanceOf<T>>, 
+        OptionQuery
+    >;
+
+    /// Sorted list of the initialized ticks of each concentrated liquidity pool
+    #[pallet::storage]
+    #[pallet::getter(fn initialized_ticks)]
+    pub type InitializedTicks<T: Config> = StorageMap<
+        _, 
+        Blake2_128Concat, 
+        (T::AssetId, T::AssetId), 
+        BoundedVec<i32, T::MaxTicks>, 
+        ValueQuery
+    >;
+
+    /// The account that initialized each tick of a concentrated liquidity pool and the
+    /// deposit reserved from it. Maps (pair, tick) -> (depositor, deposit)
+    #[pallet::storage]
+    #[pallet::getter(fn tick_deposits)]
+    pub type TickDeposits<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId),
+        Twox64Concat,
+        i32,
+        (T::AccountId, BalanceOf<T>),
+        OptionQuery
+    >;
+
+    /// Concentrated liquidity positions by id
+    #[pallet::storage]
+    #[pallet::getter(fn positions)]
+    pub type Positions<T: Config> = StorageMap<_, Blake2_128Concat, PositionId, PositionOf<T>, OptionQuery>;
+
+    /// The id the next opened position will get
+    #[pallet::storage]
+    #[pallet::getter(fn next_position_id)]
+    pub type NextPositionId<T: Config> = StorageValue<_, PositionId, ValueQuery>;
+
+    /// StableSwap pools by id
+    #[pallet::storage]
+    #[pallet::getter(fn stable_pools)]
+    pub type StablePools<T: Config> = StorageMap<_, Blake2_128Concat, StablePoolId, StablePoolOf<T>, OptionQuery>;
+
+    /// The id the next created StableSwap pool will get
+    #[pallet::storage]
+    #[pallet::getter(fn next_stable_pool_id)]
+    pub type NextStablePoolId<T: Config> = StorageValue<_, StablePoolId, ValueQuery>;
+
+    /// Protocol fees accrued per StableSwap pool and not yet claimed. Maps (pool, asset) -> amount
+    #[pallet::storage]
+    #[pallet::getter(fn stable_protocol_fees)]
+    pub type StableProtocolFees<T: Config> = StorageDoubleMap<
+        _, 
+        Blake2_128Concat, 
+        StablePoolId, 
+        Blake2_128Concat, 
+        T::AssetId, 
+        BalanceOf<T>, 
+        ValueQuery
+    >;
+
+    /// Pools paused by `PoolAdminOrigin`. They reject trades and deposits, but providers can
+    /// still withdraw. Maps canonical (asset_a, asset_b) -> ()
+    #[pallet::storage]
+    #[pallet::getter(fn paused_pools)]
+    pub type PausedPools<T: Config> = StorageMap<_, Blake2_128Concat, (T::AssetId, T::AssetId), (), OptionQuery>;
+
+    /// StableSwap pools paused by `PoolAdminOrigin`, which only accept withdrawals.
+    /// Maps pool id -> ()
+    #[pallet::storage]
+    #[pallet::getter(fn paused_stable_pools)]
+    pub type PausedStablePools<T: Config> = StorageMap<_, Blake2_128Concat, StablePoolId, (), OptionQuery>;
+
+    /// Reward schedules of each pool's farm. Maps canonical (asset_a, asset_b) -> schedules
+    #[pallet::storage]
+    #[pallet::getter(fn farms)]
+    pub type Farms<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId),
+        BoundedVec<RewardScheduleOf<T>, T::MaxRewardAssets>,
+        ValueQuery
+    >;
+
+    /// LP shares staked in a pool's farm. Maps (pair, account) -> staked shares
+    #[pallet::storage]
+    #[pallet::getter(fn stakes)]
+    pub type Stakes<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId),
+        Blake2_128Concat,
+        T::AccountId,
+        BalanceOf<T>,
+        ValueQuery
+    >;
+
+    /// Total LP shares staked in a pool's farm
+    #[pallet::storage]
+    #[pallet::getter(fn total_staked)]
+    pub type TotalStaked<T: Config> = StorageMap<_, Blake2_128Concat, (T::AssetId, T::AssetId), BalanceOf<T>, ValueQuery>;
+
+    /// Reward accounting per staker. Maps (pair, account, reward_asset) -> StakerReward
+    #[pallet::storage]
+    #[pallet::getter(fn staker_rewards)]
+    pub type StakerRewards<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, (T::AssetId, T::AssetId)>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, T::AssetId>,
+        ),
+        StakerReward<BalanceOf<T>>,
+        ValueQuery
+    >;
+
+    /// Pools with a flash swap in progress. They reject every other call that touches their
+    /// reserves until the flash swap has been repaid. Maps canonical (asset_a, asset_b) -> ()
+    #[pallet::storage]
+    pub type FlashLocks<T: Config> = StorageMap<_, Blake2_128Concat, (T::AssetId, T::AssetId), (), OptionQuery>;
+
+    /// Assets denied by `PoolAdminOrigin`. They cannot be pooled, deposited or traded until
+    /// they are allowed again, but providers can still withdraw them
+    #[pallet::storage]
+    #[pallet::getter(fn denied_assets)]
+    pub type DeniedAssets<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, (), OptionQuery>;
+
+    /// Resting limit orders by id
+    #[pallet::storage]
+    #[pallet::getter(fn orders)]
+    pub type Orders<T: Config> = StorageMap<_, Blake2_128Concat, OrderId, OrderOf<T>, OptionQuery>;
+
+    /// The id the next placed order will get
+    #[pallet::storage]
+    #[pallet::getter(fn next_order_id)]
+    pub type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+    /// Resting orders of each pool in the order they were placed, which is the order they are
+    /// checked in. Maps canonical (asset_a, asset_b) -> order ids
+    #[pallet::storage]
+    #[pallet::getter(fn pool_orders)]
+    pub type PoolOrders<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId),
+        BoundedVec<OrderId, T::MaxOrdersPerPool>,
+        ValueQuery
+    >;
+
+    /// Volume and fee aggregates of each asset pair for its most recent epochs with any trades,
+    /// oldest first, across every pool kind that trades the pair. Maps canonical
+    /// (asset_a, asset_b) -> epochs
+    #[pallet::storage]
+    #[pallet::getter(fn pool_stats)]
+    pub type PoolStats<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId),
+        BoundedVec<EpochStats<BalanceOf<T>>, T::MaxStatsEpochs>,
+        ValueQuery
+    >;
+
+    /// The pool and the last of its orders checked when `on_idle` ran out of weight, if any.
+    /// Matching resumes with the next order in the next block, so every order gets its turn.
+    #[pallet::storage]
+    pub type OrderBookCursor<T: Config> =
+        StorageValue<_, ((T::AssetId, T::AssetId), Option<OrderId>), OptionQuery>;
+
+    /// LP shares the v1 migration could not mint to their owner, held by the pallet account
+    /// until `PoolAdminOrigin` releases them with `release_held_shares`.
+    /// Maps canonical (asset_a, asset_b, account) -> shares
+    #[pallet::storage]
+    #[pallet::getter(fn held_shares)]
+    pub type HeldShares<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        (T::AssetId, T::AssetId, T::AccountId),
+        BalanceOf<T>,
+        ValueQuery
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// Liquidity added to a pool. [who, asset_a, asset_b, amount_a, amount_b, liquidity]
-        LiquidityAdded(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        /// A pool was registered. [creator, asset_a, asset_b, lp_asset]
+        PoolCreated(T::AccountId, T::AssetId, T::AssetId, T::AssetId),
+        
+        /// Liquidity added to a pool, leaving it with `reserve_a` and `reserve_b`
+        LiquidityAdded {
+            who: T::AccountId,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount_a: BalanceOf<T>,
+            amount_b: BalanceOf<T>,
+            liquidity: BalanceOf<T>,
+            reserve_a: BalanceOf<T>,
+            reserve_b: BalanceOf<T>,
+        },
         
-        /// Liquidity removed from a pool. [who, asset_a, asset_b, amount_a, amount_b, liquidity]
-        LiquidityRemoved(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        /// Liquidity removed from a pool, leaving it with `reserve_a` and `reserve_b`
+        LiquidityRemoved {
+            who: T::AccountId,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount_a: BalanceOf<T>,
+            amount_b: BalanceOf<T>,
+            liquidity: BalanceOf<T>,
+            reserve_a: BalanceOf<T>,
+            reserve_b: BalanceOf<T>,
+        },
         
-        /// Assets swapped. [who, asset_in, asset_out, amount_in, amount_out, fee_amount]
-        Swap(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        /// Assets swapped through one pool, leaving it with `reserve_in` of `asset_in` and
+        /// `reserve_out` of `asset_out`. `fee_amount` is charged in `asset_in`.
+        Swap {
+            who: T::AccountId,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            amount_in: BalanceOf<T>,
+            amount_out: BalanceOf<T>,
+            fee_amount: BalanceOf<T>,
+            reserve_in: BalanceOf<T>,
+            reserve_out: BalanceOf<T>,
+        },
         
         /// Fee collected from a swap. [asset, amount]
         FeeCollected(T::AssetId, BalanceOf<T>),
+        
+        /// Protocol fees of a pool were paid out. [asset_a, asset_b, amount_a, amount_b, destination]
+        ProtocolFeesClaimed(T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>, T::AccountId),
+        
+        /// A multi-hop swap settled; each hop also emits `Swap` and `FeeCollected`.
+        /// [who, path, amount_in, amount_out]
+        RoutedSwap(T::AccountId, SwapPathOf<T>, BalanceOf<T>, BalanceOf<T>),
+        
+        /// A concentrated liquidity pool was registered. [asset_a, asset_b, tick_spacing, initial_tick]
+        ConcentratedPoolCreated(T::AssetId, T::AssetId, u32, i32),
+        
+        /// A range position was opened. [who, position_id, asset_a, asset_b, tick_lower, tick_upper]
+        PositionOpened(T::AccountId, PositionId, T::AssetId, T::AssetId, i32, i32),
+        
+        /// Liquidity was added to a position. [position_id, liquidity, amount_a, amount_b]
+        PositionIncreased(PositionId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        
+        /// Liquidity was removed from a position. [position_id, liquidity, amount_a, amount_b]
+        PositionDecreased(PositionId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        
+        /// Fees earned by a position were paid out. [position_id, amount_a, amount_b]
+        PositionFeesCollected(PositionId, BalanceOf<T>, BalanceOf<T>),
+        
+        /// A StableSwap pool was registered. [pool_id, assets, lp_asset, amplification]
+        StablePoolCreated(StablePoolId, Vec<T::AssetId>, T::AssetId, u32),
+        
+        /// Liquidity added to a StableSwap pool. [who, pool_id, amounts, liquidity]
+        StableLiquidityAdded(T::AccountId, StablePoolId, Vec<BalanceOf<T>>, BalanceOf<T>),
+        
+        /// Liquidity removed from a StableSwap pool. [who, pool_id, amounts, liquidity]
+        StableLiquidityRemoved(T::AccountId, StablePoolId, Vec<BalanceOf<T>>, BalanceOf<T>),
+        
+        /// The amplification of a StableSwap pool started ramping.
+        /// [pool_id, initial_a, future_a, future_block]
+        AmplificationRampStarted(StablePoolId, u32, u32, T::BlockNumber),
+        
+        /// The amplification ramp of a StableSwap pool was stopped. [pool_id, amplification]
+        AmplificationRampStopped(StablePoolId, u32),
+        
+        /// Protocol fees of a StableSwap pool were paid out. [pool_id, amounts, destination]
+        StableProtocolFeesClaimed(StablePoolId, Vec<BalanceOf<T>>, T::AccountId),
+        
+        /// A pool stopped accepting trades and deposits. [asset_a, asset_b]
+        PoolPaused(T::AssetId, T::AssetId),
+        
+        /// A paused pool was reopened. [asset_a, asset_b]
+        PoolUnpaused(T::AssetId, T::AssetId),
+        
+        /// The fee tier of a pool was changed. [asset_a, asset_b, fee_tier]
+        PoolFeeSet(T::AssetId, T::AssetId, Perquintill),
+        
+        /// A denied asset can be pooled and traded again. [asset]
+        AssetAllowed(T::AssetId),
+        
+        /// An asset can no longer be pooled or traded. [asset]
+        AssetDenied(T::AssetId),
+        
+        /// A farm's emission of a reward asset was set.
+        /// [asset_a, asset_b, reward_asset, reward_per_block, start, end]
+        RewardScheduleSet(T::AssetId, T::AssetId, T::AssetId, BalanceOf<T>, T::BlockNumber, T::BlockNumber),
+        
+        /// LP shares were staked in a pool's farm. [who, asset_a, asset_b, amount]
+        Staked(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>),
+        
+        /// LP shares were withdrawn from a pool's farm. [who, asset_a, asset_b, amount]
+        Unstaked(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>),
+        
+        /// Farming rewards were paid out. [who, asset_a, asset_b, rewards]
+        RewardsClaimed(T::AccountId, T::AssetId, T::AssetId, Vec<(T::AssetId, BalanceOf<T>)>),
+        
+        /// A flash swap was repaid.
+        /// [who, asset_a, asset_b, amount_a_out, amount_b_out, amount_a_in, amount_b_in]
+        FlashSwap(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+        
+        /// A limit order was placed. [order_id, who, asset_in, asset_out, amount_in, min_amount_out]
+        OrderPlaced(OrderId, T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>, BalanceOf<T>),
+        
+        /// A limit order was filled against its pool. [order_id, who, amount_in, amount_out]
+        OrderFilled(OrderId, T::AccountId, BalanceOf<T>, BalanceOf<T>),
+        
+        /// A limit order was cancelled and refunded. [order_id, who]
+        OrderCancelled(OrderId, T::AccountId),
+        
+        /// A limit order passed its expiry unfilled and was refunded. [order_id, who]
+        OrderExpired(OrderId, T::AccountId),
+        
+        /// A StableSwap pool stopped accepting trades and deposits. [pool_id]
+        StablePoolPaused(StablePoolId),
+        
+        /// A paused StableSwap pool was reopened. [pool_id]
+        StablePoolUnpaused(StablePoolId),
+        
+        /// The fee tier of a StableSwap pool was changed. [pool_id, fee_tier]
+        StablePoolFeeSet(StablePoolId, Perquintill),
+        
+        /// LP shares held since the v1 migration were paid out to their owner.
+        /// [who, asset_a, asset_b, shares]
+        HeldSharesReleased(T::AccountId, T::AssetId, T::AssetId, BalanceOf<T>),
     }
 
     #[pallet::error]
@@ -119,26 +883,143 @@ pub mod pallet {
         /// Slippage tolerance exceeded
         SlippageExceeded,
         
-        /// Deadline exceeded
-        DeadlineExceeded,
-    }
-
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// Add liquidity to a pool
-        #[pallet::weight(10_000)]
-        pub fn add_liquidity(
-            origin: OriginFor<T>,
-            asset_a: T::AssetId,
-            asset_b: T::AssetId,
-            amount_a: BalanceOf<T>,
-            amount_b: BalanceOf<T>,
+        /// The call was included after its deadline block
+        Expired,
+        
+        /// A pool for this asset pair is already registered
+        PoolAlreadyExists,
+        
+        /// Swap path is shorter than two assets or does not match the quoted amounts
+        InvalidPath,
+        
+        /// Deposit is too small to mint any LP shares, or the first deposit does not
+        /// exceed the locked minimum liquidity
+        InsufficientLiquidityMinted,
+        
+        /// The pool has no unclaimed protocol fees
+        NoProtocolFees,
+        
+        /// The call does not apply to this kind of pool, e.g. full-range liquidity on a
+        /// concentrated liquidity pool or an exact-output route through one
+        WrongPoolKind,
+        
+        /// Ticks are out of bounds, out of order or not on the pool's tick spacing
+        InvalidTickRange,
+        
+        /// The pool already has the maximum number of initialized ticks
+        TooManyTicks,
+        
+        /// No position exists with this id
+        PositionNotFound,
+        
+        /// The position belongs to another account
+        NotPositionOwner,
+        
+        /// Amplification is out of bounds, or a ramp is too short or changes it more than tenfold
+        InvalidAmplification,
+        
+        /// The pool is paused and only accepts withdrawals
+        PoolPaused,
+        
+        /// One of the assets is denied by governance
+        AssetNotAllowed,
+        
+        /// The fee tier is outside the `MinPoolFee`..=`MaxPoolFee` bounds
+        FeeOutOfBounds,
+        
+        /// A reward schedule must end after it starts and after the current block
+        InvalidRewardSchedule,
+        
+        /// The farm already emits `MaxRewardAssets` reward assets
+        TooManyRewardAssets,
+        
+        /// There are no rewards to claim
+        NoRewards,
+        
+        /// A flash swap on the pool is in progress
+        PoolLocked,
+        
+        /// The repayment of a flash swap does not restore the pool's invariant plus the fee
+        FlashSwapNotRepaid,
+        
+        /// No order exists with this id
+        OrderNotFound,
+        
+        /// The order belongs to another account
+        NotOrderOwner,
+        
+        /// The pool already has `MaxOrdersPerPool` resting orders
+        TooManyOrders,
+        
+        /// Swap path visits an asset more than once, so it could trade through a pool twice
+        /// at a quote from before its first visit
+        PathRepeatsAsset,
+        
+        /// The account has no LP shares held for it in this pool
+        NoHeldShares,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Fill the resting orders whose pools have crossed their limits with the weight
+        /// left over at the end of the block
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::match_orders(now, remaining_weight)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+            for (pair, pool) in Pools::<T>::iter() {
+                ensure!(
+                    TotalLiquidity::<T>::get(pair) == T::Assets::total_issuance(pool.lp_asset),
+                    "TotalLiquidity out of sync with LP asset issuance"
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Register a pool for an asset pair. The pair is stored in canonical (sorted) order,
+        /// so (A, B) and (B, A) always refer to the same pool.
+        #[pallet::weight(T::WeightInfo::create_pool())]
+        pub fn create_pool(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::register_pool(who, asset_a, asset_b)?;
+
+            Ok(())
+        }
+
+        /// Add liquidity to a pool, no later than block `deadline` if one is given
+        #[pallet::weight(T::WeightInfo::add_liquidity())]
+        pub fn add_liquidity(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount_a: BalanceOf<T>,
+            amount_b: BalanceOf<T>,
             min_liquidity: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
 
-            // Ensure the asset pair is valid
-            ensure!(asset_a != asset_b, Error::<T>::InvalidAssetPair);
+            // Resolve the pool and line the amounts up with its canonical order
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            let (amount_a, amount_b) = if pair.0 == asset_a {
+                (amount_a, amount_b)
+            } else {
+                (amount_b, amount_a)
+            };
+            let (asset_a, asset_b) = pair;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            Self::ensure_pool_open(pair)?;
 
             // Ensure the amounts are non-zero
             ensure!(amount_a > Zero::zero() && amount_b > Zero::zero(), Error::<T>::InvalidAmount);
@@ -146,64 +1027,65 @@ pub mod pallet {
             // Fetch the current reserves
             let (reserve_a, reserve_b) = LiquidityPools::<T>::get((asset_a, asset_b));
 
+            let total_liquidity = TotalLiquidity::<T>::get(pair);
+            let is_initial = total_liquidity.is_zero();
+
             // Calculate the liquidity to be added
-            let liquidity = if reserve_a.is_zero() && reserve_b.is_zero() {
-                // Initial liquidity provision
-                amount_a.min(amount_b)
+            let liquidity = if is_initial {
+                // Initial liquidity provision mints sqrt(amount_a * amount_b), minus the
+                // permanently locked minimum that makes share-price inflation uneconomic
+                let minimum = T::MinimumLiquidity::get();
+                let initial = Self::initial_liquidity(amount_a, amount_b)?;
+                ensure!(initial > minimum, Error::<T>::InsufficientLiquidityMinted);
+                initial.saturating_sub(minimum)
             } else {
-                // Calculate based on existing reserves
-                let liquidity_a = amount_a
-                    .checked_mul(&reserve_b)
-                    .ok_or(Error::<T>::CalculationError)?
-                    .checked_div(&reserve_a)
-                    .ok_or(Error::<T>::CalculationError)?;
-                let liquidity_b = amount_b
-                    .checked_mul(&reserve_a)
-                    .ok_or(Error::<T>::CalculationError)?
-                    .checked_div(&reserve_b)
-                    .ok_or(Error::<T>::CalculationError)?;
-                liquidity_a.min(liquidity_b)
+                Self::shares_for_deposit(amount_a, amount_b, reserve_a, reserve_b, total_liquidity)?
             };
 
-            // Ensure the liquidity meets the minimum requirement
+            // Ensure the deposit mints shares and meets the minimum requirement
+            ensure!(liquidity > Zero::zero(), Error::<T>::InsufficientLiquidityMinted);
             ensure!(liquidity >= min_liquidity, Error::<T>::InsufficientLiquidity);
 
-            // Reserve (transfer) tokens from the user
-            T::Currency::transfer(
-                &who, 
-                &Self::account_id(), 
-                amount_a, 
-                ExistenceRequirement::KeepAlive
-            )?;
-            T::Currency::transfer(
-                &who, 
-                &Self::account_id(), 
-                amount_b, 
-                ExistenceRequirement::KeepAlive
-            )?;
+            // Transfer both assets from the user into the pool
+            Self::transfer_asset(asset_a, &who, &Self::account_id(), amount_a, true)?;
+            Self::transfer_asset(asset_b, &who, &Self::account_id(), amount_b, true)?;
+
+            // Advance the price oracle with the reserves from before this deposit
+            Self::update_price_oracle(pair);
 
             // Update the liquidity pool reserves
-            LiquidityPools::<T>::mutate((asset_a, asset_b), |reserves| {
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::mutate((asset_a, asset_b), |reserves| {
                 reserves.0 = reserves.0.saturating_add(amount_a);
                 reserves.1 = reserves.1.saturating_add(amount_b);
+                *reserves
             });
 
-            // Mint liquidity tokens to the provider
-            Self::mint_liquidity_tokens(&who, (asset_a, asset_b), liquidity)?;
+            // Lock the minimum liquidity in the pallet account on the first deposit
+            let minimum = T::MinimumLiquidity::get();
+            if is_initial && !minimum.is_zero() {
+                Self::mint_liquidity_tokens(&Self::account_id(), pair, &pool, minimum)?;
+            }
 
-            // Update total liquidity
-            TotalLiquidity::<T>::mutate((asset_a, asset_b), |total| {
-                *total = total.saturating_add(liquidity);
-            });
+            // Mint LP assets to the provider
+            Self::mint_liquidity_tokens(&who, pair, &pool, liquidity)?;
 
             // Emit an event
-            Self::deposit_event(Event::LiquidityAdded(who, asset_a, asset_b, amount_a, amount_b, liquidity));
+            Self::deposit_event(Event::LiquidityAdded {
+                who,
+                asset_a,
+                asset_b,
+                amount_a,
+                amount_b,
+                liquidity,
+                reserve_a,
+                reserve_b,
+            });
 
             Ok(())
         }
 
-        /// Remove liquidity from a pool
-        #[pallet::weight(10_000)]
+        /// Remove liquidity from a pool, no later than block `deadline` if one is given
+        #[pallet::weight(T::WeightInfo::remove_liquidity())]
         pub fn remove_liquidity(
             origin: OriginFor<T>,
             asset_a: T::AssetId,
@@ -211,11 +1093,21 @@ pub mod pallet {
             liquidity: BalanceOf<T>,
             min_amount_a: BalanceOf<T>,
             min_amount_b: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
 
-            // Ensure the asset pair is valid
-            ensure!(asset_a != asset_b, Error::<T>::InvalidAssetPair);
+            // Resolve the pool and line the minimums up with its canonical order
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            let (min_amount_a, min_amount_b) = if pair.0 == asset_a {
+                (min_amount_a, min_amount_b)
+            } else {
+                (min_amount_b, min_amount_a)
+            };
+            let (asset_a, asset_b) = pair;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            ensure!(!FlashLocks::<T>::contains_key(pair), Error::<T>::PoolLocked);
 
             // Ensure the liquidity amount is non-zero
             ensure!(liquidity > Zero::zero(), Error::<T>::InvalidAmount);
@@ -223,184 +1115,1904 @@ pub mod pallet {
             // Fetch the current reserves
             let (reserve_a, reserve_b) = LiquidityPools::<T>::get((asset_a, asset_b));
 
-            // Ensure the pool exists (has liquidity)
-            ensure!(reserve_a > Zero::zero() && reserve_b > Zero::zero(), Error::<T>::PoolDoesNotExist);
+            // Ensure the pool has liquidity
+            ensure!(reserve_a > Zero::zero() && reserve_b > Zero::zero(), Error::<T>::InsufficientLiquidity);
 
             // Get the total liquidity of the pool
             let total_liquidity = TotalLiquidity::<T>::get((asset_a, asset_b));
-            ensure!(total_liquidity > Zero::zero(), Error::<T>::PoolDoesNotExist);
+            ensure!(total_liquidity > Zero::zero(), Error::<T>::InsufficientLiquidity);
 
             // Calculate the amounts to return
-            let amount_a = liquidity
-                .checked_mul(&reserve_a)
-                .ok_or(Error::<T>::CalculationError)?
-                .checked_div(&total_liquidity)
-                .ok_or(Error::<T>::CalculationError)?;
-            let amount_b = liquidity
-                .checked_mul(&reserve_b)
-                .ok_or(Error::<T>::CalculationError)?
-                .checked_div(&total_liquidity)
-                .ok_or(Error::<T>::CalculationError)?;
+            let (amount_a, amount_b) = Self::withdrawal_for_shares(liquidity, reserve_a, reserve_b, total_liquidity)?;
 
             // Ensure minimum amounts are satisfied
             ensure!(amount_a >= min_amount_a, Error::<T>::SlippageExceeded);
             ensure!(amount_b >= min_amount_b, Error::<T>::SlippageExceeded);
 
-            // Burn the liquidity tokens
-            Self::burn_liquidity_tokens(&who, (asset_a, asset_b), liquidity)?;
+            // Burn the provider's LP assets
+            Self::burn_liquidity_tokens(&who, pair, &pool, liquidity)?;
 
-            // Update total liquidity
-            TotalLiquidity::<T>::mutate((asset_a, asset_b), |total| {
-                *total = total.saturating_sub(liquidity);
-            });
+            // Advance the price oracle with the reserves from before this withdrawal
+            Self::update_price_oracle(pair);
 
             // Update the reserves
-            LiquidityPools::<T>::mutate((asset_a, asset_b), |reserves| {
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::mutate((asset_a, asset_b), |reserves| {
                 reserves.0 = reserves.0.saturating_sub(amount_a);
                 reserves.1 = reserves.1.saturating_sub(amount_b);
+                *reserves
             });
 
             // Transfer the assets back to the user
-            T::Currency::transfer(
-                &Self::account_id(), 
-                &who, 
-                amount_a, 
-                ExistenceRequirement::AllowDeath
-            )?;
-            T::Currency::transfer(
-                &Self::account_id(), 
-                &who, 
-                amount_b, 
-                ExistenceRequirement::AllowDeath
-            )?;
+            Self::transfer_asset(asset_a, &Self::account_id(), &who, amount_a, false)?;
+            Self::transfer_asset(asset_b, &Self::account_id(), &who, amount_b, false)?;
 
             // Emit an event
-            Self::deposit_event(Event::LiquidityRemoved(who, asset_a, asset_b, amount_a, amount_b, liquidity));
+            Self::deposit_event(Event::LiquidityRemoved {
+                who,
+                asset_a,
+                asset_b,
+                amount_a,
+                amount_b,
+                liquidity,
+                reserve_a,
+                reserve_b,
+            });
 
             Ok(())
         }
 
-        /// Swap tokens using a liquidity pool with the Nourish Chain 0.369% fee
-        #[pallet::weight(10_000)]
+        /// Swap tokens using a liquidity pool with the Nourish Chain 0.369% fee, no later
+        /// than block `deadline` if one is given. A concentrated pool is charged for crossing
+        /// `MaxTicks` ticks up front and refunded for the ticks it did not cross.
+        #[pallet::weight(
+            T::WeightInfo::swap().saturating_add(T::WeightInfo::concentrated_swap_hop(T::MaxTicks::get()))
+        )]
         pub fn swap(
             origin: OriginFor<T>,
             asset_in: T::AssetId,
             asset_out: T::AssetId,
             amount_in: BalanceOf<T>,
             min_amount_out: BalanceOf<T>,
-        ) -> DispatchResult {
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
 
-            // Ensure the asset pair is valid
-            ensure!(asset_in != asset_out, Error::<T>::InvalidAssetPair);
+            // Ensure the amount is non-zero
+            ensure!(amount_in > Zero::zero(), Error::<T>::InvalidAmount);
+
+            // Quote the trade through the direct pool
+            let path = [asset_in, asset_out];
+            let amounts = Self::get_amounts_out(&path, amount_in)?;
+            let amount_out = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
+
+            // Ensure minimum amount out is satisfied
+            ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+            let concentrated_weight = Self::do_swap(&who, &path, &amounts)?;
+
+            Ok(Some(T::WeightInfo::swap().saturating_add(concentrated_weight)).into())
+        }
+
+        /// Swap an exact `amount_in` of `path[0]` for as much of the last asset in `path` as
+        /// possible, routing through every pool along the path. Each hop pays its pool's fee
+        /// and only the final output is checked against `amount_out_min`, no later than block
+        /// `deadline` if one is given. Every hop is charged for crossing `MaxTicks` ticks of a
+        /// concentrated pool up front and refunded for the ticks it did not cross.
+        #[pallet::weight({
+            let hops = (path.len() as u32).saturating_sub(1);
+            T::WeightInfo::swap_exact_tokens_for_tokens(path.len() as u32)
+                .saturating_add(T::WeightInfo::concentrated_swap_hop(T::MaxTicks::get()).saturating_mul(hops.into()))
+        })]
+        pub fn swap_exact_tokens_for_tokens(
+            origin: OriginFor<T>,
+            path: SwapPathOf<T>,
+            amount_in: BalanceOf<T>,
+            amount_out_min: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
 
             // Ensure the amount is non-zero
             ensure!(amount_in > Zero::zero(), Error::<T>::InvalidAmount);
 
-            // Determine the correct order of assets
-            let (asset_a, asset_b, is_reversed) = if asset_in < asset_out {
-                (asset_in, asset_out, false)
-            } else {
-                (asset_out, asset_in, true)
-            };
+            // Quote every hop forwards from the exact input
+            let amounts = Self::get_amounts_out(&path, amount_in)?;
+            let amount_out = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
 
-            // Fetch the current reserves
-            let (mut reserve_a, mut reserve_b) = LiquidityPools::<T>::get((asset_a, asset_b));
+            // Ensure minimum amount out is satisfied
+            ensure!(amount_out >= amount_out_min, Error::<T>::SlippageExceeded);
+
+            let concentrated_weight = Self::do_swap(&who, &path, &amounts)?;
+            let weight =
+                T::WeightInfo::swap_exact_tokens_for_tokens(path.len() as u32).saturating_add(concentrated_weight);
+
+            Self::deposit_event(Event::RoutedSwap(who, path, amount_in, amount_out));
+
+            Ok(Some(weight).into())
+        }
+
+        /// Swap as little of `path[0]` as possible for an exact `amount_out` of the last asset
+        /// in `path`, routing through every pool along the path. Each hop pays its pool's fee
+        /// and only the total input is checked against `amount_in_max`, no later than block
+        /// `deadline` if one is given.
+        #[pallet::weight(T::WeightInfo::swap_tokens_for_exact_tokens(path.len() as u32))]
+        pub fn swap_tokens_for_exact_tokens(
+            origin: OriginFor<T>,
+            path: SwapPathOf<T>,
+            amount_out: BalanceOf<T>,
+            amount_in_max: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
+
+            // Ensure the amount is non-zero
+            ensure!(amount_out > Zero::zero(), Error::<T>::InvalidAmount);
+
+            // Quote every hop backwards from the exact output
+            let amounts = Self::get_amounts_in(&path, amount_out)?;
+            let amount_in = *amounts.first().ok_or(Error::<T>::InvalidPath)?;
+
+            // Ensure maximum amount in is respected
+            ensure!(amount_in <= amount_in_max, Error::<T>::SlippageExceeded);
+
+            Self::do_swap(&who, &path, &amounts)?;
+
+            Self::deposit_event(Event::RoutedSwap(who, path, amount_in, amount_out));
+
+            Ok(())
+        }
+
+        /// Pay a pool's accrued protocol fees out to `FeeDestination`. Anyone may trigger the
+        /// sweep; the funds always go to the configured destination.
+        #[pallet::weight(T::WeightInfo::claim_protocol_fees())]
+        pub fn claim_protocol_fees(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            let amount_a = ProtocolFees::<T>::take(pair, pair.0);
+            let amount_b = ProtocolFees::<T>::take(pair, pair.1);
+            ensure!(!amount_a.is_zero() || !amount_b.is_zero(), Error::<T>::NoProtocolFees);
+
+            let destination = T::FeeDestination::get();
+            if !amount_a.is_zero() {
+                Self::transfer_asset(pair.0, &Self::account_id(), &destination, amount_a, false)?;
+            }
+            if !amount_b.is_zero() {
+                Self::transfer_asset(pair.1, &Self::account_id(), &destination, amount_b, false)?;
+            }
+
+            Self::deposit_event(Event::ProtocolFeesClaimed(pair.0, pair.1, amount_a, amount_b, destination));
+
+            Ok(())
+        }
+
+        /// Register a concentrated liquidity pool whose price starts at `initial_tick`.
+        /// Liquidity is provided through range positions instead of `add_liquidity`, so the
+        /// LP asset the pool is registered with stays unused.
+        #[pallet::weight(T::WeightInfo::create_concentrated_pool())]
+        pub fn create_concentrated_pool(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            tick_spacing: u32,
+            initial_tick: i32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Ensure the tick spacing is usable and the initial price within the tick bounds
+            ensure!(
+                tick_spacing > 0 && tick_spacing <= concentrated::MAX_TICK as u32,
+                Error::<T>::InvalidTickRange
+            );
+            ensure!(
+                (concentrated::MIN_TICK..=concentrated::MAX_TICK).contains(&initial_tick),
+                Error::<T>::InvalidTickRange
+            );
+
+            let pair = Self::register_pool(who, asset_a, asset_b)?;
+            ConcentratedPools::<T>::insert(pair, ConcentratedPool {
+                sqrt_price: concentrated::sqrt_price_at_tick(initial_tick),
+                tick: initial_tick,
+                tick_spacing,
+                liquidity: Zero::zero(),
+                fee_growth_global_a: FixedU128::zero(),
+                fee_growth_global_b: FixedU128::zero(),
+            });
+
+            Self::deposit_event(Event::ConcentratedPoolCreated(pair.0, pair.1, tick_spacing, initial_tick));
+
+            Ok(())
+        }
 
-            // Ensure the pool exists
-            ensure!(reserve_a > Zero::zero() && reserve_b > Zero::zero(), Error::<T>::PoolDoesNotExist);
+        /// Open a position providing liquidity to a concentrated liquidity pool between
+        /// `tick_lower` and `tick_upper`, prices of asset_a in asset_b of the canonical pair.
+        /// Deposits the largest liquidity the maximum amounts can back at the current price.
+        #[pallet::weight(T::WeightInfo::open_position())]
+        pub fn open_position(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            tick_lower: i32,
+            tick_upper: i32,
+            amount_a_max: BalanceOf<T>,
+            amount_b_max: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
 
-            // Apply the reserves based on the swap direction
-            let (reserve_in, reserve_out) = if is_reversed {
-                (reserve_b, reserve_a)
+            // Resolve the pool and line the amounts up with its canonical order
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            let (amount_a_max, amount_b_max) = if pair.0 == asset_a {
+                (amount_a_max, amount_b_max)
             } else {
-                (reserve_a, reserve_b)
+                (amount_b_max, amount_a_max)
             };
+            let state = ConcentratedPools::<T>::get(pair).ok_or(Error::<T>::WrongPoolKind)?;
+            Self::ensure_pool_open(pair)?;
+            Self::ensure_valid_range(state.tick_spacing, tick_lower, tick_upper)?;
 
-            // Calculate fee amount (0.369% of amount_in)
-            let fee_amount = T::TradingFee::mul_floor(amount_in);
-            let amount_in_after_fee = amount_in.saturating_sub(fee_amount);
+            let position_id = NextPositionId::<T>::get();
+            NextPositionId::<T>::put(position_id.checked_add(1).ok_or(Error::<T>::CalculationError)?);
+            let position = Position {
+                owner: who.clone(),
+                pool: pair,
+                tick_lower,
+                tick_upper,
+                liquidity: Zero::zero(),
+                fee_growth_inside_a_last: FixedU128::zero(),
+                fee_growth_inside_b_last: FixedU128::zero(),
+                tokens_owed_a: Zero::zero(),
+                tokens_owed_b: Zero::zero(),
+            };
 
-            // Calculate amount out using the constant product formula: x * y = k
-            // (reserve_in + amount_in_after_fee) * (reserve_out - amount_out) = reserve_in * reserve_out
-            // So: amount_out = reserve_out - (reserve_in * reserve_out) / (reserve_in + amount_in_after_fee)
-            let numerator = reserve_in
-                .checked_mul(&reserve_out)
-                .ok_or(Error::<T>::CalculationError)?;
-            let denominator = reserve_in
-                .checked_add(&amount_in_after_fee)
-                .ok_or(Error::<T>::CalculationError)?;
-            let new_reserve_out = numerator
-                .checked_div(&denominator)
-                .ok_or(Error::<T>::CalculationError)?;
-            let amount_out = reserve_out.saturating_sub(new_reserve_out);
+            Self::deposit_event(Event::PositionOpened(who, position_id, pair.0, pair.1, tick_lower, tick_upper));
 
-            // Ensure minimum amount out is satisfied
-            ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+            Self::increase_position(position_id, position, state, amount_a_max, amount_b_max)
+        }
 
-            // Reserve (transfer) tokens from the user
-            T::Currency::transfer(
-                &who, 
-                &Self::account_id(), 
-                amount_in, 
-                ExistenceRequirement::KeepAlive
-            )?;
+        /// Add liquidity to an existing position, with maximum amounts in the pool's canonical
+        /// asset order
+        #[pallet::weight(T::WeightInfo::increase_liquidity())]
+        pub fn increase_liquidity(
+            origin: OriginFor<T>,
+            position_id: PositionId,
+            amount_a_max: BalanceOf<T>,
+            amount_b_max: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
 
-            // Update the reserves
-            if is_reversed {
-                reserve_b = reserve_b.saturating_add(amount_in);
-                reserve_a = reserve_a.saturating_sub(amount_out);
+            let position = Self::owned_position(&who, position_id)?;
+            let state = ConcentratedPools::<T>::get(position.pool).ok_or(Error::<T>::PoolDoesNotExist)?;
+            Self::ensure_pool_open(position.pool)?;
+
+            Self::increase_position(position_id, position, state, amount_a_max, amount_b_max)
+        }
+
+        /// Withdraw `liquidity` from a position, with minimum amounts in the pool's canonical
+        /// asset order. Fees earned so far stay in the position until collected.
+        #[pallet::weight(T::WeightInfo::decrease_liquidity())]
+        pub fn decrease_liquidity(
+            origin: OriginFor<T>,
+            position_id: PositionId,
+            liquidity: BalanceOf<T>,
+            min_amount_a: BalanceOf<T>,
+            min_amount_b: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut position = Self::owned_position(&who, position_id)?;
+            let pair = position.pool;
+            let mut state = ConcentratedPools::<T>::get(pair).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+            // Ensure the liquidity amount is non-zero and held by the position
+            ensure!(liquidity > Zero::zero(), Error::<T>::InvalidAmount);
+            ensure!(liquidity <= position.liquidity, Error::<T>::InsufficientBalance);
+
+            // Calculate the amounts to return, rounded in the pool's favour
+            let (amount_a, amount_b) = concentrated::amounts_for_liquidity(
+                state.sqrt_price,
+                concentrated::sqrt_price_at_tick(position.tick_lower),
+                concentrated::sqrt_price_at_tick(position.tick_upper),
+                liquidity.saturated_into(),
+                false,
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            let amount_a = Self::to_balance(amount_a)?;
+            let amount_b = Self::to_balance(amount_b)?;
+
+            // Ensure minimum amounts are satisfied
+            ensure!(amount_a >= min_amount_a, Error::<T>::SlippageExceeded);
+            ensure!(amount_b >= min_amount_b, Error::<T>::SlippageExceeded);
+
+            // Advance the price oracle before the reserves change
+            Self::update_price_oracle(pair);
+
+            Self::modify_position(&mut position, &mut state, liquidity, false)?;
+            Positions::<T>::insert(position_id, position.clone());
+
+            LiquidityPools::<T>::mutate(pair, |reserves| {
+                reserves.0 = reserves.0.saturating_sub(amount_a);
+                reserves.1 = reserves.1.saturating_sub(amount_b);
+            });
+
+            // Transfer the assets back to the owner
+            if !amount_a.is_zero() {
+                Self::transfer_asset(pair.0, &Self::account_id(), &who, amount_a, false)?;
+            }
+            if !amount_b.is_zero() {
+                Self::transfer_asset(pair.1, &Self::account_id(), &who, amount_b, false)?;
+            }
+
+            Self::deposit_event(Event::PositionDecreased(position_id, liquidity, amount_a, amount_b));
+
+            Ok(())
+        }
+
+        /// Pay out the fees a position has earned. A position without liquidity is closed
+        /// once its fees are collected.
+        #[pallet::weight(T::WeightInfo::collect_fees())]
+        pub fn collect_fees(origin: OriginFor<T>, position_id: PositionId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut position = Self::owned_position(&who, position_id)?;
+            let pair = position.pool;
+            let mut state = ConcentratedPools::<T>::get(pair).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+            // Settle the fees earned since the position last changed
+            Self::modify_position(&mut position, &mut state, Zero::zero(), true)?;
+            let amount_a = sp_std::mem::take(&mut position.tokens_owed_a);
+            let amount_b = sp_std::mem::take(&mut position.tokens_owed_b);
+
+            if position.liquidity.is_zero() {
+                Positions::<T>::remove(position_id);
             } else {
-                reserve_a = reserve_a.saturating_add(amount_in);
-                reserve_b = reserve_b.saturating_sub(amount_out);
+                Positions::<T>::insert(position_id, position);
             }
-            LiquidityPools::<T>::insert((asset_a, asset_b), (reserve_a, reserve_b));
 
-            // Transfer the output assets to the user
-            T::Currency::transfer(
-                &Self::account_id(), 
-                &who, 
-                amount_out, 
-                ExistenceRequirement::AllowDeath
-            )?;
+            if !amount_a.is_zero() {
+                Self::transfer_asset(pair.0, &Self::account_id(), &who, amount_a, false)?;
+            }
+            if !amount_b.is_zero() {
+                Self::transfer_asset(pair.1, &Self::account_id(), &who, amount_b, false)?;
+            }
 
-            // Emit events
-            Self::deposit_event(Event::Swap(who, asset_in, asset_out, amount_in, amount_out, fee_amount));
-            Self::deposit_event(Event::FeeCollected(asset_in, fee_amount));
+            Self::deposit_event(Event::PositionFeesCollected(position_id, amount_a, amount_b));
 
             Ok(())
         }
-    }
 
-    impl<T: Config> Pallet<T> {
-        /// Get the account ID for the pallet
-        fn account_id() -> T::AccountId {
-            T::PalletId::get().into_account_truncating()
+        /// Register a StableSwap pool for 2 to `MAX_STABLE_ASSETS` pegged assets with the
+        /// given amplification coefficient. The assets must share the same decimals.
+        #[pallet::weight(T::WeightInfo::create_stable_pool())]
+        pub fn create_stable_pool(
+            origin: OriginFor<T>,
+            assets: BoundedVec<T::AssetId, ConstU32<MAX_STABLE_ASSETS>>,
+            amplification: u32,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            // Ensure the assets are distinct, stored in sorted order
+            let mut assets = assets.into_inner();
+            assets.sort();
+            ensure!(assets.len() >= 2, Error::<T>::InvalidAssetPair);
+            ensure!(assets.windows(2).all(|pair| pair[0] != pair[1]), Error::<T>::InvalidAssetPair);
+            Self::ensure_assets_allowed(&assets)?;
+            let assets = BoundedVec::<_, ConstU32<MAX_STABLE_ASSETS>>::truncate_from(assets);
+            ensure!(
+                amplification > 0 && amplification <= T::MaxAmplification::get(),
+                Error::<T>::InvalidAmplification
+            );
+
+            // Allocate the pool id and LP asset
+            let pool_id = NextStablePoolId::<T>::get();
+            NextStablePoolId::<T>::put(pool_id.checked_add(1).ok_or(Error::<T>::CalculationError)?);
+            let lp_asset = Self::allocate_lp_asset()?;
+
+            let now = frame_system::Pallet::<T>::block_number();
+            let reserves = BoundedVec::truncate_from(sp_std::vec![Zero::zero(); assets.len()]);
+            StablePools::<T>::insert(pool_id, StablePool {
+                assets: assets.clone(),
+                reserves,
+                fee_tier: T::TradingFee::get(),
+                lp_asset,
+                amplification: AmplificationRamp {
+                    initial_a: amplification,
+                    future_a: amplification,
+                    initial_block: now,
+                    future_block: now,
+                },
+            });
+
+            Self::deposit_event(Event::StablePoolCreated(pool_id, assets.into_inner(), lp_asset, amplification));
+
+            Ok(())
         }
 
-        /// Mint liquidity tokens to a user
-        fn mint_liquidity_tokens(
-            who: &T::AccountId,
-            pool: (T::AssetId, T::AssetId),
-            amount: BalanceOf<T>,
+        /// Add liquidity to a StableSwap pool, with one amount per pooled asset in the pool's
+        /// asset order. The first deposit must fund every asset.
+        #[pallet::weight(T::WeightInfo::add_stable_liquidity())]
+        pub fn add_stable_liquidity(
+            origin: OriginFor<T>,
+            pool_id: StablePoolId,
+            amounts: BoundedVec<BalanceOf<T>, ConstU32<MAX_STABLE_ASSETS>>,
+            min_liquidity: BalanceOf<T>,
         ) -> DispatchResult {
-            LiquidityTokens::<T>::mutate((pool.0, pool.1, who.clone()), |balance| {
-                *balance = balance.saturating_add(amount);
-            });
+            let who = ensure_signed(origin)?;
+
+            let mut pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+            Self::ensure_stable_pool_open(pool_id)?;
+            Self::ensure_assets_allowed(&pool.assets)?;
+            let is_initial = T::Assets::total_issuance(pool.lp_asset).is_zero();
+
+            // Calculate the liquidity to be added
+            let (liquidity, reserves, protocol_fees) = Self::stable_shares_for_deposit(&pool, &amounts)?;
+            ensure!(liquidity >= min_liquidity, Error::<T>::InsufficientLiquidity);
+
+            // Transfer the assets from the user into the pool
+            for (asset, amount) in pool.assets.iter().zip(amounts.iter()) {
+                if !amount.is_zero() {
+                    Self::transfer_asset(*asset, &who, &Self::account_id(), *amount, true)?;
+                }
+            }
+
+            // Update the reserves and set the protocol's share of any imbalance fee aside
+            for (asset, protocol_fee) in pool.assets.iter().zip(protocol_fees) {
+                if !protocol_fee.is_zero() {
+                    StableProtocolFees::<T>::mutate(pool_id, asset, |accrued| {
+                        *accrued = accrued.saturating_add(protocol_fee);
+                    });
+                }
+            }
+            pool.reserves = BoundedVec::truncate_from(reserves);
+
+            let minted = Self::mint_stable_shares(&who, &pool, liquidity, is_initial)?;
+            StablePools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(Event::StableLiquidityAdded(who, pool_id, amounts.into_inner(), minted));
+
             Ok(())
         }
 
-        /// Burn liquidity tokens from a user
-        fn burn_liquidity_tokens(
-            who: &T::AccountId,
-            pool: (T::AssetId, T::AssetId),
-            amount: BalanceOf<T>,
+        /// Remove liquidity from a StableSwap pool in proportion to its reserves, with one
+        /// minimum amount per pooled asset in the pool's asset order
+        #[pallet::weight(T::WeightInfo::remove_stable_liquidity())]
+        pub fn remove_stable_liquidity(
+            origin: OriginFor<T>,
+            pool_id: StablePoolId,
+            liquidity: BalanceOf<T>,
+            min_amounts: BoundedVec<BalanceOf<T>, ConstU32<MAX_STABLE_ASSETS>>,
         ) -> DispatchResult {
-            LiquidityTokens::<T>::try_mutate((pool.0, pool.1, who.clone()), |balance| -> DispatchResult {
-                ensure!(*balance >= amount, Error::<T>::InsufficientBalance);
-                *balance = balance.saturating_sub(amount);
-                Ok(())
-            })
+            let who = ensure_signed(origin)?;
+
+            let mut pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+            ensure!(min_amounts.len() == pool.assets.len(), Error::<T>::InvalidAmount);
+
+            // Ensure the liquidity amount is non-zero and held by the user
+            ensure!(liquidity > Zero::zero(), Error::<T>::InvalidAmount);
+            ensure!(T::Assets::balance(pool.lp_asset, &who) >= liquidity, Error::<T>::InsufficientBalance);
+            let total_liquidity = T::Assets::total_issuance(pool.lp_asset);
+
+            // Calculate the amounts to return and ensure the minimums are satisfied
+            let mut amounts = Vec::with_capacity(pool.assets.len());
+            for (reserve, min_amount) in pool.reserves.iter_mut().zip(min_amounts.iter()) {
+                let amount = math::mul_div(
+                    liquidity.saturated_into(),
+                    (*reserve).saturated_into(),
+                    total_liquidity.saturated_into(),
+                    false,
+                )
+                .ok_or(Error::<T>::CalculationError)?;
+                let amount = Self::to_balance(amount)?;
+                ensure!(amount >= *min_amount, Error::<T>::SlippageExceeded);
+                *reserve = reserve.saturating_sub(amount);
+                amounts.push(amount);
+            }
+
+            // Burn the provider's LP assets and pay out
+            T::Assets::burn_from(pool.lp_asset, &who, liquidity)?;
+            for (asset, amount) in pool.assets.iter().zip(amounts.iter()) {
+                if !amount.is_zero() {
+                    Self::transfer_asset(*asset, &Self::account_id(), &who, *amount, false)?;
+                }
+            }
+            StablePools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(Event::StableLiquidityRemoved(who, pool_id, amounts, liquidity));
+
+            Ok(())
+        }
+
+        /// Swap an exact `amount_in` of one asset of a StableSwap pool for another, no later
+        /// than block `deadline` if one is given
+        #[pallet::weight(T::WeightInfo::stable_swap())]
+        pub fn stable_swap(
+            origin: OriginFor<T>,
+            pool_id: StablePoolId,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            amount_in: BalanceOf<T>,
+            min_amount_out: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
+
+            // Ensure the amount is non-zero
+            ensure!(amount_in > Zero::zero(), Error::<T>::InvalidAmount);
+
+            let mut pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+            Self::ensure_stable_pool_open(pool_id)?;
+            Self::ensure_assets_allowed(&[asset_in, asset_out])?;
+            let (amount_out, fee_amount, protocol_fee) = Self::stable_amount_out(&pool, asset_in, asset_out, amount_in)?;
+
+            // Ensure minimum amount out is satisfied
+            ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+            // The LP part of the fee stays in the reserves, the protocol's share is set aside
+            let i = Self::stable_index(&pool, asset_in)?;
+            let j = Self::stable_index(&pool, asset_out)?;
+            pool.reserves[i] = pool.reserves[i]
+                .checked_add(&amount_in.saturating_sub(protocol_fee))
+                .ok_or(Error::<T>::CalculationError)?;
+            pool.reserves[j] = pool.reserves[j].saturating_sub(amount_out);
+            let (reserve_in, reserve_out) = (pool.reserves[i], pool.reserves[j]);
+            if !protocol_fee.is_zero() {
+                StableProtocolFees::<T>::mutate(pool_id, asset_in, |accrued| {
+                    *accrued = accrued.saturating_add(protocol_fee);
+                });
+            }
+            StablePools::<T>::insert(pool_id, pool);
+
+            Self::transfer_asset(asset_in, &who, &Self::account_id(), amount_in, true)?;
+            Self::transfer_asset(asset_out, &Self::account_id(), &who, amount_out, false)?;
+
+            // Emit events
+            Self::deposit_event(Event::Swap {
+                who,
+                asset_in,
+                asset_out,
+                amount_in,
+                amount_out,
+                fee_amount,
+                reserve_in,
+                reserve_out,
+            });
+            Self::deposit_event(Event::FeeCollected(asset_in, fee_amount));
+            Self::record_swap(Self::canonical_pair(asset_in, asset_out), asset_in, amount_in, amount_out, fee_amount);
+
+            Ok(())
+        }
+
+        /// Move the amplification coefficient of a StableSwap pool linearly to `future_a`,
+        /// reaching it at `future_block`
+        #[pallet::weight(T::WeightInfo::ramp_amplification())]
+        pub fn ramp_amplification(
+            origin: OriginFor<T>,
+            pool_id: StablePoolId,
+            future_a: u32,
+            future_block: T::BlockNumber,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            StablePools::<T>::try_mutate(pool_id, |pool| -> DispatchResult {
+                let pool = pool.as_mut().ok_or(Error::<T>::PoolDoesNotExist)?;
+                let now = frame_system::Pallet::<T>::block_number();
+                let current = Self::current_amplification(&pool.amplification, now);
+                Self::ensure_valid_ramp(current, future_a, future_block.saturating_sub(now))?;
+
+                let initial_a = current.saturated_into::<u32>();
+                pool.amplification = AmplificationRamp { initial_a, future_a, initial_block: now, future_block };
+
+                Self::deposit_event(Event::AmplificationRampStarted(pool_id, initial_a, future_a, future_block));
+                Ok(())
+            })
+        }
+
+        /// Stop an amplification ramp, freezing the coefficient at its current value
+        #[pallet::weight(T::WeightInfo::stop_amplification_ramp())]
+        pub fn stop_amplification_ramp(origin: OriginFor<T>, pool_id: StablePoolId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            StablePools::<T>::try_mutate(pool_id, |pool| -> DispatchResult {
+                let pool = pool.as_mut().ok_or(Error::<T>::PoolDoesNotExist)?;
+                let now = frame_system::Pallet::<T>::block_number();
+                let current = Self::current_amplification(&pool.amplification, now).saturated_into::<u32>();
+                pool.amplification = AmplificationRamp {
+                    initial_a: current,
+                    future_a: current,
+                    initial_block: now,
+                    future_block: now,
+                };
+
+                Self::deposit_event(Event::AmplificationRampStopped(pool_id, current));
+                Ok(())
+            })
+        }
+
+        /// Pay a StableSwap pool's accrued protocol fees out to `FeeDestination`
+        #[pallet::weight(T::WeightInfo::claim_stable_protocol_fees())]
+        pub fn claim_stable_protocol_fees(origin: OriginFor<T>, pool_id: StablePoolId) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let pool = StablePools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+            let amounts: Vec<BalanceOf<T>> = pool
+                .assets
+                .iter()
+                .map(|asset| StableProtocolFees::<T>::take(pool_id, asset))
+                .collect();
+            ensure!(amounts.iter().any(|amount| !amount.is_zero()), Error::<T>::NoProtocolFees);
+
+            let destination = T::FeeDestination::get();
+            for (asset, amount) in pool.assets.iter().zip(amounts.iter()) {
+                if !amount.is_zero() {
+                    Self::transfer_asset(*asset, &Self::account_id(), &destination, *amount, false)?;
+                }
+            }
+
+            Self::deposit_event(Event::StableProtocolFeesClaimed(pool_id, amounts, destination));
+
+            Ok(())
+        }
+
+        /// Add liquidity to a constant product pool with a single asset. Part of `amount_in`
+        /// is swapped for `asset_other` at the optimal split and the rest is deposited with
+        /// the swap's output, so the provider only needs to hold one side of the pair. Fails
+        /// if included after block `deadline`, if one is given.
+        #[pallet::weight(T::WeightInfo::add_liquidity_single_asset())]
+        pub fn add_liquidity_single_asset(
+            origin: OriginFor<T>,
+            asset_in: T::AssetId,
+            asset_other: T::AssetId,
+            amount_in: BalanceOf<T>,
+            min_liquidity: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
+
+            let (pair, pool) = Self::resolve_pool(asset_in, asset_other)?;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            Self::ensure_pool_open(pair)?;
+
+            // Ensure the amount is non-zero
+            ensure!(amount_in > Zero::zero(), Error::<T>::InvalidAmount);
+
+            // The split is priced off the reserves, so the pool must already be funded
+            let (reserve_in, reserve_other, fee) = Self::get_reserves(asset_in, asset_other)?;
+            let total_liquidity = TotalLiquidity::<T>::get(pair);
+            ensure!(total_liquidity > Zero::zero(), Error::<T>::InsufficientLiquidity);
+
+            // Quote the internal swap and the reserves it leaves behind
+            let swap_amount = Self::optimal_zap_amount(reserve_in, amount_in, fee)?;
+            ensure!(swap_amount > Zero::zero() && swap_amount < amount_in, Error::<T>::InvalidAmount);
+            let swapped = Self::get_amount_out(swap_amount, reserve_in, reserve_other, fee)?;
+            let protocol_fee = T::ProtocolFeeShare::get().mul_floor(fee.mul_floor(swap_amount));
+            let reserve_in_after = reserve_in
+                .checked_add(&swap_amount.saturating_sub(protocol_fee))
+                .ok_or(Error::<T>::CalculationError)?;
+            let reserve_other_after = reserve_other.saturating_sub(swapped);
+
+            // Calculate the liquidity the remainder and the swap output are worth
+            let deposit = amount_in.saturating_sub(swap_amount);
+            let liquidity =
+                Self::shares_for_deposit(deposit, swapped, reserve_in_after, reserve_other_after, total_liquidity)?;
+            ensure!(liquidity > Zero::zero(), Error::<T>::InsufficientLiquidityMinted);
+            ensure!(liquidity >= min_liquidity, Error::<T>::InsufficientLiquidity);
+
+            // Pull the whole amount in and swap part of it inside the pool account
+            Self::transfer_asset(asset_in, &who, &Self::account_id(), amount_in, true)?;
+            Self::swap_hop(&who, asset_in, asset_other, swap_amount, swapped)?;
+
+            // Deposit the remainder together with the swap output
+            let (amount_a, amount_b) = if asset_in == pair.0 {
+                (deposit, swapped)
+            } else {
+                (swapped, deposit)
+            };
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::mutate(pair, |reserves| {
+                reserves.0 = reserves.0.saturating_add(amount_a);
+                reserves.1 = reserves.1.saturating_add(amount_b);
+                *reserves
+            });
+            Self::mint_liquidity_tokens(&who, pair, &pool, liquidity)?;
+
+            // Emit an event
+            Self::deposit_event(Event::LiquidityAdded {
+                who,
+                asset_a: pair.0,
+                asset_b: pair.1,
+                amount_a,
+                amount_b,
+                liquidity,
+                reserve_a,
+                reserve_b,
+            });
+
+            Ok(())
+        }
+
+        /// Remove liquidity from a constant product pool into a single asset. Both assets
+        /// are withdrawn and the `asset_other` side is swapped back into the pool for more
+        /// `asset_out`; only the combined amount is checked against `min_amount_out`. Fails
+        /// if included after block `deadline`, if one is given.
+        #[pallet::weight(T::WeightInfo::remove_liquidity_single_asset())]
+        pub fn remove_liquidity_single_asset(
+            origin: OriginFor<T>,
+            asset_out: T::AssetId,
+            asset_other: T::AssetId,
+            liquidity: BalanceOf<T>,
+            min_amount_out: BalanceOf<T>,
+            deadline: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(deadline)?;
+
+            let (pair, pool) = Self::resolve_pool(asset_out, asset_other)?;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            // The other side is swapped back in, so this is a trade as much as a withdrawal
+            Self::ensure_pool_open(pair)?;
+
+            // Ensure the liquidity amount is non-zero
+            ensure!(liquidity > Zero::zero(), Error::<T>::InvalidAmount);
+
+            // Fetch the current reserves, ordered for the swap back into `asset_out`
+            let (reserve_out, reserve_other, fee) = Self::get_reserves(asset_out, asset_other)?;
+            let total_liquidity = TotalLiquidity::<T>::get(pair);
+            ensure!(total_liquidity > Zero::zero(), Error::<T>::InsufficientLiquidity);
+
+            // Withdraw both sides, then quote swapping the other side against what is left
+            let (withdrawn_out, withdrawn_other) =
+                Self::withdrawal_for_shares(liquidity, reserve_out, reserve_other, total_liquidity)?;
+            let swapped = Self::get_amount_out(
+                withdrawn_other,
+                reserve_other.saturating_sub(withdrawn_other),
+                reserve_out.saturating_sub(withdrawn_out),
+                fee,
+            )?;
+            let amount_out = withdrawn_out.checked_add(&swapped).ok_or(Error::<T>::CalculationError)?;
+
+            // Ensure minimum amount out is satisfied
+            ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+            // Burn the provider's LP assets
+            Self::burn_liquidity_tokens(&who, pair, &pool, liquidity)?;
+
+            // Advance the price oracle with the reserves from before this withdrawal
+            Self::update_price_oracle(pair);
+
+            // Update the reserves
+            let (amount_a, amount_b) = if asset_out == pair.0 {
+                (withdrawn_out, withdrawn_other)
+            } else {
+                (withdrawn_other, withdrawn_out)
+            };
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::mutate(pair, |reserves| {
+                reserves.0 = reserves.0.saturating_sub(amount_a);
+                reserves.1 = reserves.1.saturating_sub(amount_b);
+                *reserves
+            });
+            Self::deposit_event(Event::LiquidityRemoved {
+                who: who.clone(),
+                asset_a: pair.0,
+                asset_b: pair.1,
+                amount_a,
+                amount_b,
+                liquidity,
+                reserve_a,
+                reserve_b,
+            });
+
+            // Swap the other side back in and pay out the single asset
+            Self::swap_hop(&who, asset_other, asset_out, withdrawn_other, swapped)?;
+            Self::transfer_asset(asset_out, &Self::account_id(), &who, amount_out, false)?;
+
+            Ok(())
+        }
+
+        /// Stop a pool from accepting trades and deposits. Providers can still withdraw.
+        /// StableSwap pools have no asset pair and are paused with `pause_stable_pool`.
+        #[pallet::weight(T::WeightInfo::pause_pool())]
+        pub fn pause_pool(origin: OriginFor<T>, asset_a: T::AssetId, asset_b: T::AssetId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            PausedPools::<T>::insert(pair, ());
+
+            Self::deposit_event(Event::PoolPaused(pair.0, pair.1));
+            Ok(())
+        }
+
+        /// Reopen a paused pool
+        #[pallet::weight(T::WeightInfo::unpause_pool())]
+        pub fn unpause_pool(origin: OriginFor<T>, asset_a: T::AssetId, asset_b: T::AssetId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            PausedPools::<T>::remove(pair);
+
+            Self::deposit_event(Event::PoolUnpaused(pair.0, pair.1));
+            Ok(())
+        }
+
+        /// Set the fee tier of a pool, within `MinPoolFee`..=`MaxPoolFee`. StableSwap pools
+        /// are repriced with `set_stable_pool_fee`.
+        #[pallet::weight(T::WeightInfo::set_pool_fee())]
+        pub fn set_pool_fee(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            fee_tier: Perquintill,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                fee_tier >= T::MinPoolFee::get() && fee_tier <= T::MaxPoolFee::get(),
+                Error::<T>::FeeOutOfBounds
+            );
+            let (pair, mut pool) = Self::resolve_pool(asset_a, asset_b)?;
+            pool.fee_tier = fee_tier;
+            Pools::<T>::insert(pair, pool);
+
+            Self::deposit_event(Event::PoolFeeSet(pair.0, pair.1, fee_tier));
+            Ok(())
+        }
+
+        /// Stop a StableSwap pool from accepting trades and deposits. Providers can still
+        /// withdraw.
+        #[pallet::weight(T::WeightInfo::pause_stable_pool())]
+        pub fn pause_stable_pool(origin: OriginFor<T>, pool_id: StablePoolId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            ensure!(StablePools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+            PausedStablePools::<T>::insert(pool_id, ());
+
+            Self::deposit_event(Event::StablePoolPaused(pool_id));
+            Ok(())
+        }
+
+        /// Reopen a paused StableSwap pool
+        #[pallet::weight(T::WeightInfo::unpause_stable_pool())]
+        pub fn unpause_stable_pool(origin: OriginFor<T>, pool_id: StablePoolId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            ensure!(StablePools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+            PausedStablePools::<T>::remove(pool_id);
+
+            Self::deposit_event(Event::StablePoolUnpaused(pool_id));
+            Ok(())
+        }
+
+        /// Set the fee tier of a StableSwap pool, within `MinPoolFee`..=`MaxPoolFee`
+        #[pallet::weight(T::WeightInfo::set_stable_pool_fee())]
+        pub fn set_stable_pool_fee(
+            origin: OriginFor<T>,
+            pool_id: StablePoolId,
+            fee_tier: Perquintill,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                fee_tier >= T::MinPoolFee::get() && fee_tier <= T::MaxPoolFee::get(),
+                Error::<T>::FeeOutOfBounds
+            );
+            StablePools::<T>::try_mutate(pool_id, |pool| -> DispatchResult {
+                let pool = pool.as_mut().ok_or(Error::<T>::PoolDoesNotExist)?;
+                pool.fee_tier = fee_tier;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::StablePoolFeeSet(pool_id, fee_tier));
+            Ok(())
+        }
+
+        /// Allow a denied asset to be pooled and traded again
+        #[pallet::weight(T::WeightInfo::allow_asset())]
+        pub fn allow_asset(origin: OriginFor<T>, asset: T::AssetId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            DeniedAssets::<T>::remove(asset);
+
+            Self::deposit_event(Event::AssetAllowed(asset));
+            Ok(())
+        }
+
+        /// Deny an asset: pools holding it reject trades and deposits and no new pool can
+        /// list it. Providers can still withdraw.
+        #[pallet::weight(T::WeightInfo::deny_asset())]
+        pub fn deny_asset(origin: OriginFor<T>, asset: T::AssetId) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            DeniedAssets::<T>::insert(asset, ());
+
+            Self::deposit_event(Event::AssetDenied(asset));
+            Ok(())
+        }
+
+        /// Emit `reward_per_block` of `reward_asset` to the LP shares staked in a pool's farm
+        /// from `start` until `end`. Replaces any schedule the farm already has for the
+        /// asset, keeping what was emitted so far. The farm account must hold the rewards.
+        #[pallet::weight(T::WeightInfo::set_reward_schedule())]
+        pub fn set_reward_schedule(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            reward_asset: T::AssetId,
+            reward_per_block: BalanceOf<T>,
+            start: T::BlockNumber,
+            end: T::BlockNumber,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(start < end && now < end, Error::<T>::InvalidRewardSchedule);
+            // The farm account must be able to receive non-sufficient reward assets
+            Self::ensure_provider(&Self::farm_account_id());
+
+            Farms::<T>::try_mutate(pair, |schedules| -> DispatchResult {
+                // Bring the farm up to date before the emission rate changes
+                Self::accrue_farm(pair, schedules, now)?;
+                match schedules.iter_mut().find(|schedule| schedule.reward_asset == reward_asset) {
+                    Some(schedule) => {
+                        schedule.reward_per_block = reward_per_block;
+                        schedule.start = start;
+                        schedule.end = end;
+                    },
+                    None => schedules
+                        .try_push(RewardSchedule {
+                            reward_asset,
+                            reward_per_block,
+                            start,
+                            end,
+                            reward_per_share: FixedU128::zero(),
+                            last_update: now,
+                        })
+                        .map_err(|_| Error::<T>::TooManyRewardAssets)?,
+                }
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::RewardScheduleSet(pair.0, pair.1, reward_asset, reward_per_block, start, end));
+            Ok(())
+        }
+
+        /// Stake LP shares of a pool in its farm to earn the farm's rewards
+        #[pallet::weight(T::WeightInfo::stake())]
+        pub fn stake(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Ensure the amount is non-zero and held by the staker
+            ensure!(amount > Zero::zero(), Error::<T>::InvalidAmount);
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            ensure!(T::Assets::balance(pool.lp_asset, &who) >= amount, Error::<T>::InsufficientBalance);
+
+            // Settle rewards on the old stake before it changes
+            Self::settle_staker(pair, &who)?;
+            // LP shares are not sufficient, so the farm account needs its own provider
+            Self::ensure_provider(&Self::farm_account_id());
+            Self::transfer_asset(pool.lp_asset, &who, &Self::farm_account_id(), amount, false)?;
+            Stakes::<T>::mutate(pair, &who, |staked| *staked = staked.saturating_add(amount));
+            TotalStaked::<T>::mutate(pair, |total| *total = total.saturating_add(amount));
+
+            Self::deposit_event(Event::Staked(who, pair.0, pair.1, amount));
+            Ok(())
+        }
+
+        /// Withdraw staked LP shares from a pool's farm. Earned rewards stay claimable.
+        #[pallet::weight(T::WeightInfo::unstake())]
+        pub fn unstake(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Ensure the amount is non-zero and staked
+            ensure!(amount > Zero::zero(), Error::<T>::InvalidAmount);
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            ensure!(Stakes::<T>::get(pair, &who) >= amount, Error::<T>::InsufficientBalance);
+
+            // Settle rewards on the old stake before it changes
+            Self::settle_staker(pair, &who)?;
+            Stakes::<T>::mutate(pair, &who, |staked| *staked = staked.saturating_sub(amount));
+            TotalStaked::<T>::mutate(pair, |total| *total = total.saturating_sub(amount));
+            Self::transfer_asset(pool.lp_asset, &Self::farm_account_id(), &who, amount, false)?;
+
+            Self::deposit_event(Event::Unstaked(who, pair.0, pair.1, amount));
+            Ok(())
+        }
+
+        /// Pay out every reward earned in a pool's farm
+        #[pallet::weight(T::WeightInfo::claim_rewards())]
+        pub fn claim_rewards(origin: OriginFor<T>, asset_a: T::AssetId, asset_b: T::AssetId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b)?;
+            let schedules = Self::settle_staker(pair, &who)?;
+
+            let mut rewards = Vec::with_capacity(schedules.len());
+            for schedule in schedules.iter() {
+                let key = (pair, who.clone(), schedule.reward_asset);
+                let amount = StakerRewards::<T>::mutate(key, |reward| sp_std::mem::take(&mut reward.pending));
+                if !amount.is_zero() {
+                    Self::transfer_asset(schedule.reward_asset, &Self::farm_account_id(), &who, amount, false)?;
+                    rewards.push((schedule.reward_asset, amount));
+                }
+            }
+            ensure!(!rewards.is_empty(), Error::<T>::NoRewards);
+
+            Self::deposit_event(Event::RewardsClaimed(who, pair.0, pair.1, rewards));
+            Ok(())
+        }
+
+        /// Borrow `amount_a_out` and `amount_b_out` from a constant product pool, dispatch
+        /// `call` as the borrower and then collect `amount_a_in` and `amount_b_in` from them.
+        /// The repayment must keep the pool's constant product after the pool's fee is taken
+        /// from the amounts paid in, so a plain loan of one asset costs the same fee as a swap.
+        /// The pool is locked while `call` runs and the whole call reverts if it fails.
+        #[pallet::weight({
+            let info = call.get_dispatch_info();
+            (T::WeightInfo::flash_swap().saturating_add(info.weight), info.class)
+        })]
+        pub fn flash_swap(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            amount_a_out: BalanceOf<T>,
+            amount_b_out: BalanceOf<T>,
+            amount_a_in: BalanceOf<T>,
+            amount_b_in: BalanceOf<T>,
+            call: Box<<T as Config>::RuntimeCall>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin.clone())?;
+
+            // Resolve the pool and line the amounts up with its canonical order
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            let (amount_a_out, amount_b_out, amount_a_in, amount_b_in) = if pair.0 == asset_a {
+                (amount_a_out, amount_b_out, amount_a_in, amount_b_in)
+            } else {
+                (amount_b_out, amount_a_out, amount_b_in, amount_a_in)
+            };
+            let (asset_a, asset_b) = pair;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            Self::ensure_pool_open(pair)?;
+
+            // Ensure something is borrowed and the pool can lend it
+            ensure!(amount_a_out > Zero::zero() || amount_b_out > Zero::zero(), Error::<T>::InvalidAmount);
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::get(pair);
+            ensure!(amount_a_out < reserve_a && amount_b_out < reserve_b, Error::<T>::InsufficientLiquidity);
+
+            // Lend optimistically and hand control to the borrower
+            FlashLocks::<T>::insert(pair, ());
+            if !amount_a_out.is_zero() {
+                Self::transfer_asset(asset_a, &Self::account_id(), &who, amount_a_out, false)?;
+            }
+            if !amount_b_out.is_zero() {
+                Self::transfer_asset(asset_b, &Self::account_id(), &who, amount_b_out, false)?;
+            }
+            call.dispatch(origin).map_err(|e| e.error)?;
+            FlashLocks::<T>::remove(pair);
+
+            // Collect the repayment
+            if !amount_a_in.is_zero() {
+                Self::transfer_asset(asset_a, &who, &Self::account_id(), amount_a_in, false)?;
+            }
+            if !amount_b_in.is_zero() {
+                Self::transfer_asset(asset_b, &who, &Self::account_id(), amount_b_in, false)?;
+            }
+
+            // The balances net of the fee on what was paid in must keep the constant product
+            let fee_a = pool.fee_tier.mul_floor(amount_a_in);
+            let fee_b = pool.fee_tier.mul_floor(amount_b_in);
+            let balance_a = reserve_a
+                .saturating_sub(amount_a_out)
+                .checked_add(&amount_a_in)
+                .ok_or(Error::<T>::CalculationError)?;
+            let balance_b = reserve_b
+                .saturating_sub(amount_b_out)
+                .checked_add(&amount_b_in)
+                .ok_or(Error::<T>::CalculationError)?;
+            let k_before = U256::from(reserve_a.saturated_into::<u128>()) * U256::from(reserve_b.saturated_into::<u128>());
+            let k_after = U256::from(balance_a.saturating_sub(fee_a).saturated_into::<u128>())
+                * U256::from(balance_b.saturating_sub(fee_b).saturated_into::<u128>());
+            ensure!(k_after >= k_before, Error::<T>::FlashSwapNotRepaid);
+
+            // Settle like a swap: the protocol's share of the fee is set aside, the rest stays
+            let protocol_fee_a = Self::accrue_protocol_fee(pair, asset_a, fee_a);
+            let protocol_fee_b = Self::accrue_protocol_fee(pair, asset_b, fee_b);
+            Self::update_price_oracle(pair);
+            LiquidityPools::<T>::insert(
+                pair,
+                (balance_a.saturating_sub(protocol_fee_a), balance_b.saturating_sub(protocol_fee_b)),
+            );
+            Self::record_pool_activity(
+                pair,
+                amount_a_out.saturating_add(amount_a_in),
+                amount_b_out.saturating_add(amount_b_in),
+                fee_a,
+                fee_b,
+            );
+
+            Self::deposit_event(Event::FlashSwap(
+                who,
+                asset_a,
+                asset_b,
+                amount_a_out,
+                amount_b_out,
+                amount_a_in,
+                amount_b_in,
+            ));
+            Ok(())
+        }
+
+        /// Place a limit order selling `amount_in` of `asset_in` for at least `min_amount_out`
+        /// of `asset_out` through their constant product pool. The input is held in escrow and
+        /// `OrderDeposit` is reserved until the order fills in `on_idle`, is cancelled or
+        /// passes `expires_at`. An order that can already fill does so at the end of the block.
+        #[pallet::weight(T::WeightInfo::place_order())]
+        pub fn place_order(
+            origin: OriginFor<T>,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            amount_in: BalanceOf<T>,
+            min_amount_out: BalanceOf<T>,
+            kind: OrderKind,
+            expires_at: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_not_expired(expires_at)?;
+
+            // Ensure the order is priced and its pool trades on reserves
+            ensure!(amount_in > Zero::zero() && min_amount_out > Zero::zero(), Error::<T>::InvalidAmount);
+            let (pair, _) = Self::resolve_pool(asset_in, asset_out)?;
+            ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+            Self::ensure_assets_allowed(&[asset_in, asset_out])?;
+
+            // Take the deposit and the input into escrow
+            let order_id = NextOrderId::<T>::get();
+            PoolOrders::<T>::try_append(pair, order_id).map_err(|_| Error::<T>::TooManyOrders)?;
+            let deposit = T::OrderDeposit::get();
+            T::Currency::reserve(&who, deposit)?;
+            Self::ensure_provider(&Self::order_account_id());
+            Self::transfer_asset(asset_in, &who, &Self::order_account_id(), amount_in, true)?;
+
+            let order = Order {
+                owner: who.clone(),
+                asset_in,
+                asset_out,
+                amount_in,
+                min_amount_out,
+                kind,
+                expires_at,
+                deposit,
+            };
+            Orders::<T>::insert(order_id, order);
+            NextOrderId::<T>::put(order_id.saturating_add(1));
+
+            Self::deposit_event(Event::OrderPlaced(order_id, who, asset_in, asset_out, amount_in, min_amount_out));
+            Ok(())
+        }
+
+        /// Cancel a resting order, refunding its input and deposit
+        #[pallet::weight(T::WeightInfo::cancel_order())]
+        pub fn cancel_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+            ensure!(order.owner == who, Error::<T>::NotOrderOwner);
+
+            Self::refund_order(order_id, &order)?;
+            Self::remove_from_book(Self::canonical_pair(order.asset_in, order.asset_out), order_id);
+
+            Self::deposit_event(Event::OrderCancelled(order_id, who));
+            Ok(())
+        }
+
+        /// Pay the LP shares the v1 migration held for `who` out to them, once they can hold
+        /// the pool's LP asset
+        #[pallet::weight(T::WeightInfo::release_held_shares())]
+        pub fn release_held_shares(
+            origin: OriginFor<T>,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            who: T::AccountId,
+        ) -> DispatchResult {
+            T::PoolAdminOrigin::ensure_origin(origin)?;
+
+            let (pair, pool) = Self::resolve_pool(asset_a, asset_b)?;
+            let key = (pair.0, pair.1, who.clone());
+            let shares = HeldShares::<T>::get(&key);
+            ensure!(!shares.is_zero(), Error::<T>::NoHeldShares);
+            Self::transfer_asset(pool.lp_asset, &Self::account_id(), &who, shares, false)?;
+            HeldShares::<T>::remove(key);
+
+            Self::deposit_event(Event::HeldSharesReleased(who, pair.0, pair.1, shares));
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Get the account ID for the pallet
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Ensure a pool accepts trades and deposits: it is not paused and neither of its
+        /// assets is denied
+        pub(crate) fn ensure_pool_open(pair: (T::AssetId, T::AssetId)) -> DispatchResult {
+            ensure!(!FlashLocks::<T>::contains_key(pair), Error::<T>::PoolLocked);
+            ensure!(!PausedPools::<T>::contains_key(pair), Error::<T>::PoolPaused);
+            Self::ensure_assets_allowed(&[pair.0, pair.1])
+        }
+
+        /// Ensure a StableSwap pool is not paused
+        pub(crate) fn ensure_stable_pool_open(pool_id: StablePoolId) -> DispatchResult {
+            ensure!(!PausedStablePools::<T>::contains_key(pool_id), Error::<T>::PoolPaused);
+            Ok(())
+        }
+
+        /// Ensure none of `assets` is denied
+        pub(crate) fn ensure_assets_allowed(assets: &[T::AssetId]) -> DispatchResult {
+            ensure!(
+                assets.iter().all(|asset| !DeniedAssets::<T>::contains_key(asset)),
+                Error::<T>::AssetNotAllowed
+            );
+            Ok(())
+        }
+
+        /// Reject a call included after its `deadline` block, if it set one
+        pub(crate) fn ensure_not_expired(deadline: Option<BlockNumberFor<T>>) -> DispatchResult {
+            if let Some(deadline) = deadline {
+                ensure!(frame_system::Pallet::<T>::block_number() <= deadline, Error::<T>::Expired);
+            }
+            Ok(())
+        }
+
+        /// Register a pool for an asset pair and its LP asset, returning the canonical pair
+        pub(crate) fn register_pool(
+            who: T::AccountId,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+        ) -> Result<(T::AssetId, T::AssetId), DispatchError> {
+            // Ensure the asset pair is valid
+            ensure!(asset_a != asset_b, Error::<T>::InvalidAssetPair);
+
+            let pair = Self::canonical_pair(asset_a, asset_b);
+            ensure!(!Pools::<T>::contains_key(pair), Error::<T>::PoolAlreadyExists);
+            Self::ensure_assets_allowed(&[asset_a, asset_b])?;
+
+            // Allocate the pool's LP asset, administered by the pallet account
+            let lp_asset = Self::allocate_lp_asset()?;
+
+            Pools::<T>::insert(pair, PoolInfo {
+                creator: who.clone(),
+                created_at: frame_system::Pallet::<T>::block_number(),
+                fee_tier: T::TradingFee::get(),
+                lp_asset,
+            });
+
+            Self::deposit_event(Event::PoolCreated(who, pair.0, pair.1, lp_asset));
+
+            Ok(pair)
+        }
+
+        /// A position owned by `who`
+        pub(crate) fn owned_position(who: &T::AccountId, position_id: PositionId) -> Result<PositionOf<T>, DispatchError> {
+            let position = Positions::<T>::get(position_id).ok_or(Error::<T>::PositionNotFound)?;
+            ensure!(position.owner == *who, Error::<T>::NotPositionOwner);
+            Ok(position)
+        }
+
+        /// Deposit the largest liquidity the maximum amounts can back into a position and
+        /// store it
+        pub(crate) fn increase_position(
+            position_id: PositionId,
+            mut position: PositionOf<T>,
+            mut state: ConcentratedPoolOf<T>,
+            amount_a_max: BalanceOf<T>,
+            amount_b_max: BalanceOf<T>,
+        ) -> DispatchResult {
+            let pair = position.pool;
+            let lower = concentrated::sqrt_price_at_tick(position.tick_lower);
+            let upper = concentrated::sqrt_price_at_tick(position.tick_upper);
+
+            // Calculate the liquidity to be added and what it costs, rounded in the pool's favour
+            let liquidity = concentrated::liquidity_for_amounts(
+                state.sqrt_price,
+                lower,
+                upper,
+                amount_a_max.saturated_into(),
+                amount_b_max.saturated_into(),
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            ensure!(liquidity > 0, Error::<T>::InsufficientLiquidityMinted);
+            let (amount_a, amount_b) = concentrated::amounts_for_liquidity(state.sqrt_price, lower, upper, liquidity, true)
+                .ok_or(Error::<T>::CalculationError)?;
+            let liquidity = Self::to_balance(liquidity)?;
+            let amount_a = Self::to_balance(amount_a)?;
+            let amount_b = Self::to_balance(amount_b)?;
+            ensure!(amount_a <= amount_a_max && amount_b <= amount_b_max, Error::<T>::SlippageExceeded);
+
+            // Transfer the assets from the owner into the pool
+            if !amount_a.is_zero() {
+                Self::transfer_asset(pair.0, &position.owner, &Self::account_id(), amount_a, true)?;
+            }
+            if !amount_b.is_zero() {
+                Self::transfer_asset(pair.1, &position.owner, &Self::account_id(), amount_b, true)?;
+            }
+
+            // Advance the price oracle before the reserves change
+            Self::update_price_oracle(pair);
+
+            Self::modify_position(&mut position, &mut state, liquidity, true)?;
+            Positions::<T>::insert(position_id, position);
+
+            LiquidityPools::<T>::try_mutate(pair, |reserves| -> DispatchResult {
+                reserves.0 = reserves.0.checked_add(&amount_a).ok_or(Error::<T>::CalculationError)?;
+                reserves.1 = reserves.1.checked_add(&amount_b).ok_or(Error::<T>::CalculationError)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::PositionIncreased(position_id, liquidity, amount_a, amount_b));
+
+            Ok(())
+        }
+
+        /// Take the next LP asset id and create the asset. The id is only consumed if the
+        /// asset was created.
+        pub(crate) fn allocate_lp_asset() -> Result<T::AssetId, DispatchError> {
+            let lp_asset = NextLpAssetId::<T>::get().unwrap_or_else(T::LpAssetIdStart::get);
+            let next_lp_asset = lp_asset
+                .checked_add(&One::one())
+                .ok_or(Error::<T>::CalculationError)?;
+            Self::create_lp_asset(lp_asset)?;
+            NextLpAssetId::<T>::put(next_lp_asset);
+            Ok(lp_asset)
+        }
+
+        /// Create a pool's LP asset with the pallet account as admin. The pallet account gets
+        /// a provider reference so it can hold the locked minimum liquidity.
+        pub(crate) fn create_lp_asset(lp_asset: T::AssetId) -> DispatchResult {
+            let pallet_account = Self::account_id();
+            Self::ensure_provider(&pallet_account);
+            T::Assets::create(lp_asset, pallet_account, false, One::one())
+        }
+
+        /// Give a pallet-owned account a provider reference, so it can hold LP shares and
+        /// other non-sufficient assets without a balance of a sufficient one
+        pub(crate) fn ensure_provider(who: &T::AccountId) {
+            if frame_system::Pallet::<T>::providers(who).is_zero() {
+                frame_system::Pallet::<T>::inc_providers(who);
+            }
+        }
+
+        /// Sort an asset pair into the canonical order used as the pool key
+        pub fn canonical_pair(asset_a: T::AssetId, asset_b: T::AssetId) -> (T::AssetId, T::AssetId) {
+            if asset_a < asset_b {
+                (asset_a, asset_b)
+            } else {
+                (asset_b, asset_a)
+            }
+        }
+
+        /// Look up the registered pool for an asset pair given in either order
+        pub fn resolve_pool(
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+        ) -> Result<((T::AssetId, T::AssetId), PoolInfoOf<T>), DispatchError> {
+            ensure!(asset_a != asset_b, Error::<T>::InvalidAssetPair);
+            let pair = Self::canonical_pair(asset_a, asset_b);
+            let pool = Pools::<T>::get(pair).ok_or(Error::<T>::PoolDoesNotExist)?;
+            Ok((pair, pool))
+        }
+
+        /// Current reserves of a pool, ordered as (reserve_in, reserve_out) for a trade
+        /// from `asset_in` to `asset_out`, together with the pool's fee tier
+        pub fn get_reserves(
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+        ) -> Result<(BalanceOf<T>, BalanceOf<T>, Perquintill), DispatchError> {
+            let (pair, pool) = Self::resolve_pool(asset_in, asset_out)?;
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::get(pair);
+
+            // Ensure the pool has liquidity
+            ensure!(reserve_a > Zero::zero() && reserve_b > Zero::zero(), Error::<T>::InsufficientLiquidity);
+
+            if asset_in == pair.0 {
+                Ok((reserve_a, reserve_b, pool.fee_tier))
+            } else {
+                Ok((reserve_b, reserve_a, pool.fee_tier))
+            }
+        }
+
+        /// Output of a single hop for an exact input, using the constant product formula
+        /// (reserve_in + amount_in_after_fee) * (reserve_out - amount_out) >= reserve_in * reserve_out
+        pub fn get_amount_out(
+            amount_in: BalanceOf<T>,
+            reserve_in: BalanceOf<T>,
+            reserve_out: BalanceOf<T>,
+            fee: Perquintill,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            let amount_out = math::amount_out(
+                amount_in.saturated_into(),
+                reserve_in.saturated_into(),
+                reserve_out.saturated_into(),
+                fee,
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+
+            ensure!(amount_out > 0, Error::<T>::InsufficientLiquidity);
+            Self::to_balance(amount_out)
+        }
+
+        /// Input of a single hop for an exact output, the inverse of `get_amount_out`
+        /// rounded up so the pool never loses to rounding
+        pub fn get_amount_in(
+            amount_out: BalanceOf<T>,
+            reserve_in: BalanceOf<T>,
+            reserve_out: BalanceOf<T>,
+            fee: Perquintill,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            ensure!(amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+
+            let amount_in = math::amount_in(
+                amount_out.saturated_into(),
+                reserve_in.saturated_into(),
+                reserve_out.saturated_into(),
+                fee,
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            Self::to_balance(amount_in)
+        }
+
+        /// Amounts flowing through every hop of `path` for an exact input.
+        /// `amounts[0]` is `amount_in` and `amounts[i + 1]` is the output of hop `i`.
+        pub fn get_amounts_out(
+            path: &[T::AssetId],
+            amount_in: BalanceOf<T>,
+        ) -> Result<Vec<BalanceOf<T>>, DispatchError> {
+            Self::ensure_valid_path(path)?;
+
+            let mut amounts = Vec::with_capacity(path.len());
+            amounts.push(amount_in);
+            for hop in path.windows(2) {
+                let previous = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
+                let (pair, pool) = Self::resolve_pool(hop[0], hop[1])?;
+                Self::ensure_pool_open(pair)?;
+                let amount_out = if ConcentratedPools::<T>::contains_key(pair) {
+                    Self::concentrated_swap(pair, pool.fee_tier, hop[0], previous, false)?.amount_out
+                } else {
+                    let (reserve_in, reserve_out, fee) = Self::get_reserves(hop[0], hop[1])?;
+                    Self::get_amount_out(previous, reserve_in, reserve_out, fee)?
+                };
+                amounts.push(amount_out);
+            }
+            Ok(amounts)
+        }
+
+        /// Amounts flowing through every hop of `path` for an exact output.
+        /// `amounts[0]` is the required input and the last entry is `amount_out`.
+        pub fn get_amounts_in(
+            path: &[T::AssetId],
+            amount_out: BalanceOf<T>,
+        ) -> Result<Vec<BalanceOf<T>>, DispatchError> {
+            Self::ensure_valid_path(path)?;
+
+            let mut amounts = Vec::with_capacity(path.len());
+            amounts.push(amount_out);
+            for hop in path.windows(2).rev() {
+                // Exact-output quotes are only supported on the constant product curve
+                let (pair, _) = Self::resolve_pool(hop[0], hop[1])?;
+                ensure!(!ConcentratedPools::<T>::contains_key(pair), Error::<T>::WrongPoolKind);
+                Self::ensure_pool_open(pair)?;
+                let (reserve_in, reserve_out, fee) = Self::get_reserves(hop[0], hop[1])?;
+                let next = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
+                amounts.push(Self::get_amount_in(next, reserve_in, reserve_out, fee)?);
+            }
+            amounts.reverse();
+            Ok(amounts)
+        }
+
+        /// Ensure `path` has at least two assets and visits each of them once. Every hop is
+        /// quoted before the route runs, so a pool visited twice would settle its second hop
+        /// at a quote that ignores the first.
+        fn ensure_valid_path(path: &[T::AssetId]) -> DispatchResult {
+            ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+            ensure!(
+                path.iter().enumerate().all(|(i, asset)| !path[..i].contains(asset)),
+                Error::<T>::PathRepeatsAsset
+            );
+            Ok(())
+        }
+
+        /// Shares minted by the first deposit into a pool: the geometric mean sqrt(a * b),
+        /// which is independent of the assets' decimals
+        pub fn initial_liquidity(
+            amount_a: BalanceOf<T>,
+            amount_b: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            Self::to_balance(math::initial_shares(amount_a.saturated_into(), amount_b.saturated_into()))
+        }
+
+        /// Shares a deposit into a funded pool is worth: the smaller of the two proportional
+        /// claims it makes on the existing supply
+        pub(crate) fn shares_for_deposit(
+            amount_a: BalanceOf<T>,
+            amount_b: BalanceOf<T>,
+            reserve_a: BalanceOf<T>,
+            reserve_b: BalanceOf<T>,
+            total_liquidity: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            let shares = math::shares_for_deposit(
+                amount_a.saturated_into(),
+                amount_b.saturated_into(),
+                reserve_a.saturated_into(),
+                reserve_b.saturated_into(),
+                total_liquidity.saturated_into(),
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            Self::to_balance(shares)
+        }
+
+        /// Amounts of both assets paid out for burning `liquidity` shares, rounded down
+        pub(crate) fn withdrawal_for_shares(
+            liquidity: BalanceOf<T>,
+            reserve_a: BalanceOf<T>,
+            reserve_b: BalanceOf<T>,
+            total_liquidity: BalanceOf<T>,
+        ) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+            let (amount_a, amount_b) = math::withdrawal_for_shares(
+                liquidity.saturated_into(),
+                reserve_a.saturated_into(),
+                reserve_b.saturated_into(),
+                total_liquidity.saturated_into(),
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            Ok((Self::to_balance(amount_a)?, Self::to_balance(amount_b)?))
+        }
+
+        /// Part of a single-asset deposit of `amount_in` to swap through a pool holding
+        /// `reserve_in`, so that the rest lines up with the reserves after the trade and
+        /// its protocol fee has left them
+        pub fn optimal_zap_amount(
+            reserve_in: BalanceOf<T>,
+            amount_in: BalanceOf<T>,
+            fee: Perquintill,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            // Perbill parts scale to Perquintill parts exactly
+            let protocol_share = Perquintill::from_parts(
+                (T::ProtocolFeeShare::get().deconstruct() as u64).saturating_mul(1_000_000_000),
+            );
+            let amount = math::optimal_zap_amount(
+                reserve_in.saturated_into(),
+                amount_in.saturated_into(),
+                fee,
+                protocol_share,
+            )
+            .ok_or(Error::<T>::CalculationError)?;
+            Self::to_balance(amount)
+        }
+
+        /// Runtime API: output of swapping an exact `amount_in` along `path`
+        pub fn quote_price_exact_in(path: Vec<T::AssetId>, amount_in: BalanceOf<T>) -> Option<BalanceOf<T>> {
+            if path.len() > T::MaxSwapPathLength::get() as usize || amount_in.is_zero() {
+                return None;
+            }
+            Self::get_amounts_out(&path, amount_in).ok()?.last().copied()
+        }
+
+        /// Runtime API: input needed to receive an exact `amount_out` at the end of `path`
+        pub fn quote_price_exact_out(path: Vec<T::AssetId>, amount_out: BalanceOf<T>) -> Option<BalanceOf<T>> {
+            if path.len() > T::MaxSwapPathLength::get() as usize || amount_out.is_zero() {
+                return None;
+            }
+            Self::get_amounts_in(&path, amount_out).ok()?.first().copied()
+        }
+
+        /// Runtime API: reserves of the pool for `(asset_a, asset_b)`, in the order given
+        pub fn pool_reserves(asset_a: T::AssetId, asset_b: T::AssetId) -> Option<(BalanceOf<T>, BalanceOf<T>)> {
+            let (pair, _) = Self::resolve_pool(asset_a, asset_b).ok()?;
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::get(pair);
+            if asset_a == pair.0 {
+                Some((reserve_a, reserve_b))
+            } else {
+                Some((reserve_b, reserve_a))
+            }
+        }
+
+        /// Runtime API: LP share balance of `who` in the pool for `(asset_a, asset_b)`
+        pub fn lp_balance(who: T::AccountId, asset_a: T::AssetId, asset_b: T::AssetId) -> BalanceOf<T> {
+            Self::resolve_pool(asset_a, asset_b)
+                .map(|(_, pool)| T::Assets::balance(pool.lp_asset, &who))
+                .unwrap_or_else(|_| Zero::zero())
+        }
+
+        /// Execute a quoted route. The input is pulled from `who` once, every hop settles
+        /// inside the pallet account and only the final output is paid out, so the whole
+        /// route either succeeds or is reverted together with the extrinsic. Returns the
+        /// weight of the route's concentrated hops on top of its constant product cost.
+        pub(crate) fn do_swap(
+            who: &T::AccountId,
+            path: &[T::AssetId],
+            amounts: &[BalanceOf<T>],
+        ) -> Result<Weight, DispatchError> {
+            ensure!(path.len() >= 2 && path.len() == amounts.len(), Error::<T>::InvalidPath);
+
+            let asset_in = path[0];
+            let asset_out = path[path.len() - 1];
+            let amount_in = amounts[0];
+            let amount_out = amounts[amounts.len() - 1];
+
+            // Transfer the input asset from the user into the pool
+            Self::transfer_asset(asset_in, who, &Self::account_id(), amount_in, true)?;
+
+            let mut concentrated_weight = Weight::zero();
+            for (i, hop) in path.windows(2).enumerate() {
+                if let Some(ticks_crossed) = Self::swap_hop(who, hop[0], hop[1], amounts[i], amounts[i + 1])? {
+                    concentrated_weight =
+                        concentrated_weight.saturating_add(T::WeightInfo::concentrated_swap_hop(ticks_crossed));
+                }
+            }
+
+            // Transfer the output asset to the user
+            Self::transfer_asset(asset_out, &Self::account_id(), who, amount_out, false)?;
+
+            Ok(concentrated_weight)
+        }
+
+        /// Set the protocol's share of a trading fee aside for the pool and return it.
+        /// The remainder of the fee stays in the reserves for LPs.
+        pub(crate) fn accrue_protocol_fee(
+            pair: (T::AssetId, T::AssetId),
+            asset: T::AssetId,
+            fee_amount: BalanceOf<T>,
+        ) -> BalanceOf<T> {
+            let protocol_fee = T::ProtocolFeeShare::get().mul_floor(fee_amount);
+            if !protocol_fee.is_zero() {
+                ProtocolFees::<T>::mutate(pair, asset, |accrued| {
+                    *accrued = accrued.saturating_add(protocol_fee);
+                });
+            }
+            protocol_fee
+        }
+
+        /// Spot prices (price of asset_a in asset_b, price of asset_b in asset_a) of a pool,
+        /// from its reserves or, for concentrated liquidity pools, its current price
+        fn spot_prices(pair: (T::AssetId, T::AssetId)) -> Option<(FixedU128, FixedU128)> {
+            if let Some(state) = ConcentratedPools::<T>::get(pair) {
+                let price = state.sqrt_price.saturating_mul(state.sqrt_price);
+                return Some((price, price.reciprocal()?));
+            }
+            let (reserve_a, reserve_b) = LiquidityPools::<T>::get(pair);
+            let reserve_a = reserve_a.saturated_into::<u128>();
+            let reserve_b = reserve_b.saturated_into::<u128>();
+            Some((
+                FixedU128::checked_from_rational(reserve_b, reserve_a)?,
+                FixedU128::checked_from_rational(reserve_a, reserve_b)?,
+            ))
+        }
+
+        /// Advance cumulative prices to `now` at the pool's current prices, i.e. as if those
+        /// prices held for every block since the last update
+        fn advance_cumulative(
+            mut cumulative: PriceCumulative<T::BlockNumber>,
+            pair: (T::AssetId, T::AssetId),
+            now: T::BlockNumber,
+        ) -> PriceCumulative<T::BlockNumber> {
+            if cumulative.last_update >= now {
+                return cumulative;
+            }
+            if let Some((price_a, price_b)) = Self::spot_prices(pair) {
+                let elapsed = FixedU128::saturating_from_integer(
+                    now.saturating_sub(cumulative.last_update).saturated_into::<u128>(),
+                );
+                cumulative.price_a_cumulative = FixedU128::from_inner(
+                    cumulative.price_a_cumulative.into_inner()
+                        .wrapping_add(price_a.saturating_mul(elapsed).into_inner()),
+                );
+                cumulative.price_b_cumulative = FixedU128::from_inner(
+                    cumulative.price_b_cumulative.into_inner()
+                        .wrapping_add(price_b.saturating_mul(elapsed).into_inner()),
+                );
+            }
+            cumulative.last_update = now;
+            cumulative
+        }
+
+        /// Advance a pool's cumulative prices to the current block and snapshot them. Must run
+        /// before the reserves change, so a price only counts once it has survived into a
+        /// later block and same-block manipulation never reaches the oracle.
+        pub(crate) fn update_price_oracle(pair: (T::AssetId, T::AssetId)) {
+            let now = frame_system::Pallet::<T>::block_number();
+            let stored = match PriceCumulatives::<T>::get(pair) {
+                Some(stored) if stored.last_update >= now => return,
+                Some(stored) => stored,
+                None => {
+                    // The first reserve change of a pool starts the clock
+                    PriceCumulatives::<T>::insert(pair, PriceCumulative {
+                        last_update: now,
+                        ..Default::default()
+                    });
+                    return;
+                },
+            };
+
+            let cumulative = Self::advance_cumulative(stored, pair, now);
+            PriceObservations::<T>::mutate(pair, |observations| {
+                if observations.len() as u32 >= T::MaxPriceObservations::get() {
+                    observations.remove(0);
+                }
+                let _ = observations.try_push(cumulative.clone());
+            });
+            PriceCumulatives::<T>::insert(pair, cumulative);
+        }
+
+        /// Time-weighted average price of `asset_in` in units of `asset_out` over at least the
+        /// last `window` blocks. Averages from the newest observation at or before
+        /// `now - window`, so the result covers `window` blocks or slightly more on quiet
+        /// pools. `None` if the pool has not been observed that far back.
+        pub fn consult(
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            window: T::BlockNumber,
+        ) -> Option<FixedU128> {
+            if window.is_zero() {
+                return None;
+            }
+            let (pair, _) = Self::resolve_pool(asset_in, asset_out).ok()?;
+            let now = frame_system::Pallet::<T>::block_number();
+            let cutoff = now.checked_sub(&window)?;
+
+            let current = Self::advance_cumulative(PriceCumulatives::<T>::get(pair)?, pair, now);
+            let past = PriceObservations::<T>::get(pair)
+                .into_iter()
+                .rev()
+                .find(|observation| observation.last_update <= cutoff)?;
+
+            let elapsed = now.saturating_sub(past.last_update).saturated_into::<u128>();
+            let (current, past) = if asset_in == pair.0 {
+                (current.price_a_cumulative, past.price_a_cumulative)
+            } else {
+                (current.price_b_cumulative, past.price_b_cumulative)
+            };
+            let sum = FixedU128::from_inner(current.into_inner().wrapping_sub(past.into_inner()));
+            sum.checked_div(&FixedU128::saturating_from_integer(elapsed))
+        }
+
+        /// Settle one hop of a route against the pool reserves and report its fee. Returns the
+        /// number of ticks crossed if the hop went through a concentrated pool.
+        pub(crate) fn swap_hop(
+            who: &T::AccountId,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            amount_in: BalanceOf<T>,
+            amount_out: BalanceOf<T>,
+        ) -> Result<Option<u32>, DispatchError> {
+            let (pair, pool) = Self::resolve_pool(asset_in, asset_out)?;
+            if ConcentratedPools::<T>::contains_key(pair) {
+                Self::update_price_oracle(pair);
+                let result = Self::concentrated_swap(pair, pool.fee_tier, asset_in, amount_in, true)?;
+                ensure!(result.amount_out == amount_out, Error::<T>::InvalidPath);
+                Self::record_swap(pair, asset_in, amount_in, amount_out, result.fee_amount);
+                let (reserve_in, reserve_out) = Self::ordered_reserves(pair, asset_in);
+                Self::deposit_event(Event::Swap {
+                    who: who.clone(),
+                    asset_in,
+                    asset_out,
+                    amount_in,
+                    amount_out,
+                    fee_amount: result.fee_amount,
+                    reserve_in,
+                    reserve_out,
+                });
+                Self::deposit_event(Event::FeeCollected(asset_in, result.fee_amount));
+                return Ok(Some(result.ticks_crossed));
+            }
+
+            let fee_amount = pool.fee_tier.mul_floor(amount_in);
+            let protocol_fee = Self::accrue_protocol_fee(pair, asset_in, fee_amount);
+            let amount_to_reserve = amount_in.saturating_sub(protocol_fee);
+
+            Self::update_price_oracle(pair);
+            let (reserve_in, reserve_out) = LiquidityPools::<T>::try_mutate(pair, |reserves| -> Result<_, DispatchError> {
+                let (reserve_in, reserve_out) = if asset_in == pair.0 {
+                    (&mut reserves.0, &mut reserves.1)
+                } else {
+                    (&mut reserves.1, &mut reserves.0)
+                };
+                ensure!(amount_out < *reserve_out, Error::<T>::InsufficientLiquidity);
+                *reserve_in = reserve_in.checked_add(&amount_to_reserve).ok_or(Error::<T>::CalculationError)?;
+                *reserve_out = reserve_out.saturating_sub(amount_out);
+                Ok((*reserve_in, *reserve_out))
+            })?;
+            Self::record_swap(pair, asset_in, amount_in, amount_out, fee_amount);
+
+            // Emit events
+            Self::deposit_event(Event::Swap {
+                who: who.clone(),
+                asset_in,
+                asset_out,
+                amount_in,
+                amount_out,
+                fee_amount,
+                reserve_in,
+                reserve_out,
+            });
+            Self::deposit_event(Event::FeeCollected(asset_in, fee_amount));
+
+            Ok(None)
+        }
+
+        /// Move `amount` of `asset` between two accounts, routing the native
+        /// asset through `Currency` and everything else through `Assets`
+        pub(crate) fn transfer_asset(
+            asset: T::AssetId,
+            from: &T::AccountId,
+            to: &T::AccountId,
+            amount: BalanceOf<T>,
+            keep_alive: bool,
+        ) -> DispatchResult {
+            if asset == T::NativeAssetId::get() {
+                let existence = if keep_alive {
+                    ExistenceRequirement::KeepAlive
+                } else {
+                    ExistenceRequirement::AllowDeath
+                };
+                T::Currency::transfer(from, to, amount, existence)
+            } else {
+                <T::Assets as FungiblesTransfer<T::AccountId>>::transfer(asset, from, to, amount, keep_alive)
+                    .map(|_| ())
+            }
+        }
+
+        /// Free balance of `asset` held by `who`
+        pub fn asset_balance(asset: T::AssetId, who: &T::AccountId) -> BalanceOf<T> {
+            if asset == T::NativeAssetId::get() {
+                T::Currency::free_balance(who)
+            } else {
+                <T::Assets as FungiblesInspect<T::AccountId>>::balance(asset, who)
+            }
+        }
+
+        /// Mint a pool's LP asset to a user, keeping `TotalLiquidity` in step
+        pub(crate) fn mint_liquidity_tokens(
+            who: &T::AccountId,
+            pair: (T::AssetId, T::AssetId),
+            pool: &PoolInfoOf<T>,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            T::Assets::mint_into(pool.lp_asset, who, amount)?;
+            TotalLiquidity::<T>::mutate(pair, |total| {
+                *total = total.saturating_add(amount);
+            });
+            Ok(())
+        }
+
+        /// Burn a pool's LP asset from a user, keeping `TotalLiquidity` in step
+        pub(crate) fn burn_liquidity_tokens(
+            who: &T::AccountId,
+            pair: (T::AssetId, T::AssetId),
+            pool: &PoolInfoOf<T>,
+            amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            ensure!(T::Assets::balance(pool.lp_asset, who) >= amount, Error::<T>::InsufficientBalance);
+            T::Assets::burn_from(pool.lp_asset, who, amount)?;
+            TotalLiquidity::<T>::mutate(pair, |total| {
+                *total = total.saturating_sub(amount);
+            });
+            Ok(())
         }
     }
 }
@@ -0,0 +1,136 @@
+//! Pool arithmetic for `pallet-liquidity-pool`.
+//!
+//! Pure functions over `u128` amounts. Every product is formed in 256-bit precision, so a
+//! pool whose reserves approach `u128::MAX` still quotes, mints and redeems; `None` only
+//! means the final result does not fit in 128 bits or a denominator is zero. Rounding always
+//! favours the pool: outputs round down and required inputs round up.
+//!
+//! The pallet wraps these with its own balance type and errors. They are public so the
+//! fuzz targets in `fuzz/` can drive them against a reference big-integer implementation.
+
+use sp_arithmetic::{PerThing, Perquintill};
+use sp_core::{U256, U512};
+
+/// `a * b / denominator` in 256-bit precision, rounded up or down. `None` on division by
+/// zero or if the result does not fit in 128 bits.
+pub fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let product = U256::from(a) * U256::from(b);
+    let denominator = U256::from(denominator);
+    let (mut quotient, remainder) = product.div_mod(denominator);
+    if round_up && !remainder.is_zero() {
+        quotient = quotient.checked_add(U256::one())?;
+    }
+    quotient.try_into().ok()
+}
+
+/// Output of a constant product pool for an exact `amount_in`, with `fee` taken from the
+/// input and rounded down
+///
+///     amount_out = amount_in_after_fee * reserve_out / (reserve_in + amount_in_after_fee)
+pub fn amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+    let amount_in_after_fee = amount_in.saturating_sub(fee.mul_floor(amount_in));
+    let numerator = U256::from(amount_in_after_fee) * U256::from(reserve_out);
+    let denominator = U256::from(reserve_in) + U256::from(amount_in_after_fee);
+    if denominator.is_zero() {
+        return None;
+    }
+    (numerator / denominator).try_into().ok()
+}
+
+/// Input a constant product pool needs to pay out an exact `amount_out`, the inverse of
+/// [`amount_out`] rounded up. `None` if `amount_out` is not below `reserve_out`, the fee
+/// takes the whole input or the input does not fit in 128 bits.
+///
+///     amount_in_after_fee = reserve_in * amount_out / (reserve_out - amount_out) + 1
+///     amount_in = ceil(amount_in_after_fee / (1 - fee))
+pub fn amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+    if amount_out >= reserve_out {
+        return None;
+    }
+    let amount_in_after_fee: u128 = (U256::from(reserve_in) * U256::from(amount_out)
+        / U256::from(reserve_out - amount_out)
+        + U256::one())
+    .try_into()
+    .ok()?;
+
+    // Gross the input up so that the fee taken by `amount_out` leaves enough behind
+    let retained = fee.left_from_one().deconstruct() as u128;
+    mul_div(amount_in_after_fee, Perquintill::ACCURACY as u128, retained, true)
+}
+
+/// Shares minted by the first deposit into a pool: the geometric mean `sqrt(a * b)`,
+/// rounded down. Always fits in 128 bits.
+pub fn initial_shares(amount_a: u128, amount_b: u128) -> u128 {
+    let root = (U256::from(amount_a) * U256::from(amount_b)).integer_sqrt();
+    root.low_u128()
+}
+
+/// Shares a deposit into a funded pool is worth: the smaller of the two proportional claims
+/// it makes on `total_shares`, rounded down
+pub fn shares_for_deposit(
+    amount_a: u128,
+    amount_b: u128,
+    reserve_a: u128,
+    reserve_b: u128,
+    total_shares: u128,
+) -> Option<u128> {
+    let shares_a = mul_div(amount_a, total_shares, reserve_a, false)?;
+    let shares_b = mul_div(amount_b, total_shares, reserve_b, false)?;
+    Some(shares_a.min(shares_b))
+}
+
+/// Amounts of both assets paid out for burning `shares` of `total_shares`, rounded down
+pub fn withdrawal_for_shares(
+    shares: u128,
+    reserve_a: u128,
+    reserve_b: u128,
+    total_shares: u128,
+) -> Option<(u128, u128)> {
+    let amount_a = mul_div(shares, reserve_a, total_shares, false)?;
+    let amount_b = mul_div(shares, reserve_b, total_shares, false)?;
+    Some((amount_a, amount_b))
+}
+
+/// Part of a single-asset deposit of `amount_in` to swap through a pool holding
+/// `reserve_in`, so that the rest lines up with the reserves after the trade.
+/// The `protocol_share` of the fee leaves the pool and the rest stays in the reserves, so
+/// with `g = 1 - protocol_share * f` the swap `s` solves
+///
+///     (1 - f) * g * s^2 + (2 - f) * reserve_in * s - amount_in * reserve_in = 0
+///
+/// The rates are taken to the nearest millionth and the root is formed in 512-bit
+/// precision, so even reserves near `u128::MAX` can be zapped into.
+pub fn optimal_zap_amount(
+    reserve_in: u128,
+    amount_in: u128,
+    fee: Perquintill,
+    protocol_share: Perquintill,
+) -> Option<u128> {
+    const PRECISION: u64 = 1_000_000;
+    let to_precision = |rate: Perquintill| rate.deconstruct() / (Perquintill::ACCURACY / PRECISION);
+    let precision = U512::from(PRECISION);
+    let retained = U512::from(to_precision(fee.left_from_one()));
+    if retained.is_zero() {
+        return None;
+    }
+    let fee_to_protocol = to_precision(fee) * to_precision(protocol_share) / PRECISION;
+    let kept = U512::from(PRECISION - fee_to_protocol);
+
+    let reserve_in = U512::from(reserve_in);
+    let amount_in = U512::from(amount_in);
+
+    // s = (sqrt(b^2 + 4 * a * amount_in * reserve_in) - b) / (2 * a), with a = (1 - f) * g
+    // and b = (2 - f) * reserve_in. Every rate is scaled by PRECISION, so a and b carry
+    // PRECISION^2. For 128-bit amounts, b^2 and the second term stay below 2^340.
+    let a = retained * kept;
+    let b = reserve_in * (precision + retained) * precision;
+    let discriminant = b * b + U512::from(4u8) * a * amount_in * reserve_in * precision * precision;
+    let amount = (discriminant.integer_sqrt() - b) / (a * 2);
+    if amount > U512::from(u128::MAX) {
+        return None;
+    }
+    Some(amount.low_u128())
+}
@@ -0,0 +1,297 @@
+//! Storage migrations for `pallet-liquidity-pool`.
+
+use super::*;
+use frame_support::{
+    pallet_prelude::*,
+    storage_alias,
+    traits::{
+        fungibles::Mutate,
+        GetStorageVersion, OnRuntimeUpgrade, StorageVersion,
+    },
+};
+use sp_runtime::{
+    traits::{Saturating, Zero},
+    SaturatedConversion,
+};
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+
+/// v0 -> v1: pool shares move from the internal `LiquidityTokens` map into real LP assets.
+pub mod v1 {
+    use super::*;
+
+    /// The v0 share ledger. Maps (asset_a, asset_b, account) -> liquidity_tokens
+    #[storage_alias]
+    pub type LiquidityTokens<T: Config> = StorageMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        (<T as Config>::AssetId, <T as Config>::AssetId, <T as frame_system::Config>::AccountId),
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+
+    /// Mints every `LiquidityTokens` balance as the pool's LP asset and clears the old map.
+    ///
+    /// v0 keyed pools by the caller's asset order, so one pair may have two pools. A pool
+    /// stored under the non-canonical `(b, a)` key is merged into the canonical entry with its
+    /// reserves swapped into canonical order, and its shares are converted at the rate a
+    /// deposit of its reserves into the canonical pool would mint. Every pool with reserves
+    /// gets a registry entry and LP asset, and `TotalLiquidity` ends up equal to the LP
+    /// asset's issuance.
+    ///
+    /// A balance is only removed from `LiquidityTokens` once its shares are minted. Shares
+    /// that cannot be minted to their owner are minted to the pallet account and recorded
+    /// in `HeldShares`, from where `release_held_shares` pays them out.
+    ///
+    /// v0 moved every pooled asset through the native currency, while v1 pays non-native
+    /// reserves out of the assets provider. The migration does not move any funds: the
+    /// runtime must credit the pallet account with each non-native asset's reserves before
+    /// it runs, which `post_upgrade` checks.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> MigrateToV1<T> {
+        /// Registry entry for `pair`, registering the pool and its LP asset if it is missing
+        fn ensure_registered(pair: (T::AssetId, T::AssetId), weight: &mut Weight) -> Option<PoolInfoOf<T>> {
+            *weight = weight.saturating_add(T::DbWeight::get().reads(1));
+            if let Some(pool) = Pools::<T>::get(pair) {
+                return Some(pool);
+            }
+
+            let lp_asset = match Pallet::<T>::allocate_lp_asset() {
+                Ok(lp_asset) => lp_asset,
+                Err(error) => {
+                    log::error!(target: "runtime::liquidity-pool", "could not allocate an LP asset: {:?}", error);
+                    return None;
+                },
+            };
+
+            let pool = PoolInfo {
+                creator: Pallet::<T>::account_id(),
+                created_at: frame_system::Pallet::<T>::block_number(),
+                fee_tier: T::TradingFee::get(),
+                lp_asset,
+            };
+            Pools::<T>::insert(pair, pool.clone());
+            *weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 4));
+            Some(pool)
+        }
+
+        /// Merge every pool stored under a non-canonical key into its canonical entry and
+        /// register every pool. Returns the (canonical shares, v0 shares) conversion rate of
+        /// each merged key.
+        fn merge_pools(
+            weight: &mut Weight,
+        ) -> BTreeMap<(T::AssetId, T::AssetId), (BalanceOf<T>, BalanceOf<T>)> {
+            let mut rates = BTreeMap::new();
+            let keys: Vec<_> = LiquidityPools::<T>::iter_keys().collect();
+            *weight = weight.saturating_add(T::DbWeight::get().reads(keys.len() as u64));
+
+            for key in keys {
+                let pair = Pallet::<T>::canonical_pair(key.0, key.1);
+                if Self::ensure_registered(pair, weight).is_none() {
+                    continue;
+                }
+                if key == pair {
+                    continue;
+                }
+
+                // Reserves of a (b, a) pool are stored as (reserve_b, reserve_a)
+                let (reserve_b, reserve_a) = LiquidityPools::<T>::get(key);
+                let v0_total = TotalLiquidity::<T>::get(key);
+                let (canonical_a, canonical_b) = LiquidityPools::<T>::get(pair);
+                let canonical_total = TotalLiquidity::<T>::get(pair);
+                let shares = if canonical_total.is_zero() || canonical_a.is_zero() || canonical_b.is_zero() {
+                    v0_total
+                } else {
+                    match Pallet::<T>::shares_for_deposit(reserve_a, reserve_b, canonical_a, canonical_b, canonical_total)
+                    {
+                        Ok(shares) => shares,
+                        Err(e) => {
+                            log::error!(
+                                target: "runtime::liquidity-pool",
+                                "could not merge pool {:?} into {:?}: {:?}",
+                                key,
+                                pair,
+                                e
+                            );
+                            continue;
+                        },
+                    }
+                };
+
+                LiquidityPools::<T>::remove(key);
+                TotalLiquidity::<T>::remove(key);
+                LiquidityPools::<T>::insert(
+                    pair,
+                    (canonical_a.saturating_add(reserve_a), canonical_b.saturating_add(reserve_b)),
+                );
+                TotalLiquidity::<T>::insert(pair, canonical_total.saturating_add(shares));
+                rates.insert(key, (shares, v0_total));
+                *weight = weight.saturating_add(T::DbWeight::get().reads_writes(4, 4));
+            }
+            rates
+        }
+
+        /// Mint `shares` of `pool` to `who`, or to the pallet account with a `HeldShares`
+        /// record if `who` cannot hold them. False if neither mint succeeds.
+        fn mint_shares(
+            pair: (T::AssetId, T::AssetId),
+            pool: &PoolInfoOf<T>,
+            who: &T::AccountId,
+            shares: BalanceOf<T>,
+        ) -> bool {
+            let error = match T::Assets::mint_into(pool.lp_asset, who, shares) {
+                Ok(()) => return true,
+                Err(e) => e,
+            };
+            log::warn!(
+                target: "runtime::liquidity-pool",
+                "failed to mint {:?} LP shares of {:?} to {:?}, holding them in the pallet account: {:?}",
+                shares,
+                pair,
+                who,
+                error
+            );
+            if let Err(e) = T::Assets::mint_into(pool.lp_asset, &Pallet::<T>::account_id(), shares) {
+                log::error!(
+                    target: "runtime::liquidity-pool",
+                    "failed to hold {:?} LP shares of {:?} for {:?}: {:?}",
+                    shares,
+                    pair,
+                    who,
+                    e
+                );
+                return false;
+            }
+            HeldShares::<T>::mutate((pair.0, pair.1, who.clone()), |held| *held = held.saturating_add(shares));
+            true
+        }
+    }
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let on_chain = Pallet::<T>::on_chain_storage_version();
+            if on_chain != 0 {
+                log::info!(
+                    target: "runtime::liquidity-pool",
+                    "skipping v1 migration, on-chain storage version is {:?}",
+                    on_chain
+                );
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut weight = T::DbWeight::get().reads(1);
+            let rates = Self::merge_pools(&mut weight);
+
+            let mut migrated = 0u32;
+            let balances: Vec<_> = LiquidityTokens::<T>::iter().collect();
+            for ((asset_a, asset_b, who), balance) in balances {
+                let key = (asset_a, asset_b);
+                weight = weight.saturating_add(T::DbWeight::get().reads(1));
+                if balance.is_zero() {
+                    LiquidityTokens::<T>::remove((asset_a, asset_b, who));
+                    continue;
+                }
+
+                // v0 keyed shares by the caller's asset order; the pool key is canonical now
+                let pair = Pallet::<T>::canonical_pair(asset_a, asset_b);
+                let shares = if key == pair {
+                    balance
+                } else {
+                    // Shares of a pool that could not be merged stay where they are
+                    let (shares, v0_total) = match rates.get(&key) {
+                        Some(rate) => *rate,
+                        None => continue,
+                    };
+                    let converted = math::mul_div(
+                        balance.saturated_into(),
+                        shares.saturated_into(),
+                        v0_total.saturated_into(),
+                        false,
+                    );
+                    match converted.and_then(|shares| Pallet::<T>::to_balance(shares).ok()) {
+                        Some(shares) => shares,
+                        None => continue,
+                    }
+                };
+                let pool = match Self::ensure_registered(pair, &mut weight) {
+                    Some(pool) => pool,
+                    None => continue,
+                };
+
+                if !shares.is_zero() && !Self::mint_shares(pair, &pool, &who, shares) {
+                    continue;
+                }
+                LiquidityTokens::<T>::remove((asset_a, asset_b, who));
+                migrated = migrated.saturating_add(1);
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 3));
+            }
+
+            // Converted balances round down, so the supply is whatever was actually minted
+            for (pair, pool) in Pools::<T>::iter() {
+                TotalLiquidity::<T>::insert(pair, T::Assets::total_issuance(pool.lp_asset));
+                weight = weight.saturating_add(T::DbWeight::get().reads_writes(2, 1));
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            log::info!(target: "runtime::liquidity-pool", "migrated {} LP balances to LP assets", migrated);
+
+            weight.saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+            let holders = LiquidityTokens::<T>::iter().filter(|(_, balance)| !balance.is_zero()).count() as u32;
+            Ok(holders.encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+            let holders: u32 = Decode::decode(&mut &state[..]).map_err(|_| "invalid pre-upgrade state")?;
+            log::info!(target: "runtime::liquidity-pool", "{} LP holders before migration", holders);
+
+            let unmigrated = LiquidityTokens::<T>::iter().count();
+            if unmigrated > 0 {
+                log::error!(target: "runtime::liquidity-pool", "{} LP balances left in LiquidityTokens", unmigrated);
+            }
+            let held = HeldShares::<T>::iter().count();
+            if held > 0 {
+                log::warn!(target: "runtime::liquidity-pool", "{} LP balances held by the pallet account", held);
+            }
+            ensure!(Pallet::<T>::on_chain_storage_version() == 1, "storage version not bumped");
+            for (pair, _) in LiquidityPools::<T>::iter() {
+                ensure!(pair == Pallet::<T>::canonical_pair(pair.0, pair.1), "non-canonical pool left behind");
+            }
+            for (pair, pool) in Pools::<T>::iter() {
+                ensure!(
+                    TotalLiquidity::<T>::get(pair) == T::Assets::total_issuance(pool.lp_asset),
+                    "TotalLiquidity out of sync with LP asset issuance"
+                );
+            }
+
+            // Every pool is paid out of the one pallet account, so it must hold the
+            // reserves of all pools in each asset
+            let mut reserves = BTreeMap::<T::AssetId, BalanceOf<T>>::new();
+            for ((asset_a, asset_b), (reserve_a, reserve_b)) in LiquidityPools::<T>::iter() {
+                let total = reserves.entry(asset_a).or_default();
+                *total = total.saturating_add(reserve_a);
+                let total = reserves.entry(asset_b).or_default();
+                *total = total.saturating_add(reserve_b);
+            }
+            let pallet_account = Pallet::<T>::account_id();
+            for (asset, reserve) in reserves {
+                let held = Pallet::<T>::asset_balance(asset, &pallet_account);
+                if held < reserve {
+                    log::error!(
+                        target: "runtime::liquidity-pool",
+                        "pallet account holds {:?} of asset {:?} against {:?} of reserves",
+                        held,
+                        asset,
+                        reserve
+                    );
+                }
+                ensure!(held >= reserve, "pool reserves not backed by the pallet account");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Test runtime for `pallet-liquidity-pool`.
+
+use crate as pallet_liquidity_pool;
+use frame_support::{
+    parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, GenesisBuild},
+    PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill, Perquintill,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = u64;
+pub type Balance = u128;
+pub type AssetId = u32;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+pub const TREASURY: AccountId = 99;
+
+pub const NATIVE: AssetId = 0;
+pub const NRSH: AssetId = 1;
+pub const ELXR: AssetId = 2;
+pub const USDT: AssetId = 3;
+
+pub const INITIAL_BALANCE: Balance = 1_000_000_000_000;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        LiquidityPool: pallet_liquidity_pool,
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type Balance = Balance;
+    type DustRemoval = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxLocks = ();
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_assets::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = AssetId;
+    type AssetIdParameter = AssetId;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU128<0>;
+    type AssetAccountDeposit = ConstU128<0>;
+    type MetadataDepositBase = ConstU128<0>;
+    type MetadataDepositPerByte = ConstU128<0>;
+    type ApprovalDeposit = ConstU128<0>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type Extra = ();
+    type CallbackHandle = ();
+    type WeightInfo = ();
+    type RemoveItemsLimit = ConstU32<1000>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+parameter_types! {
+    pub const LiquidityPoolPalletId: PalletId = PalletId(*b"nrsh/lqp");
+    pub const TradingFee: Perquintill = pallet_liquidity_pool::NOURISH_CHAIN_FEE;
+    pub static MinimumLiquidity: Balance = 1_000;
+    pub const ProtocolFeeShare: Perbill = Perbill::from_percent(20);
+    pub const FeeDestination: AccountId = TREASURY;
+    pub const MinPoolFee: Perquintill = Perquintill::from_parts(100_000_000_000_000); // 0.01%
+    pub const MaxPoolFee: Perquintill = Perquintill::from_percent(1);
+}
+
+impl pallet_liquidity_pool::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type Currency = Balances;
+    type AssetId = AssetId;
+    type Assets = Assets;
+    type NativeAssetId = ConstU32<NATIVE>;
+    type PalletId = LiquidityPoolPalletId;
+    type TradingFee = TradingFee;
+    type LpAssetIdStart = ConstU32<10_000>;
+    type MinimumLiquidity = MinimumLiquidity;
+    type MaxSwapPathLength = ConstU32<4>;
+    type ProtocolFeeShare = ProtocolFeeShare;
+    type FeeDestination = FeeDestination;
+    type MaxPriceObservations = ConstU32<8>;
+    type MaxTicks = ConstU32<64>;
+    type TickDeposit = ConstU128<10>;
+    type PoolAdminOrigin = EnsureRoot<AccountId>;
+    type MaxAmplification = ConstU32<10_000>;
+    type MinRampDuration = ConstU64<10>;
+    type MinPoolFee = MinPoolFee;
+    type MaxPoolFee = MaxPoolFee;
+    type MaxRewardAssets = ConstU32<3>;
+    type OrderDeposit = ConstU128<100>;
+    type MaxOrdersPerPool = ConstU32<4>;
+    type StatsEpochLength = ConstU64<10>;
+    type MaxStatsEpochs = ConstU32<3>;
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = BenchmarkAssets;
+}
+
+/// Benchmark assets start above the genesis assets so they can be created fresh
+#[cfg(feature = "runtime-benchmarks")]
+pub struct BenchmarkAssets;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_liquidity_pool::BenchmarkHelper<AssetId> for BenchmarkAssets {
+    fn asset_id(i: u32) -> AssetId {
+        100 + i
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let accounts = [ALICE, BOB, CHARLIE];
+    let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: accounts.iter().map(|who| (*who, INITIAL_BALANCE)).collect(),
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    pallet_assets::GenesisConfig::<Test> {
+        assets: [NRSH, ELXR, USDT].iter().map(|id| (*id, ALICE, true, 1)).collect(),
+        metadata: vec![],
+        accounts: [NRSH, ELXR, USDT]
+            .iter()
+            .flat_map(|id| accounts.iter().map(move |who| (*id, *who, INITIAL_BALANCE)))
+            .collect(),
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,182 @@
+//! Limit orders for `pallet-liquidity-pool`.
+//!
+//! Resting orders sell a fixed amount of one asset of a constant product pool for at least a
+//! minimum amount of the other. Their input waits in escrow, and at the end of every block
+//! `on_idle` quotes each order against its pool's reserves and swaps it through the pool once
+//! the quote reaches the order's minimum. Stop-loss orders additionally wait until the pool's
+//! spot price has fallen to their trigger. Orders are checked pool by pool, in the order they
+//! were placed; when the block runs out of weight, the next block resumes with the next order.
+
+use super::*;
+use frame_support::{
+    pallet_prelude::{DispatchResult, Weight},
+    sp_runtime::{traits::AccountIdConversion, FixedPointNumber, FixedU128, SaturatedConversion},
+    storage::with_storage_layer,
+    traits::{Get, ReservableCurrency},
+};
+use sp_std::prelude::*;
+
+impl<T: Config> Pallet<T> {
+    /// The account holding the input of every resting order
+    pub fn order_account_id() -> T::AccountId {
+        T::PalletId::get().into_sub_account_truncating(b"book")
+    }
+
+    /// What an order would receive from its pool right now, if it may fill at all
+    pub fn order_quote(order: &OrderOf<T>) -> Option<BalanceOf<T>> {
+        let (reserve_in, reserve_out, fee) = Self::get_reserves(order.asset_in, order.asset_out).ok()?;
+        if let OrderKind::StopLoss(trigger) = order.kind {
+            let price = FixedU128::checked_from_rational(
+                reserve_out.saturated_into::<u128>(),
+                reserve_in.saturated_into::<u128>(),
+            )?;
+            if price > trigger {
+                return None;
+            }
+        }
+        let amount_out = Self::get_amount_out(order.amount_in, reserve_in, reserve_out, fee).ok()?;
+        if amount_out >= order.min_amount_out {
+            Some(amount_out)
+        } else {
+            None
+        }
+    }
+
+    /// Swap an order's escrowed input through its pool and pay the output to its owner
+    pub(crate) fn fill_order(order_id: OrderId, order: &OrderOf<T>, amount_out: BalanceOf<T>) -> DispatchResult {
+        Self::ensure_pool_open(Self::canonical_pair(order.asset_in, order.asset_out))?;
+
+        Self::transfer_asset(order.asset_in, &Self::order_account_id(), &Self::account_id(), order.amount_in, false)?;
+        Self::swap_hop(&order.owner, order.asset_in, order.asset_out, order.amount_in, amount_out)?;
+        Self::transfer_asset(order.asset_out, &Self::account_id(), &order.owner, amount_out, false)?;
+        T::Currency::unreserve(&order.owner, order.deposit);
+        Orders::<T>::remove(order_id);
+
+        Self::deposit_event(Event::OrderFilled(order_id, order.owner.clone(), order.amount_in, amount_out));
+        Ok(())
+    }
+
+    /// Return an order's escrowed input and deposit to its owner and drop the order.
+    /// The caller removes it from its pool's book.
+    pub(crate) fn refund_order(order_id: OrderId, order: &OrderOf<T>) -> DispatchResult {
+        Self::transfer_asset(order.asset_in, &Self::order_account_id(), &order.owner, order.amount_in, false)?;
+        T::Currency::unreserve(&order.owner, order.deposit);
+        Orders::<T>::remove(order_id);
+        Ok(())
+    }
+
+    /// Drop an order id from its pool's book
+    pub(crate) fn remove_from_book(pair: (T::AssetId, T::AssetId), order_id: OrderId) {
+        PoolOrders::<T>::mutate_exists(pair, |ids| {
+            if let Some(book) = ids {
+                book.retain(|id| *id != order_id);
+                if book.is_empty() {
+                    *ids = None;
+                }
+            }
+        });
+    }
+
+    /// Expire or fill one order. Returns whether the order left the book. Each attempt runs
+    /// in its own storage layer, so a failed fill or refund leaves the order resting.
+    fn process_order(order_id: OrderId, now: T::BlockNumber) -> bool {
+        let order = match Orders::<T>::get(order_id) {
+            Some(order) => order,
+            None => return true,
+        };
+
+        if order.expires_at.map_or(false, |expires_at| now > expires_at) {
+            let refunded = with_storage_layer(|| Self::refund_order(order_id, &order)).is_ok();
+            if refunded {
+                Self::deposit_event(Event::OrderExpired(order_id, order.owner));
+            }
+            return refunded;
+        }
+
+        match Self::order_quote(&order) {
+            Some(amount_out) => with_storage_layer(|| Self::fill_order(order_id, &order, amount_out)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Check as many orders as `remaining_weight` allows, pool by pool, starting where the
+    /// previous block stopped. Returns the weight used.
+    pub(crate) fn match_orders(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+        let mut used = T::DbWeight::get().reads_writes(1, 1);
+        if used.any_gt(remaining_weight) {
+            return Weight::zero();
+        }
+
+        // Finish the book the previous block stopped in before moving on to the next pools
+        let cursor = OrderBookCursor::<T>::take();
+        if let Some((pair, after)) = cursor {
+            if !Self::match_book(pair, after, now, &mut used, remaining_weight) {
+                return used;
+            }
+        }
+        let pairs = match cursor {
+            Some((pair, _)) => PoolOrders::<T>::iter_keys_from(PoolOrders::<T>::hashed_key_for(pair)),
+            None => PoolOrders::<T>::iter_keys(),
+        };
+        for pair in pairs {
+            if !Self::match_book(pair, None, now, &mut used, remaining_weight) {
+                return used;
+            }
+        }
+        used
+    }
+
+    /// Check the orders of one pool's book placed after order `after`, one at a time while
+    /// `remaining_weight` allows. Returns whether the whole book was checked; otherwise the
+    /// cursor is left at the last order checked, so the next block carries on from there and
+    /// a book too long for one block is still worked through.
+    fn match_book(
+        pair: (T::AssetId, T::AssetId),
+        after: Option<OrderId>,
+        now: T::BlockNumber,
+        used: &mut Weight,
+        remaining_weight: Weight,
+    ) -> bool {
+        // Only open a book if at least one of its orders can be checked
+        let book_weight = T::DbWeight::get().reads_writes(1, 1);
+        if used.saturating_add(book_weight).saturating_add(T::WeightInfo::fill_order()).any_gt(remaining_weight) {
+            OrderBookCursor::<T>::put((pair, after));
+            return false;
+        }
+        *used = used.saturating_add(book_weight);
+
+        let book = PoolOrders::<T>::get(pair);
+        let mut resting = Vec::with_capacity(book.len());
+        let mut last = after;
+        let mut complete = true;
+        for order_id in book {
+            // Order ids only grow, so the book is sorted and everything up to `after` is done
+            if !complete || after.map_or(false, |after| order_id <= after) {
+                resting.push(order_id);
+                continue;
+            }
+            let next = used.saturating_add(T::WeightInfo::fill_order());
+            if next.any_gt(remaining_weight) {
+                complete = false;
+                resting.push(order_id);
+                continue;
+            }
+            *used = next;
+            if !Self::process_order(order_id, now) {
+                resting.push(order_id);
+            }
+            last = Some(order_id);
+        }
+
+        if resting.is_empty() {
+            PoolOrders::<T>::remove(pair);
+        } else {
+            // `resting` is a subset of a bounded book, so it always fits
+            PoolOrders::<T>::insert(pair, BoundedVec::truncate_from(resting));
+        }
+        if !complete {
+            OrderBookCursor::<T>::put((pair, last));
+        }
+        complete
+    }
+}
@@ -0,0 +1,247 @@
+//! StableSwap pools for `pallet-liquidity-pool`.
+//!
+//! Pegged assets trade on the Curve StableSwap invariant
+//!
+//!     A * n^n * sum(x_i) + D = A * D * n^n + D^(n+1) / (n^n * prod(x_i))
+//!
+//! which is close to a constant sum while the pool is balanced and bends towards a
+//! constant product as it drains. The amplification coefficient `A` sets how far the flat
+//! region reaches. `D` and the post-trade balances are found with Newton's method in 256-bit
+//! arithmetic. Reserves are compared one to one, so the assets of a pool must share the same
+//! number of decimals.
+
+use super::*;
+use frame_support::{
+    ensure,
+    pallet_prelude::{DispatchError, DispatchResult},
+    sp_runtime::{
+        traits::{Saturating, Zero},
+        PerThing, Perquintill, SaturatedConversion,
+    },
+    traits::{fungibles::{Inspect, Mutate}, Get},
+};
+use sp_core::U256;
+use sp_std::prelude::*;
+
+/// Newton's method converges in a handful of rounds for sane pools; give up after this many
+const MAX_ITERATIONS: usize = 255;
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+/// The StableSwap invariant `D` of a pool's reserves. `None` if a reserve is empty while
+/// others are not, or if Newton's method does not converge.
+pub fn compute_d(reserves: &[u128], amplification: u128) -> Option<u128> {
+    let n = U256::from(reserves.len());
+    let sum = reserves.iter().try_fold(U256::zero(), |sum, x| sum.checked_add(U256::from(*x)))?;
+    if sum.is_zero() {
+        return Some(0);
+    }
+    let ann = U256::from(amplification).checked_mul(n)?;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^(n+1) / (n^n * prod(x_i))
+        let mut d_p = d;
+        for x in reserves {
+            d_p = d_p.checked_mul(d)?.checked_div(U256::from(*x).checked_mul(n)?)?;
+        }
+        let previous = d;
+        // D = (Ann * S + n * d_p) * D / ((Ann - 1) * D + (n + 1) * d_p)
+        let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+        let denominator = ann
+            .checked_sub(U256::one())?
+            .checked_mul(d)?
+            .checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+        d = numerator.checked_div(denominator)?;
+        if abs_diff(d, previous) <= U256::one() {
+            return d.try_into().ok();
+        }
+    }
+    None
+}
+
+/// The reserve of asset `j` that keeps the invariant at `d` once the reserve of asset `i`
+/// is set to `x`
+pub fn compute_y(reserves: &[u128], i: usize, j: usize, x: u128, amplification: u128, d: u128) -> Option<u128> {
+    let n = U256::from(reserves.len());
+    let ann = U256::from(amplification).checked_mul(n)?;
+    let d = U256::from(d);
+
+    // c = D^(n+1) / (n^n * prod(x_k, k != j) * Ann), b = sum(x_k, k != j) + D / Ann
+    let mut c = d;
+    let mut sum = U256::zero();
+    for (k, reserve) in reserves.iter().enumerate() {
+        if k == j {
+            continue;
+        }
+        let x_k = U256::from(if k == i { x } else { *reserve });
+        sum = sum.checked_add(x_k)?;
+        c = c.checked_mul(d)?.checked_div(x_k.checked_mul(n)?)?;
+    }
+    c = c.checked_mul(d)?.checked_div(ann.checked_mul(n)?)?;
+    let b = sum.checked_add(d.checked_div(ann)?)?;
+
+    // y = (y^2 + c) / (2y + b - D)
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let numerator = y.checked_mul(y)?.checked_add(c)?;
+        let denominator = y.checked_mul(U256::from(2))?.checked_add(b)?.checked_sub(d)?;
+        y = numerator.checked_div(denominator)?;
+        if abs_diff(y, previous) <= U256::one() {
+            return y.try_into().ok();
+        }
+    }
+    None
+}
+
+impl<T: Config> Pallet<T> {
+    /// The amplification coefficient of a pool at `now`, interpolated linearly while a ramp
+    /// is in progress
+    pub fn current_amplification(ramp: &AmplificationRamp<T::BlockNumber>, now: T::BlockNumber) -> u128 {
+        let initial = ramp.initial_a as u128;
+        let future = ramp.future_a as u128;
+        if now >= ramp.future_block || ramp.future_block <= ramp.initial_block {
+            return future;
+        }
+        let elapsed = now.saturating_sub(ramp.initial_block).saturated_into::<u128>();
+        let duration = ramp.future_block.saturating_sub(ramp.initial_block).saturated_into::<u128>();
+        if future > initial {
+            initial.saturating_add((future - initial).saturating_mul(elapsed) / duration)
+        } else {
+            initial.saturating_sub((initial - future).saturating_mul(elapsed) / duration)
+        }
+    }
+
+    /// Position of `asset` in a stable pool
+    pub(crate) fn stable_index(pool: &StablePoolOf<T>, asset: T::AssetId) -> Result<usize, DispatchError> {
+        pool.assets
+            .iter()
+            .position(|a| *a == asset)
+            .ok_or_else(|| Error::<T>::InvalidAssetPair.into())
+    }
+
+    /// Output and fee of swapping an exact `amount_in` through a stable pool, and the
+    /// protocol's share of the fee
+    pub fn stable_amount_out(
+        pool: &StablePoolOf<T>,
+        asset_in: T::AssetId,
+        asset_out: T::AssetId,
+        amount_in: BalanceOf<T>,
+    ) -> Result<(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>), DispatchError> {
+        ensure!(asset_in != asset_out, Error::<T>::InvalidAssetPair);
+        let i = Self::stable_index(pool, asset_in)?;
+        let j = Self::stable_index(pool, asset_out)?;
+        let reserves: Vec<u128> = pool.reserves.iter().map(|r| (*r).saturated_into()).collect();
+        ensure!(reserves.iter().all(|r| !r.is_zero()), Error::<T>::InsufficientLiquidity);
+
+        // The fee is taken from the input, as on every other pool
+        let fee_amount = pool.fee_tier.mul_floor(amount_in);
+        let protocol_fee = T::ProtocolFeeShare::get().mul_floor(fee_amount);
+        let amount_in_after_fee = amount_in.saturating_sub(fee_amount).saturated_into::<u128>();
+
+        let amplification = Self::current_amplification(&pool.amplification, frame_system::Pallet::<T>::block_number());
+        let d = compute_d(&reserves, amplification).ok_or(Error::<T>::CalculationError)?;
+        let x = reserves[i].checked_add(amount_in_after_fee).ok_or(Error::<T>::CalculationError)?;
+        let y = compute_y(&reserves, i, j, x, amplification, d).ok_or(Error::<T>::CalculationError)?;
+
+        // Round the output down by one unit so the invariant never decreases
+        let amount_out = reserves[j].saturating_sub(y).saturating_sub(1);
+        ensure!(amount_out > 0, Error::<T>::InsufficientLiquidity);
+        Ok((Self::to_balance(amount_out)?, fee_amount, protocol_fee))
+    }
+
+    /// Shares minted for depositing `amounts` into a stable pool and the reserves afterwards.
+    /// Deposits that unbalance the pool pay the pool fee on the imbalance, like a swap would.
+    pub(crate) fn stable_shares_for_deposit(
+        pool: &StablePoolOf<T>,
+        amounts: &[BalanceOf<T>],
+    ) -> Result<(BalanceOf<T>, Vec<BalanceOf<T>>, Vec<BalanceOf<T>>), DispatchError> {
+        ensure!(amounts.len() == pool.assets.len(), Error::<T>::InvalidAmount);
+        ensure!(amounts.iter().any(|a| !a.is_zero()), Error::<T>::InvalidAmount);
+
+        let amplification = Self::current_amplification(&pool.amplification, frame_system::Pallet::<T>::block_number());
+        let supply = T::Assets::total_issuance(pool.lp_asset).saturated_into::<u128>();
+        let old: Vec<u128> = pool.reserves.iter().map(|r| (*r).saturated_into()).collect();
+        let new = old
+            .iter()
+            .zip(amounts)
+            .map(|(reserve, amount)| reserve.checked_add((*amount).saturated_into()))
+            .collect::<Option<Vec<u128>>>()
+            .ok_or(Error::<T>::CalculationError)?;
+
+        if supply.is_zero() {
+            // The first deposit sets the pool's balance, so it must fund every asset
+            ensure!(amounts.iter().all(|a| !a.is_zero()), Error::<T>::InvalidAmount);
+            let d = compute_d(&new, amplification).ok_or(Error::<T>::CalculationError)?;
+            let reserves = new.into_iter().map(Self::to_balance).collect::<Result<Vec<_>, _>>()?;
+            let protocol_fees = sp_std::vec![Zero::zero(); reserves.len()];
+            return Ok((Self::to_balance(d)?, reserves, protocol_fees));
+        }
+
+        let d0 = compute_d(&old, amplification).ok_or(Error::<T>::CalculationError)?;
+        let d1 = compute_d(&new, amplification).ok_or(Error::<T>::CalculationError)?;
+        ensure!(d1 > d0, Error::<T>::InsufficientLiquidityMinted);
+
+        // Charge fee * n / (4 * (n - 1)) on each asset's deviation from a balanced deposit
+        let n = pool.assets.len() as u64;
+        let imbalance_fee = pool.fee_tier * Perquintill::from_rational(n, 4 * (n - 1));
+        let mut for_invariant = Vec::with_capacity(new.len());
+        let mut reserves = Vec::with_capacity(new.len());
+        let mut protocol_fees = Vec::with_capacity(new.len());
+        for (old_reserve, new_reserve) in old.iter().zip(new.iter()) {
+            let ideal = math::mul_div(d1, *old_reserve, d0, false).ok_or(Error::<T>::CalculationError)?;
+            let fee = imbalance_fee.mul_floor(ideal.max(*new_reserve) - ideal.min(*new_reserve));
+            let protocol_fee = T::ProtocolFeeShare::get().mul_floor(fee);
+            for_invariant.push(new_reserve.saturating_sub(fee));
+            reserves.push(Self::to_balance(new_reserve.saturating_sub(protocol_fee))?);
+            protocol_fees.push(Self::to_balance(protocol_fee)?);
+        }
+
+        let d2 = compute_d(&for_invariant, amplification).ok_or(Error::<T>::CalculationError)?;
+        let shares = math::mul_div(supply, d2.saturating_sub(d0), d0, false)
+            .ok_or(Error::<T>::CalculationError)?;
+        Ok((Self::to_balance(shares)?, reserves, protocol_fees))
+    }
+
+    /// Check a new amplification ramp against the bounds and the current coefficient
+    pub(crate) fn ensure_valid_ramp(current: u128, future_a: u32, duration: T::BlockNumber) -> DispatchResult {
+        ensure!(future_a > 0 && future_a <= T::MaxAmplification::get(), Error::<T>::InvalidAmplification);
+        ensure!(duration >= T::MinRampDuration::get(), Error::<T>::InvalidAmplification);
+        // At most a tenfold change per ramp, in either direction
+        let future = future_a as u128;
+        ensure!(
+            future <= current.saturating_mul(10) && current <= future.saturating_mul(10),
+            Error::<T>::InvalidAmplification
+        );
+        Ok(())
+    }
+
+    /// Mint LP shares of a stable pool, locking the minimum liquidity on the first deposit
+    pub(crate) fn mint_stable_shares(
+        who: &T::AccountId,
+        pool: &StablePoolOf<T>,
+        shares: BalanceOf<T>,
+        is_initial: bool,
+    ) -> Result<BalanceOf<T>, DispatchError> {
+        let minted = if is_initial {
+            let minimum = T::MinimumLiquidity::get();
+            ensure!(shares > minimum, Error::<T>::InsufficientLiquidityMinted);
+            if !minimum.is_zero() {
+                T::Assets::mint_into(pool.lp_asset, &Self::account_id(), minimum)?;
+            }
+            shares.saturating_sub(minimum)
+        } else {
+            shares
+        };
+        ensure!(!minted.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+        T::Assets::mint_into(pool.lp_asset, who, minted)?;
+        Ok(minted)
+    }
+}
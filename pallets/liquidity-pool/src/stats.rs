@@ -0,0 +1,101 @@
+//! Trading statistics for `pallet-liquidity-pool`.
+//!
+//! Every trade through a constant product, concentrated liquidity or StableSwap pool adds its
+//! volume and fee to the traded pair's aggregate for the current epoch of `StatsEpochLength`
+//! blocks. A StableSwap trade counts towards the two assets it swaps, not towards its pool.
+//! Only the last `MaxStatsEpochs` epochs with any trades are kept, which is enough for
+//! indexers and dashboards to derive rolling volume and fee APR without replaying events.
+
+use super::*;
+use frame_support::{
+    sp_runtime::{
+        traits::{Saturating, Zero},
+        SaturatedConversion,
+    },
+    traits::Get,
+};
+use sp_std::prelude::*;
+
+impl<T: Config> Pallet<T> {
+    /// The statistics epoch `now` falls in
+    pub fn stats_epoch(now: T::BlockNumber) -> u32 {
+        let length = T::StatsEpochLength::get().saturated_into::<u32>().max(1);
+        now.saturated_into::<u32>() / length
+    }
+
+    /// Add volume and fees, both in the pool's canonical asset order, to the current epoch
+    pub(crate) fn record_pool_activity(
+        pair: (T::AssetId, T::AssetId),
+        volume_a: BalanceOf<T>,
+        volume_b: BalanceOf<T>,
+        fees_a: BalanceOf<T>,
+        fees_b: BalanceOf<T>,
+    ) {
+        let epoch = Self::stats_epoch(frame_system::Pallet::<T>::block_number());
+        PoolStats::<T>::mutate(pair, |history| {
+            if history.last().map_or(true, |stats| stats.epoch != epoch) {
+                if history.len() as u32 >= T::MaxStatsEpochs::get() {
+                    history.remove(0);
+                }
+                let _ = history.try_push(EpochStats { epoch, ..Default::default() });
+            }
+            if let Some(stats) = history.last_mut() {
+                stats.volume_a = stats.volume_a.saturating_add(volume_a);
+                stats.volume_b = stats.volume_b.saturating_add(volume_b);
+                stats.fees_a = stats.fees_a.saturating_add(fees_a);
+                stats.fees_b = stats.fees_b.saturating_add(fees_b);
+                stats.trades = stats.trades.saturating_add(1);
+            }
+        });
+    }
+
+    /// Record one swap hop from `asset_in`, whose fee is charged in `asset_in`
+    pub(crate) fn record_swap(
+        pair: (T::AssetId, T::AssetId),
+        asset_in: T::AssetId,
+        amount_in: BalanceOf<T>,
+        amount_out: BalanceOf<T>,
+        fee_amount: BalanceOf<T>,
+    ) {
+        if asset_in == pair.0 {
+            Self::record_pool_activity(pair, amount_in, amount_out, fee_amount, Zero::zero());
+        } else {
+            Self::record_pool_activity(pair, amount_out, amount_in, Zero::zero(), fee_amount);
+        }
+    }
+
+    /// Current reserves of a pool ordered as (reserve_in, reserve_out) for a trade from
+    /// `asset_in`
+    pub(crate) fn ordered_reserves(
+        pair: (T::AssetId, T::AssetId),
+        asset_in: T::AssetId,
+    ) -> (BalanceOf<T>, BalanceOf<T>) {
+        let (reserve_a, reserve_b) = LiquidityPools::<T>::get(pair);
+        if asset_in == pair.0 {
+            (reserve_a, reserve_b)
+        } else {
+            (reserve_b, reserve_a)
+        }
+    }
+
+    /// Kept epoch aggregates of the pool for `(asset_a, asset_b)`, oldest first, with their
+    /// `_a` and `_b` fields in the order the assets were given
+    pub fn epoch_stats(asset_a: T::AssetId, asset_b: T::AssetId) -> Vec<EpochStats<BalanceOf<T>>> {
+        let pair = Self::canonical_pair(asset_a, asset_b);
+        let history = PoolStats::<T>::get(pair).into_inner();
+        if asset_a == pair.0 {
+            return history;
+        }
+        history
+            .into_iter()
+            .map(|stats| EpochStats {
+                epoch: stats.epoch,
+                volume_a: stats.volume_b,
+                volume_b: stats.volume_a,
+                fees_a: stats.fees_b,
+                fees_b: stats.fees_a,
+                trades: stats.trades,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,2326 @@
+//! Unit tests for `pallet-liquidity-pool`.
+
+use crate::{
+    migration::v1::{LiquidityTokens, MigrateToV1},
+    mock::*, CheckSwapDeadline, ConcentratedPools, EpochStats, Error, HeldShares, LiquidityPools, NextLpAssetId,
+    NextStablePoolId, OrderKind, Orders, PoolOrders, PoolStats, Pools, Positions, ProtocolFees, StablePools,
+    StableProtocolFees, SwapPathOf, TickDeposits, TotalLiquidity, WeightInfo,
+};
+use frame_support::{
+    assert_noop, assert_ok,
+    dispatch::{DispatchInfo, DispatchResultWithPostInfo, GetDispatchInfo},
+    traits::{
+        fungibles::{Create, Inspect, Mutate},
+        Currency, Get, GetStorageVersion, Hooks, OnRuntimeUpgrade, ReservableCurrency, StorageVersion,
+    },
+    weights::Weight,
+};
+use proptest::prelude::*;
+use sp_runtime::{
+    traits::{Dispatchable, SignedExtension},
+    transaction_validity::{InvalidTransaction, ValidTransaction},
+    DispatchError, FixedU128, PerThing, Perquintill,
+};
+
+fn lp_asset(asset_a: AssetId, asset_b: AssetId) -> AssetId {
+    LiquidityPool::resolve_pool(asset_a, asset_b).unwrap().1.lp_asset
+}
+
+fn lp_balance(asset_a: AssetId, asset_b: AssetId, who: AccountId) -> Balance {
+    Assets::balance(lp_asset(asset_a, asset_b), who)
+}
+
+fn seed_pool(asset_a: AssetId, asset_b: AssetId, amount_a: Balance, amount_b: Balance) {
+    assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), asset_a, asset_b));
+    assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), asset_a, asset_b, amount_a, amount_b, 0, None));
+}
+
+fn path(assets: &[AssetId]) -> SwapPathOf<Test> {
+    assets.to_vec().try_into().unwrap()
+}
+
+#[test]
+fn first_deposit_mints_geometric_mean_and_locks_minimum() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 10_000, 40_000, 0, None));
+
+        // sqrt(10_000 * 40_000) = 20_000, of which 1_000 stay locked in the pallet account
+        assert_eq!(TotalLiquidity::<Test>::get((NRSH, ELXR)), 20_000);
+        assert_eq!(lp_balance(NRSH, ELXR, ALICE), 19_000);
+        assert_eq!(lp_balance(NRSH, ELXR, LiquidityPool::account_id()), 1_000);
+        assert_eq!(Assets::total_issuance(lp_asset(NRSH, ELXR)), 20_000);
+    });
+}
+
+#[test]
+fn first_deposit_must_exceed_minimum_liquidity() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+
+        // sqrt(1_000 * 1_000) = 1_000 would leave nothing after the lock
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 1_000, 0, None),
+            Error::<Test>::InsufficientLiquidityMinted
+        );
+    });
+}
+
+/// Without a locked minimum, an attacker seeds the pool with a single share and then
+/// donates through a lopsided deposit, so one share is worth half the pool. The next
+/// depositor's shares round down and a third of their deposit goes to the attacker.
+#[test]
+fn donation_attack_succeeds_without_minimum_lock() {
+    new_test_ext().execute_with(|| {
+        MinimumLiquidity::set(0);
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+
+        // Attacker: one share for (1, 1), then a (1_000_000, 1) donation that still mints one share
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1, 1, 0, None));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1, 0, None));
+        assert_eq!(TotalLiquidity::<Test>::get((NRSH, ELXR)), 2);
+
+        // Victim: a proportional deposit only mints a single share
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 2, 0, None));
+        assert_eq!(lp_balance(NRSH, ELXR, BOB), 1);
+
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1, 0, 0, None));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 1_000_000 + 666_667);
+    });
+}
+
+/// The same attack against the locked minimum: the single-share seed is rejected, the
+/// donation cannot mint a share without paying for it, and the donation mostly accrues
+/// to the locked shares. The victim loses well under 0.1%.
+#[test]
+fn donation_attack_is_blocked_by_minimum_lock() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1, 1, 0, None),
+            Error::<Test>::InsufficientLiquidityMinted
+        );
+
+        // Cheapest seed that mints a single share: sqrt(1 * 1_002_001) = 1_001
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1, 1_002_001, 0, None));
+        assert_eq!(lp_balance(NRSH, ELXR, ALICE), 1);
+
+        // A lopsided donation no longer mints a share for free
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1, 0, None),
+            Error::<Test>::InsufficientLiquidityMinted
+        );
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1_001, 0, None));
+
+        // Victim deposits proportionally and withdraws everything
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 1_003_002, 0, None));
+        let shares = lp_balance(NRSH, ELXR, BOB);
+        assert_eq!(shares, 1_001);
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, shares, 0, 0, None));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 1_000_000 + 999_501);
+
+        // The attacker gets back a fraction of a percent of the donation
+        let shares = lp_balance(NRSH, ELXR, ALICE);
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, shares, 0, 0, None));
+        assert_eq!(Assets::balance(NRSH, ALICE), INITIAL_BALANCE - 1_000_001 + 1_997);
+    });
+}
+
+#[test]
+fn protocol_share_of_swap_fee_accrues_and_is_claimed_to_destination() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1_000_000, 0, None));
+        assert_noop!(
+            LiquidityPool::claim_protocol_fees(RuntimeOrigin::signed(BOB), NRSH, ELXR),
+            Error::<Test>::NoProtocolFees
+        );
+
+        // 0.369% of 100_000 is 369, of which 20% (73) belongs to the protocol
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 100_000, 0, None));
+        assert_eq!(ProtocolFees::<Test>::get((NRSH, ELXR), NRSH), 73);
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)).0, 1_100_000 - 73);
+
+        assert_ok!(LiquidityPool::claim_protocol_fees(RuntimeOrigin::signed(BOB), ELXR, NRSH));
+        assert_eq!(Assets::balance(NRSH, TREASURY), 73);
+        assert_eq!(ProtocolFees::<Test>::get((NRSH, ELXR), NRSH), 0);
+    });
+}
+
+#[test]
+fn twap_ignores_same_block_manipulation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 2_000_000, 0, None));
+
+        System::set_block_number(11);
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None));
+
+        // Dump NRSH and query in the same block: the oracle still reflects ~2 ELXR per NRSH
+        System::set_block_number(21);
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 0, None));
+        let price = LiquidityPool::consult(NRSH, ELXR, 10).unwrap();
+        assert!(price > FixedU128::from_float(1.99) && price < FixedU128::from_float(2.0));
+
+        let inverse = LiquidityPool::consult(ELXR, NRSH, 10).unwrap();
+        assert!(inverse > FixedU128::from_float(0.5) && inverse < FixedU128::from_float(0.51));
+
+        // Not enough history for a longer window
+        assert_eq!(LiquidityPool::consult(NRSH, ELXR, 30), None);
+    });
+}
+
+#[test]
+fn create_pool_rejects_identical_assets() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, NRSH),
+            Error::<Test>::InvalidAssetPair
+        );
+    });
+}
+
+#[test]
+fn create_pool_rejects_existing_pair_in_either_order() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_noop!(
+            LiquidityPool::create_pool(RuntimeOrigin::signed(BOB), ELXR, NRSH),
+            Error::<Test>::PoolAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn add_liquidity_rejects_zero_amounts() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 0, 1_000_000, 0, None),
+            Error::<Test>::InvalidAmount
+        );
+    });
+}
+
+#[test]
+fn add_liquidity_respects_min_liquidity() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 1_000, 1_001, None),
+            Error::<Test>::InsufficientLiquidity
+        );
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 1_000, 1_000, None));
+    });
+}
+
+#[test]
+fn add_liquidity_rejects_deposits_worth_no_shares() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 100_000_000);
+        // 1 NRSH is worth 10 shares but 5 ELXR is worth half a share
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1, 5, 0, None),
+            Error::<Test>::InsufficientLiquidityMinted
+        );
+    });
+}
+
+#[test]
+fn liquidity_calls_require_a_registered_pool() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1_000_000, 0, None),
+            Error::<Test>::PoolDoesNotExist
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, 0, None),
+            Error::<Test>::PoolDoesNotExist
+        );
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::PoolDoesNotExist
+        );
+        assert_noop!(
+            LiquidityPool::claim_protocol_fees(RuntimeOrigin::signed(ALICE), NRSH, ELXR),
+            Error::<Test>::PoolDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn remove_liquidity_rejects_more_shares_than_held() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let shares = lp_balance(NRSH, ELXR, ALICE);
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, shares + 1, 0, 0, None),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1, 0, 0, None),
+            Error::<Test>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn remove_liquidity_respects_minimum_amounts() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 4_000_000);
+        // 1_000 of 2_000_000 shares is worth 500 NRSH and 2_000 ELXR, minimums given in call order
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), ELXR, NRSH, 1_000, 2_001, 500, None),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), ELXR, NRSH, 1_000, 2_000, 500, None));
+    });
+}
+
+#[test]
+fn remove_liquidity_rejects_zero_shares() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 0, 0, 0, None),
+            Error::<Test>::InvalidAmount
+        );
+    });
+}
+
+#[test]
+fn swap_rejects_zero_input() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 0, 0, None),
+            Error::<Test>::InvalidAmount
+        );
+    });
+}
+
+#[test]
+fn swap_respects_min_amount_out() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let quote = LiquidityPool::quote_price_exact_in(vec![NRSH, ELXR], 10_000).unwrap();
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, quote + 1, None),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, quote, None));
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE + quote);
+    });
+}
+
+#[test]
+fn swap_against_empty_pool_fails() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::InsufficientLiquidity
+        );
+    });
+}
+
+#[test]
+fn swap_of_half_the_balance_range_is_quoted_without_overflow() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let whale = Balance::MAX / 2;
+        assert_ok!(Assets::mint_into(NRSH, &BOB, whale));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, whale, 0, None));
+
+        // The pool pays out all but the last unit of its reserve
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)).1, 1);
+        assert_invariants();
+    });
+}
+
+#[test]
+fn routed_swaps_reject_short_paths() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::swap_exact_tokens_for_tokens(RuntimeOrigin::signed(BOB), path(&[NRSH]), 1_000, 0, None),
+            Error::<Test>::InvalidPath
+        );
+        assert_noop!(
+            LiquidityPool::swap_tokens_for_exact_tokens(RuntimeOrigin::signed(BOB), path(&[NRSH]), 1_000, 10_000, None),
+            Error::<Test>::InvalidPath
+        );
+    });
+}
+
+#[test]
+fn routed_swaps_reject_paths_that_revisit_a_pool() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+        seed_pool(USDT, NRSH, 1_000_000, 1_000_000);
+
+        // Back and forth through one pool, and a cycle that returns to its first asset
+        for route in [path(&[NRSH, ELXR, NRSH]), path(&[NRSH, ELXR, USDT, NRSH])] {
+            assert_noop!(
+                LiquidityPool::swap_exact_tokens_for_tokens(RuntimeOrigin::signed(BOB), route.clone(), 1_000, 0, None),
+                Error::<Test>::PathRepeatsAsset
+            );
+            assert_noop!(
+                LiquidityPool::swap_tokens_for_exact_tokens(
+                    RuntimeOrigin::signed(BOB),
+                    route.clone(),
+                    100,
+                    10_000,
+                    None
+                ),
+                Error::<Test>::PathRepeatsAsset
+            );
+            assert_eq!(LiquidityPool::quote_price_exact_in(route.into_inner(), 1_000), None);
+        }
+    });
+}
+
+#[test]
+fn routed_swaps_respect_slippage_limits() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+        let route = path(&[NRSH, ELXR, USDT]);
+
+        let out = LiquidityPool::quote_price_exact_in(route.to_vec(), 10_000).unwrap();
+        assert_noop!(
+            LiquidityPool::swap_exact_tokens_for_tokens(
+                RuntimeOrigin::signed(BOB),
+                route.clone(),
+                10_000,
+                out + 1,
+                None
+            ),
+            Error::<Test>::SlippageExceeded
+        );
+
+        let cost = LiquidityPool::quote_price_exact_out(route.to_vec(), 10_000).unwrap();
+        assert_noop!(
+            LiquidityPool::swap_tokens_for_exact_tokens(
+                RuntimeOrigin::signed(BOB),
+                route.clone(),
+                10_000,
+                cost - 1,
+                None
+            ),
+            Error::<Test>::SlippageExceeded
+        );
+
+        assert_ok!(LiquidityPool::swap_tokens_for_exact_tokens(RuntimeOrigin::signed(BOB), route, 10_000, cost, None));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - cost);
+        assert_eq!(Assets::balance(USDT, BOB), INITIAL_BALANCE + 10_000);
+    });
+}
+
+#[test]
+fn routed_swaps_require_every_hop_to_exist() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::swap_exact_tokens_for_tokens(
+                RuntimeOrigin::signed(BOB),
+                path(&[NRSH, ELXR, USDT]),
+                1_000,
+                0,
+                None
+            ),
+            Error::<Test>::PoolDoesNotExist
+        );
+    });
+}
+
+/// A concentrated NRSH/ELXR pool starting at price 1 with ticks spaced by 10
+fn seed_concentrated_pool() {
+    assert_ok!(LiquidityPool::create_concentrated_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 10, 0));
+}
+
+fn open(who: AccountId, tick_lower: i32, tick_upper: i32, amount_a: Balance, amount_b: Balance) -> u64 {
+    let position_id = crate::NextPositionId::<Test>::get();
+    assert_ok!(LiquidityPool::open_position(
+        RuntimeOrigin::signed(who),
+        NRSH,
+        ELXR,
+        tick_lower,
+        tick_upper,
+        amount_a,
+        amount_b
+    ));
+    position_id
+}
+
+#[test]
+fn positions_deposit_both_assets_in_range_and_one_asset_out_of_range() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+
+        open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        let (reserve_a, reserve_b) = LiquidityPools::<Test>::get((NRSH, ELXR));
+        assert!(reserve_a > 0 && reserve_b > 0);
+        assert!(reserve_a <= 1_000_000 && reserve_b <= 1_000_000);
+
+        // A range above the current price is funded with asset_a only
+        open(BOB, 100, 200, 1_000_000, 1_000_000);
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE);
+        assert!(Assets::balance(NRSH, BOB) < INITIAL_BALANCE);
+
+        // Only the in-range position is active
+        let alice = Positions::<Test>::get(0).unwrap();
+        assert_eq!(ConcentratedPools::<Test>::get((NRSH, ELXR)).unwrap().liquidity, alice.liquidity);
+    });
+}
+
+#[test]
+fn concentrated_swap_pays_fees_to_in_range_positions_only() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        let alice = open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        let bob = open(BOB, 200, 400, 1_000_000, 1_000_000);
+
+        let quote = LiquidityPool::quote_price_exact_in(vec![NRSH, ELXR], 10_000).unwrap();
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(CHARLIE), NRSH, ELXR, 10_000, quote, None));
+        assert_eq!(Assets::balance(ELXR, CHARLIE), INITIAL_BALANCE + quote);
+
+        // A fee of 37 on 10_000, of which 20% (7) goes to the protocol and the rest to Alice
+        assert_eq!(ProtocolFees::<Test>::get((NRSH, ELXR), NRSH), 7);
+        let before = Assets::balance(NRSH, ALICE);
+        assert_ok!(LiquidityPool::collect_fees(RuntimeOrigin::signed(ALICE), alice));
+        assert!((29..=30).contains(&(Assets::balance(NRSH, ALICE) - before)));
+
+        let before = Assets::balance(NRSH, BOB);
+        assert_ok!(LiquidityPool::collect_fees(RuntimeOrigin::signed(BOB), bob));
+        assert_eq!(Assets::balance(NRSH, BOB), before);
+    });
+}
+
+#[test]
+fn concentrated_swap_crosses_into_the_next_range() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        let alice = open(ALICE, -20, 20, 1_000, 1_000);
+        // Below the current price, funded with asset_b only
+        let bob = open(BOB, -1_000, -20, 0, 1_000_000);
+
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(CHARLIE), NRSH, ELXR, 5_000, 0, None));
+
+        // Alice's range is exhausted and Bob's liquidity is the only one left active
+        let state = ConcentratedPools::<Test>::get((NRSH, ELXR)).unwrap();
+        assert!(state.tick < -20);
+        assert_eq!(state.liquidity, Positions::<Test>::get(bob).unwrap().liquidity);
+
+        // Both positions earned part of the fee
+        for (who, position_id) in [(ALICE, alice), (BOB, bob)] {
+            let before = Assets::balance(NRSH, who);
+            assert_ok!(LiquidityPool::collect_fees(RuntimeOrigin::signed(who), position_id));
+            assert!(Assets::balance(NRSH, who) > before);
+        }
+    });
+}
+
+#[test]
+fn concentrated_swap_is_charged_for_the_ticks_it_crosses() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        open(ALICE, -20, 20, 1_000, 1_000);
+        open(BOB, -1_000, -20, 0, 1_000_000);
+
+        // Charged for crossing every tick up front
+        let call = crate::Call::<Test>::swap {
+            asset_in: NRSH,
+            asset_out: ELXR,
+            amount_in: 5_000,
+            min_amount_out: 0,
+            deadline: None,
+        };
+        let max_ticks = <Test as crate::Config>::MaxTicks::get();
+        let declared = <() as WeightInfo>::swap().saturating_add(<() as WeightInfo>::concentrated_swap_hop(max_ticks));
+        assert_eq!(call.get_dispatch_info().weight, declared);
+
+        // Refunded down to the single tick at -20 the price moved across
+        let post = LiquidityPool::swap(RuntimeOrigin::signed(CHARLIE), NRSH, ELXR, 5_000, 0, None).unwrap();
+        let actual = <() as WeightInfo>::swap().saturating_add(<() as WeightInfo>::concentrated_swap_hop(1));
+        assert_eq!(post.actual_weight, Some(actual));
+    });
+}
+
+#[test]
+fn decreased_positions_keep_fees_until_collected() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        let alice = open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, 0, None));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), ELXR, NRSH, 10_000, 0, None));
+
+        let liquidity = Positions::<Test>::get(alice).unwrap().liquidity;
+        assert_noop!(
+            LiquidityPool::decrease_liquidity(RuntimeOrigin::signed(ALICE), alice, liquidity + 1, 0, 0),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_ok!(LiquidityPool::decrease_liquidity(RuntimeOrigin::signed(ALICE), alice, liquidity, 0, 0));
+
+        let position = Positions::<Test>::get(alice).unwrap();
+        assert_eq!(position.liquidity, 0);
+        assert!(position.tokens_owed_a > 0 && position.tokens_owed_b > 0);
+        assert_eq!(ConcentratedPools::<Test>::get((NRSH, ELXR)).unwrap().liquidity, 0);
+
+        // Collecting the fees closes the empty position
+        assert_ok!(LiquidityPool::collect_fees(RuntimeOrigin::signed(ALICE), alice));
+        assert!(Positions::<Test>::get(alice).is_none());
+    });
+}
+
+#[test]
+fn pool_kinds_only_accept_their_own_liquidity_calls() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000_000, 1_000_000, 0, None),
+            Error::<Test>::WrongPoolKind
+        );
+        assert_noop!(
+            LiquidityPool::open_position(RuntimeOrigin::signed(ALICE), ELXR, USDT, -100, 100, 1_000, 1_000),
+            Error::<Test>::WrongPoolKind
+        );
+
+        open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::swap_tokens_for_exact_tokens(
+                RuntimeOrigin::signed(BOB),
+                path(&[NRSH, ELXR]),
+                1_000,
+                10_000,
+                None
+            ),
+            Error::<Test>::WrongPoolKind
+        );
+    });
+}
+
+#[test]
+fn position_ranges_must_be_ordered_and_on_the_tick_spacing() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        for (tick_lower, tick_upper) in [(100, -100), (100, 100), (-105, 100), (-100, 1_000_000)] {
+            assert_noop!(
+                LiquidityPool::open_position(RuntimeOrigin::signed(ALICE), NRSH, ELXR, tick_lower, tick_upper, 1_000, 1_000),
+                Error::<Test>::InvalidTickRange
+            );
+        }
+        assert_noop!(
+            LiquidityPool::create_concentrated_pool(RuntimeOrigin::signed(ALICE), ELXR, USDT, 0, 0),
+            Error::<Test>::InvalidTickRange
+        );
+    });
+}
+
+#[test]
+fn positions_can_only_be_managed_by_their_owner() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        let alice = open(ALICE, -100, 100, 1_000_000, 1_000_000);
+
+        assert_noop!(
+            LiquidityPool::increase_liquidity(RuntimeOrigin::signed(BOB), alice, 1_000, 1_000),
+            Error::<Test>::NotPositionOwner
+        );
+        assert_noop!(
+            LiquidityPool::decrease_liquidity(RuntimeOrigin::signed(BOB), alice, 1, 0, 0),
+            Error::<Test>::NotPositionOwner
+        );
+        assert_noop!(
+            LiquidityPool::collect_fees(RuntimeOrigin::signed(BOB), alice),
+            Error::<Test>::NotPositionOwner
+        );
+        assert_noop!(
+            LiquidityPool::collect_fees(RuntimeOrigin::signed(ALICE), alice + 1),
+            Error::<Test>::PositionNotFound
+        );
+    });
+}
+
+#[test]
+fn initialized_ticks_are_bounded() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        // MaxTicks is 64, two new ticks per position
+        for i in 1..=32 {
+            open(ALICE, -10 * i, 10 * i, 1_000, 1_000);
+        }
+        // Funds move before the ticks are updated, so dispatch to get the rollback
+        assert_noop!(
+            dispatch(
+                ALICE,
+                crate::Call::open_position {
+                    asset_a: NRSH,
+                    asset_b: ELXR,
+                    tick_lower: -330,
+                    tick_upper: 330,
+                    amount_a_max: 1_000,
+                    amount_b_max: 1_000,
+                }
+            ),
+            Error::<Test>::TooManyTicks
+        );
+        // Ranges on existing ticks are still accepted
+        open(BOB, -320, 320, 1_000, 1_000);
+    });
+}
+
+#[test]
+fn tick_deposits_are_held_until_the_tick_is_cleared() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        let alice = open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        assert_eq!(Balances::reserved_balance(ALICE), 20);
+        assert_eq!(TickDeposits::<Test>::get((NRSH, ELXR), -100), Some((ALICE, 10)));
+
+        // Bob's range shares Alice's lower tick and only pays for its upper one
+        let bob = open(BOB, -100, 0, 1_000_000, 1_000_000);
+        assert_eq!(Balances::reserved_balance(BOB), 10);
+
+        // The shared tick keeps Alice's deposit until Bob's position stops using it
+        let liquidity = Positions::<Test>::get(alice).unwrap().liquidity;
+        assert_ok!(LiquidityPool::decrease_liquidity(RuntimeOrigin::signed(ALICE), alice, liquidity, 0, 0));
+        assert_eq!(Balances::reserved_balance(ALICE), 10);
+        let liquidity = Positions::<Test>::get(bob).unwrap().liquidity;
+        assert_ok!(LiquidityPool::decrease_liquidity(RuntimeOrigin::signed(BOB), bob, liquidity, 0, 0));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert!(crate::InitializedTicks::<Test>::get((NRSH, ELXR)).is_empty());
+        assert_eq!(TickDeposits::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn new_ticks_need_a_deposit() {
+    new_test_ext().execute_with(|| {
+        seed_concentrated_pool();
+        open(ALICE, -100, 100, 1_000_000, 1_000_000);
+
+        // Bob can pay for his assets but not for the deposit of a new tick
+        let free = Balances::free_balance(BOB);
+        assert_ok!(Balances::reserve(&BOB, free - 15));
+        assert_noop!(
+            dispatch(
+                BOB,
+                crate::Call::open_position {
+                    asset_a: NRSH,
+                    asset_b: ELXR,
+                    tick_lower: -200,
+                    tick_upper: 200,
+                    amount_a_max: 1_000,
+                    amount_b_max: 1_000,
+                }
+            ),
+            pallet_balances::Error::<Test>::InsufficientBalance
+        );
+        // Ranges on existing ticks cost no deposit
+        open(BOB, -100, 100, 1_000, 1_000);
+    });
+}
+
+/// A NRSH/ELXR/USDT StableSwap pool with A = 100 and 1e9 of every asset from Alice
+fn seed_stable_pool() -> u32 {
+    assert_ok!(LiquidityPool::create_stable_pool(
+        RuntimeOrigin::root(),
+        vec![USDT, NRSH, ELXR].try_into().unwrap(),
+        100
+    ));
+    assert_ok!(LiquidityPool::add_stable_liquidity(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        vec![1_000_000_000; 3].try_into().unwrap(),
+        0
+    ));
+    0
+}
+
+#[test]
+fn stable_pool_trades_pegged_assets_close_to_parity() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+
+        // 10% of the reserves moves the constant product price by about as much
+        let amount = 100_000_000;
+        let constant_product = LiquidityPool::quote_price_exact_in(vec![NRSH, ELXR], amount).unwrap();
+        assert_ok!(LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, NRSH, ELXR, amount, 0, None));
+        let stable = Assets::balance(ELXR, BOB) - INITIAL_BALANCE;
+
+        assert!(stable > constant_product);
+        let fee = TradingFee::get().mul_floor(amount);
+        assert!(stable > (amount - fee) * 99 / 100);
+        assert!(stable < amount - fee);
+    });
+}
+
+#[test]
+fn stable_liquidity_is_added_and_removed_across_all_assets() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        let lp_asset = StablePools::<Test>::get(pool_id).unwrap().lp_asset;
+        // A balanced first deposit mints D, the sum of the reserves
+        assert_eq!(Assets::total_issuance(lp_asset), 3_000_000_000);
+
+        assert_ok!(LiquidityPool::add_stable_liquidity(
+            RuntimeOrigin::signed(BOB),
+            pool_id,
+            vec![1_000_000, 1_000_000, 1_000_000].try_into().unwrap(),
+            3_000_000
+        ));
+        let shares = Assets::balance(lp_asset, BOB);
+        assert_eq!(shares, 3_000_000);
+
+        assert_ok!(LiquidityPool::remove_stable_liquidity(
+            RuntimeOrigin::signed(BOB),
+            pool_id,
+            shares,
+            vec![999_999; 3].try_into().unwrap()
+        ));
+        for asset in [NRSH, ELXR, USDT] {
+            assert!(INITIAL_BALANCE - Assets::balance(asset, BOB) <= 1);
+        }
+        assert_eq!(Assets::balance(lp_asset, BOB), 0);
+    });
+}
+
+#[test]
+fn unbalanced_stable_deposits_pay_the_imbalance_fee() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        let lp_asset = StablePools::<Test>::get(pool_id).unwrap().lp_asset;
+
+        assert_ok!(LiquidityPool::add_stable_liquidity(
+            RuntimeOrigin::signed(BOB),
+            pool_id,
+            vec![0, 3_000_000, 0].try_into().unwrap(),
+            0
+        ));
+        assert!(Assets::balance(lp_asset, BOB) < 3_000_000);
+        assert!(StableProtocolFees::<Test>::get(pool_id, NRSH) > 0);
+    });
+}
+
+#[test]
+fn amplification_ramps_linearly_and_can_be_stopped() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        assert_ok!(LiquidityPool::ramp_amplification(RuntimeOrigin::root(), pool_id, 200, 101));
+
+        let ramp = StablePools::<Test>::get(pool_id).unwrap().amplification;
+        assert_eq!(LiquidityPool::current_amplification(&ramp, 51), 150);
+        assert_eq!(LiquidityPool::current_amplification(&ramp, 500), 200);
+
+        System::set_block_number(26);
+        assert_ok!(LiquidityPool::stop_amplification_ramp(RuntimeOrigin::root(), pool_id));
+        let ramp = StablePools::<Test>::get(pool_id).unwrap().amplification;
+        assert_eq!(LiquidityPool::current_amplification(&ramp, 1_000), 125);
+    });
+}
+
+#[test]
+fn amplification_changes_are_bounded() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        for (future_a, future_block) in [(0, 100), (10_001, 100), (1_001, 100), (9, 100), (200, 10)] {
+            assert_noop!(
+                LiquidityPool::ramp_amplification(RuntimeOrigin::root(), pool_id, future_a, future_block),
+                Error::<Test>::InvalidAmplification
+            );
+        }
+        assert_noop!(
+            LiquidityPool::create_stable_pool(RuntimeOrigin::root(), vec![NRSH, ELXR].try_into().unwrap(), 0),
+            Error::<Test>::InvalidAmplification
+        );
+    });
+}
+
+#[test]
+fn stable_pool_administration_requires_the_admin_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            LiquidityPool::create_stable_pool(
+                RuntimeOrigin::signed(ALICE),
+                vec![NRSH, ELXR].try_into().unwrap(),
+                100
+            ),
+            DispatchError::BadOrigin
+        );
+        let pool_id = seed_stable_pool();
+        assert_noop!(
+            LiquidityPool::ramp_amplification(RuntimeOrigin::signed(ALICE), pool_id, 200, 100),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            LiquidityPool::stop_amplification_ramp(RuntimeOrigin::signed(ALICE), pool_id),
+            DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn pools_are_not_created_once_their_ids_run_out() {
+    new_test_ext().execute_with(|| {
+        NextStablePoolId::<Test>::put(u32::MAX);
+        assert_noop!(
+            LiquidityPool::create_stable_pool(RuntimeOrigin::root(), vec![NRSH, ELXR].try_into().unwrap(), 100),
+            Error::<Test>::CalculationError
+        );
+
+        NextStablePoolId::<Test>::kill();
+        NextLpAssetId::<Test>::put(AssetId::MAX);
+        assert_noop!(
+            LiquidityPool::create_stable_pool(RuntimeOrigin::root(), vec![NRSH, ELXR].try_into().unwrap(), 100),
+            Error::<Test>::CalculationError
+        );
+        assert_noop!(
+            LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, ELXR),
+            Error::<Test>::CalculationError
+        );
+    });
+}
+
+#[test]
+fn stable_pools_are_paused_and_repriced_by_id() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        assert_noop!(
+            LiquidityPool::pause_stable_pool(RuntimeOrigin::signed(ALICE), pool_id),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(LiquidityPool::pause_stable_pool(RuntimeOrigin::root(), 7), Error::<Test>::PoolDoesNotExist);
+        // The pair calls only reach constant product and concentrated pools
+        assert_noop!(LiquidityPool::pause_pool(RuntimeOrigin::root(), NRSH, ELXR), Error::<Test>::PoolDoesNotExist);
+
+        assert_ok!(LiquidityPool::pause_stable_pool(RuntimeOrigin::root(), pool_id));
+        System::assert_last_event(crate::Event::<Test>::StablePoolPaused(pool_id).into());
+        assert_noop!(
+            LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::PoolPaused
+        );
+        assert_noop!(
+            LiquidityPool::add_stable_liquidity(
+                RuntimeOrigin::signed(BOB),
+                pool_id,
+                vec![1_000; 3].try_into().unwrap(),
+                0
+            ),
+            Error::<Test>::PoolPaused
+        );
+        // Providers can still leave
+        assert_ok!(LiquidityPool::remove_stable_liquidity(
+            RuntimeOrigin::signed(ALICE),
+            pool_id,
+            1_000,
+            vec![0; 3].try_into().unwrap()
+        ));
+
+        assert_ok!(LiquidityPool::unpause_stable_pool(RuntimeOrigin::root(), pool_id));
+        System::assert_last_event(crate::Event::<Test>::StablePoolUnpaused(pool_id).into());
+        assert_ok!(LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, NRSH, ELXR, 1_000, 0, None));
+
+        for fee_tier in [Perquintill::from_parts(1), Perquintill::from_percent(2)] {
+            assert_noop!(
+                LiquidityPool::set_stable_pool_fee(RuntimeOrigin::root(), pool_id, fee_tier),
+                Error::<Test>::FeeOutOfBounds
+            );
+        }
+        let fee_tier = Perquintill::from_percent(1);
+        assert_ok!(LiquidityPool::set_stable_pool_fee(RuntimeOrigin::root(), pool_id, fee_tier));
+        System::assert_last_event(crate::Event::<Test>::StablePoolFeeSet(pool_id, fee_tier).into());
+        assert_eq!(LiquidityPool::stable_pools(pool_id).unwrap().fee_tier, fee_tier);
+    });
+}
+
+#[test]
+fn stable_pools_need_distinct_assets() {
+    new_test_ext().execute_with(|| {
+        for assets in [vec![NRSH], vec![NRSH, ELXR, NRSH]] {
+            assert_noop!(
+                LiquidityPool::create_stable_pool(RuntimeOrigin::root(), assets.try_into().unwrap(), 100),
+                Error::<Test>::InvalidAssetPair
+            );
+        }
+    });
+}
+
+#[test]
+fn stable_swaps_respect_min_amount_out() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        assert_noop!(
+            LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, USDT, NRSH, 1_000_000, 1_000_000, None),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_noop!(
+            LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, USDT, NATIVE, 1_000_000, 0, None),
+            Error::<Test>::InvalidAssetPair
+        );
+    });
+}
+
+#[test]
+fn stable_swaps_aggregate_into_the_traded_pairs_stats() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        assert_ok!(LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, USDT, NRSH, 10_000_000, 0, None));
+        let amount_out = Assets::balance(NRSH, BOB) - INITIAL_BALANCE;
+        let fee = TradingFee::get().mul_floor(10_000_000);
+
+        assert_eq!(
+            LiquidityPool::epoch_stats(USDT, NRSH),
+            vec![EpochStats { epoch: 0, volume_a: 10_000_000, volume_b: amount_out, fees_a: fee, fees_b: 0, trades: 1 }]
+        );
+        // The third asset of the pool saw no trade
+        assert!(LiquidityPool::epoch_stats(USDT, ELXR).is_empty());
+    });
+}
+
+#[test]
+fn stable_protocol_fees_are_claimed_to_destination() {
+    new_test_ext().execute_with(|| {
+        let pool_id = seed_stable_pool();
+        assert_noop!(
+            LiquidityPool::claim_stable_protocol_fees(RuntimeOrigin::signed(BOB), pool_id),
+            Error::<Test>::NoProtocolFees
+        );
+
+        assert_ok!(LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, USDT, NRSH, 10_000_000, 0, None));
+        let accrued = StableProtocolFees::<Test>::get(pool_id, USDT);
+        assert_eq!(accrued, ProtocolFeeShare::get().mul_floor(TradingFee::get().mul_floor(10_000_000)));
+
+        assert_ok!(LiquidityPool::claim_stable_protocol_fees(RuntimeOrigin::signed(BOB), pool_id));
+        assert_eq!(Assets::balance(USDT, TREASURY), accrued);
+        assert_eq!(StableProtocolFees::<Test>::get(pool_id, USDT), 0);
+    });
+}
+
+#[test]
+fn zap_in_spends_only_the_single_asset() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+
+        assert_ok!(LiquidityPool::add_liquidity_single_asset(
+            RuntimeOrigin::signed(BOB),
+            NRSH,
+            ELXR,
+            100_000_000,
+            1,
+            None
+        ));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 100_000_000);
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE);
+        assert!(lp_balance(NRSH, ELXR, BOB) > 0);
+
+        // The swap output is deposited in full, so the reserves hold everything but the protocol fee
+        let pallet = LiquidityPool::account_id();
+        let (reserve_a, reserve_b) = LiquidityPools::<Test>::get((NRSH, ELXR));
+        assert_eq!(Assets::balance(NRSH, pallet), reserve_a + ProtocolFees::<Test>::get((NRSH, ELXR), NRSH));
+        assert_eq!(Assets::balance(ELXR, pallet), reserve_b);
+    });
+}
+
+#[test]
+fn zap_split_leaves_nothing_behind_after_the_protocol_fee() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+        let amount = 100_000_000;
+        let (reserve_in, reserve_other, fee) = LiquidityPool::get_reserves(NRSH, ELXR).unwrap();
+        let total = TotalLiquidity::<Test>::get((NRSH, ELXR));
+
+        let swap = LiquidityPool::optimal_zap_amount(reserve_in, amount, fee).unwrap();
+        let swapped = LiquidityPool::get_amount_out(swap, reserve_in, reserve_other, fee).unwrap();
+        let protocol_fee = ProtocolFeeShare::get().mul_floor(fee.mul_floor(swap));
+
+        // The remainder and the swap output claim the same share of the pool, so neither
+        // side is left over as a donation to the LPs
+        let claim_in = (amount - swap) * total / (reserve_in + swap - protocol_fee);
+        let claim_other = swapped * total / (reserve_other - swapped);
+        assert!(claim_in.abs_diff(claim_other) <= 2, "{} vs {}", claim_in, claim_other);
+    });
+}
+
+#[test]
+fn zap_round_trip_only_loses_the_swap_fees() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+
+        // ELXR is the second asset of the pool, so the amounts are reordered on the way
+        assert_ok!(LiquidityPool::add_liquidity_single_asset(
+            RuntimeOrigin::signed(BOB),
+            ELXR,
+            NRSH,
+            100_000_000,
+            1,
+            None
+        ));
+        let shares = lp_balance(NRSH, ELXR, BOB);
+        assert_ok!(LiquidityPool::remove_liquidity_single_asset(
+            RuntimeOrigin::signed(BOB),
+            ELXR,
+            NRSH,
+            shares,
+            99_000_000,
+            None
+        ));
+
+        assert_eq!(lp_balance(NRSH, ELXR, BOB), 0);
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE);
+        let lost = INITIAL_BALANCE - Assets::balance(ELXR, BOB);
+        assert!(lost > 0 && lost < 1_000_000);
+    });
+}
+
+#[test]
+fn zaps_respect_their_limits() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(
+                RuntimeOrigin::signed(BOB),
+                NRSH,
+                ELXR,
+                100_000_000,
+                50_000_000,
+                None
+            ),
+            Error::<Test>::InsufficientLiquidity
+        );
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, 0, 0, None),
+            Error::<Test>::InvalidAmount
+        );
+
+        let shares = lp_balance(NRSH, ELXR, ALICE) / 10;
+        assert_noop!(
+            LiquidityPool::remove_liquidity_single_asset(
+                RuntimeOrigin::signed(ALICE),
+                NRSH,
+                ELXR,
+                shares,
+                200_000_000,
+                None
+            ),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, shares, 0, None),
+            Error::<Test>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn zaps_need_a_funded_constant_product_pool() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), NRSH, USDT));
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, USDT, 1_000_000, 0, None),
+            Error::<Test>::InsufficientLiquidity
+        );
+
+        seed_concentrated_pool();
+        open(ALICE, -100, 100, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 0, None),
+            Error::<Test>::WrongPoolKind
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity_single_asset(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1, 0, None),
+            Error::<Test>::WrongPoolKind
+        );
+    });
+}
+
+#[test]
+fn calls_included_after_their_deadline_expire() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        System::set_block_number(10);
+
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 1_000, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::swap_exact_tokens_for_tokens(
+                RuntimeOrigin::signed(BOB),
+                path(&[NRSH, ELXR]),
+                1_000,
+                0,
+                Some(9)
+            ),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::swap_tokens_for_exact_tokens(
+                RuntimeOrigin::signed(BOB),
+                path(&[NRSH, ELXR]),
+                1_000,
+                10_000,
+                Some(9)
+            ),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+        assert_noop!(
+            LiquidityPool::remove_liquidity_single_asset(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+        let pool_id = seed_stable_pool();
+        assert_noop!(
+            LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, NRSH, ELXR, 1_000, 0, Some(9)),
+            Error::<Test>::Expired
+        );
+
+        // The deadline block itself is still in time
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, Some(10)));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 1_000, 0, Some(10)));
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 100, 0, 0, Some(10)));
+        assert_ok!(LiquidityPool::swap_exact_tokens_for_tokens(
+            RuntimeOrigin::signed(BOB),
+            path(&[NRSH, ELXR]),
+            1_000,
+            0,
+            Some(10)
+        ));
+        assert_ok!(LiquidityPool::stable_swap(RuntimeOrigin::signed(BOB), pool_id, NRSH, ELXR, 1_000, 0, Some(10)));
+    });
+}
+
+#[test]
+fn stale_calls_are_rejected_before_inclusion() {
+    new_test_ext().execute_with(|| {
+        let check = CheckSwapDeadline::<Test>::new();
+        let info = DispatchInfo::default();
+        let swap = RuntimeCall::LiquidityPool(crate::Call::swap {
+            asset_in: NRSH,
+            asset_out: ELXR,
+            amount_in: 1_000,
+            min_amount_out: 0,
+            deadline: Some(5),
+        });
+
+        // Valid calls leave the pool once their deadline block has passed
+        System::set_block_number(3);
+        assert_eq!(check.validate(&BOB, &swap, &info, 0).unwrap().longevity, 3);
+        assert_ok!(check.clone().pre_dispatch(&BOB, &swap, &info, 0));
+
+        System::set_block_number(6);
+        assert_eq!(check.validate(&BOB, &swap, &info, 0), Err(InvalidTransaction::Stale.into()));
+        assert_eq!(check.clone().pre_dispatch(&BOB, &swap, &info, 0), Err(InvalidTransaction::Stale.into()));
+
+        // Routed swaps, zaps and orders carry theirs the same way
+        let routed = RuntimeCall::LiquidityPool(crate::Call::swap_exact_tokens_for_tokens {
+            path: path(&[NRSH, ELXR]),
+            amount_in: 1_000,
+            amount_out_min: 0,
+            deadline: Some(5),
+        });
+        let zap = RuntimeCall::LiquidityPool(crate::Call::add_liquidity_single_asset {
+            asset_in: NRSH,
+            asset_other: ELXR,
+            amount_in: 1_000,
+            min_liquidity: 0,
+            deadline: Some(5),
+        });
+        let order = RuntimeCall::LiquidityPool(crate::Call::place_order {
+            asset_in: NRSH,
+            asset_out: ELXR,
+            amount_in: 1_000,
+            min_amount_out: 1_000,
+            kind: OrderKind::Limit,
+            expires_at: Some(5),
+        });
+        for call in [&routed, &zap, &order] {
+            assert_eq!(check.validate(&BOB, call, &info, 0), Err(InvalidTransaction::Stale.into()));
+        }
+
+        // Calls without a deadline are left alone
+        let claim = RuntimeCall::LiquidityPool(crate::Call::claim_protocol_fees { asset_a: NRSH, asset_b: ELXR });
+        assert_eq!(check.validate(&BOB, &claim, &info, 0), Ok(ValidTransaction::default()));
+    });
+}
+
+#[test]
+fn paused_pools_only_allow_withdrawals() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+        assert_ok!(LiquidityPool::pause_pool(RuntimeOrigin::root(), ELXR, NRSH));
+        System::assert_last_event(crate::Event::<Test>::PoolPaused(NRSH, ELXR).into());
+
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::PoolPaused
+        );
+        assert_noop!(
+            LiquidityPool::swap_exact_tokens_for_tokens(
+                RuntimeOrigin::signed(BOB),
+                path(&[USDT, ELXR, NRSH]),
+                1_000,
+                0,
+                None
+            ),
+            Error::<Test>::PoolPaused
+        );
+        assert_noop!(
+            LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 1_000, 0, None),
+            Error::<Test>::PoolPaused
+        );
+        assert_eq!(LiquidityPool::quote_price_exact_in(vec![NRSH, ELXR], 1_000), None);
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, 0, None));
+
+        // Other pools keep trading
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), USDT, ELXR, 1_000, 0, None));
+
+        assert_ok!(LiquidityPool::unpause_pool(RuntimeOrigin::root(), NRSH, ELXR));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None));
+    });
+}
+
+#[test]
+fn pool_fees_are_set_within_bounds() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let fee_tier = Perquintill::from_percent(1);
+        assert_ok!(LiquidityPool::set_pool_fee(RuntimeOrigin::root(), NRSH, ELXR, fee_tier));
+        System::assert_last_event(crate::Event::<Test>::PoolFeeSet(NRSH, ELXR, fee_tier).into());
+
+        // 1% of 10_000 is taken before the constant product quote
+        assert_eq!(LiquidityPool::quote_price_exact_in(vec![NRSH, ELXR], 10_000), Some(9_802));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, 0, None));
+        assert_eq!(ProtocolFees::<Test>::get((NRSH, ELXR), NRSH), 20);
+
+        for fee_tier in [Perquintill::from_parts(1), Perquintill::from_percent(2)] {
+            assert_noop!(
+                LiquidityPool::set_pool_fee(RuntimeOrigin::root(), NRSH, ELXR, fee_tier),
+                Error::<Test>::FeeOutOfBounds
+            );
+        }
+        assert_noop!(
+            LiquidityPool::set_pool_fee(RuntimeOrigin::root(), NRSH, USDT, fee_tier),
+            Error::<Test>::PoolDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn denied_assets_cannot_be_pooled_or_traded() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_ok!(LiquidityPool::deny_asset(RuntimeOrigin::root(), ELXR));
+        System::assert_last_event(crate::Event::<Test>::AssetDenied(ELXR).into());
+
+        assert_noop!(
+            LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), ELXR, USDT),
+            Error::<Test>::AssetNotAllowed
+        );
+        assert_noop!(
+            LiquidityPool::create_stable_pool(RuntimeOrigin::root(), vec![ELXR, USDT].try_into().unwrap(), 100),
+            Error::<Test>::AssetNotAllowed
+        );
+        assert_noop!(
+            LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::AssetNotAllowed
+        );
+        assert_noop!(
+            LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None),
+            Error::<Test>::AssetNotAllowed
+        );
+        // Providers can still take their funds out
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_000, 0, 0, None));
+
+        assert_ok!(LiquidityPool::allow_asset(RuntimeOrigin::root(), ELXR));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None));
+        assert_ok!(LiquidityPool::create_pool(RuntimeOrigin::signed(ALICE), ELXR, USDT));
+    });
+}
+
+#[test]
+fn governance_calls_require_the_admin_origin() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let origin = || RuntimeOrigin::signed(ALICE);
+        assert_noop!(LiquidityPool::pause_pool(origin(), NRSH, ELXR), DispatchError::BadOrigin);
+        assert_noop!(LiquidityPool::unpause_pool(origin(), NRSH, ELXR), DispatchError::BadOrigin);
+        assert_noop!(
+            LiquidityPool::set_pool_fee(origin(), NRSH, ELXR, Perquintill::from_percent(1)),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(LiquidityPool::allow_asset(origin(), ELXR), DispatchError::BadOrigin);
+        assert_noop!(LiquidityPool::deny_asset(origin(), ELXR), DispatchError::BadOrigin);
+    });
+}
+
+/// A seeded NRSH/ELXR pool with Alice and Bob holding shares and a farm account funded
+/// with USDT and ELXR rewards
+fn seed_farm() {
+    seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+    assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 1_000_000, 0, None));
+    let farm = LiquidityPool::farm_account_id();
+    assert_ok!(Assets::mint_into(USDT, &farm, 1_000_000));
+    assert_ok!(Assets::mint_into(ELXR, &farm, 1_000_000));
+}
+
+fn stake(who: AccountId, amount: Balance) {
+    assert_ok!(LiquidityPool::stake(RuntimeOrigin::signed(who), NRSH, ELXR, amount));
+}
+
+#[test]
+fn farm_and_order_accounts_hold_non_sufficient_assets() {
+    new_test_ext().execute_with(|| {
+        // Nothing is minted into the farm account, so only its own provider lets it hold
+        // the LP shares, which are not sufficient
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000_000, 1_000_000, 0, None));
+        stake(BOB, 1_000);
+        assert_eq!(Assets::balance(lp_asset(NRSH, ELXR), &LiquidityPool::farm_account_id()), 1_000);
+
+        // The order escrow takes a non-sufficient asset the same way
+        const OTHER: AssetId = 4;
+        assert_ok!(<Assets as Create<AccountId>>::create(OTHER, ALICE, false, 1));
+        assert_ok!(Assets::mint_into(OTHER, &ALICE, 1_000_000));
+        assert_ok!(Assets::mint_into(OTHER, &BOB, 1_000_000));
+        seed_pool(NRSH, OTHER, 1_000_000, 1_000_000);
+        place(BOB, OTHER, NRSH, 1_000, 1_000, OrderKind::Limit);
+        assert_eq!(Assets::balance(OTHER, &LiquidityPool::order_account_id()), 1_000);
+    });
+}
+
+#[test]
+fn farm_rewards_are_shared_by_stake_and_time() {
+    new_test_ext().execute_with(|| {
+        seed_farm();
+        assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 100, 1, 101));
+
+        stake(ALICE, 500_000);
+        assert_eq!(Assets::balance(lp_asset(NRSH, ELXR), LiquidityPool::farm_account_id()), 500_000);
+
+        // Alice earns everything for ten blocks, then shares equally with Bob for ten more
+        System::set_block_number(11);
+        stake(BOB, 500_000);
+        System::set_block_number(21);
+        assert_eq!(LiquidityPool::pending_rewards(&ALICE, NRSH, ELXR), vec![(USDT, 1_500)]);
+        assert_eq!(LiquidityPool::pending_rewards(&BOB, NRSH, ELXR), vec![(USDT, 500)]);
+
+        assert_ok!(LiquidityPool::claim_rewards(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        assert_eq!(Assets::balance(USDT, ALICE), INITIAL_BALANCE + 1_500);
+        assert_eq!(LiquidityPool::pending_rewards(&ALICE, NRSH, ELXR), vec![(USDT, 0)]);
+    });
+}
+
+#[test]
+fn farms_emit_several_reward_assets_until_their_schedules_end() {
+    new_test_ext().execute_with(|| {
+        seed_farm();
+        assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 100, 1, 11));
+        assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, ELXR, 10, 1, 101));
+        stake(ALICE, 999_000);
+
+        // Unstaking returns the shares and keeps what was earned claimable
+        System::set_block_number(51);
+        assert_ok!(LiquidityPool::unstake(RuntimeOrigin::signed(ALICE), ELXR, NRSH, 999_000));
+        assert_eq!(lp_balance(NRSH, ELXR, ALICE), 999_000);
+        System::set_block_number(61);
+        assert_eq!(LiquidityPool::pending_rewards(&ALICE, NRSH, ELXR), vec![(USDT, 1_000), (ELXR, 500)]);
+
+        let elxr = Assets::balance(ELXR, ALICE);
+        assert_ok!(LiquidityPool::claim_rewards(RuntimeOrigin::signed(ALICE), NRSH, ELXR));
+        System::assert_last_event(
+            crate::Event::<Test>::RewardsClaimed(ALICE, NRSH, ELXR, vec![(USDT, 1_000), (ELXR, 500)]).into(),
+        );
+        assert_eq!(Assets::balance(USDT, ALICE), INITIAL_BALANCE + 1_000);
+        assert_eq!(Assets::balance(ELXR, ALICE), elxr + 500);
+
+        // Settling writes storage before the check, so dispatch to get the rollback
+        assert_noop!(
+            dispatch(ALICE, crate::Call::claim_rewards { asset_a: NRSH, asset_b: ELXR }),
+            Error::<Test>::NoRewards
+        );
+    });
+}
+
+#[test]
+fn changing_a_schedule_keeps_what_was_emitted() {
+    new_test_ext().execute_with(|| {
+        seed_farm();
+        assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 100, 1, 101));
+        stake(ALICE, 500_000);
+
+        System::set_block_number(11);
+        assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 200, 1, 101));
+        System::set_block_number(21);
+        assert_eq!(LiquidityPool::pending_rewards(&ALICE, NRSH, ELXR), vec![(USDT, 3_000)]);
+    });
+}
+
+#[test]
+fn farming_calls_check_their_inputs() {
+    new_test_ext().execute_with(|| {
+        seed_farm();
+        assert_noop!(
+            LiquidityPool::set_reward_schedule(RuntimeOrigin::signed(ALICE), NRSH, ELXR, USDT, 100, 1, 101),
+            DispatchError::BadOrigin
+        );
+        for (start, end) in [(50, 50), (60, 50), (0, 1)] {
+            assert_noop!(
+                LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 100, start, end),
+                Error::<Test>::InvalidRewardSchedule
+            );
+        }
+        for reward_asset in [NATIVE, NRSH, ELXR] {
+            assert_ok!(LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, reward_asset, 1, 1, 101));
+        }
+        assert_noop!(
+            LiquidityPool::set_reward_schedule(RuntimeOrigin::root(), NRSH, ELXR, USDT, 1, 1, 101),
+            Error::<Test>::TooManyRewardAssets
+        );
+
+        assert_noop!(LiquidityPool::stake(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 0), Error::<Test>::InvalidAmount);
+        assert_noop!(
+            LiquidityPool::stake(RuntimeOrigin::signed(CHARLIE), NRSH, ELXR, 1),
+            Error::<Test>::InsufficientBalance
+        );
+        stake(ALICE, 1_000);
+        assert_noop!(
+            LiquidityPool::unstake(RuntimeOrigin::signed(ALICE), NRSH, ELXR, 1_001),
+            Error::<Test>::InsufficientBalance
+        );
+        assert_noop!(
+            LiquidityPool::stake(RuntimeOrigin::signed(ALICE), NRSH, USDT, 1),
+            Error::<Test>::PoolDoesNotExist
+        );
+    });
+}
+
+fn remark() -> Box<RuntimeCall> {
+    Box::new(RuntimeCall::System(frame_system::Call::remark { remark: vec![] }))
+}
+
+fn flash(
+    who: AccountId,
+    a_out: Balance,
+    b_out: Balance,
+    a_in: Balance,
+    b_in: Balance,
+    call: Box<RuntimeCall>,
+) -> DispatchResultWithPostInfo {
+    dispatch(
+        who,
+        crate::Call::flash_swap {
+            asset_a: NRSH,
+            asset_b: ELXR,
+            amount_a_out: a_out,
+            amount_b_out: b_out,
+            amount_a_in: a_in,
+            amount_b_in: b_in,
+            call,
+        },
+    )
+}
+
+#[test]
+fn flash_loan_repaid_with_fee() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+
+        // 10_036 leaves 1_000_036 - 37 of fee, one short of the original reserve
+        assert_noop!(flash(BOB, 10_000, 0, 10_036, 0, remark()), Error::<Test>::FlashSwapNotRepaid);
+
+        assert_ok!(flash(BOB, 10_000, 0, 10_037, 0, remark()));
+        System::assert_last_event(crate::Event::<Test>::FlashSwap(BOB, NRSH, ELXR, 10_000, 0, 10_037, 0).into());
+
+        // The fee of 37 stays in the pool, less the protocol's 20%
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)), (1_000_030, 1_000_000));
+        assert_eq!(ProtocolFees::<Test>::get((NRSH, ELXR), NRSH), 7);
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 37);
+        assert_eq!(Assets::balance(NRSH, LiquidityPool::account_id()), 1_000_037);
+    });
+}
+
+#[test]
+fn flash_swap_repaid_in_the_other_asset() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+
+        // A plain swap of 10_000 NRSH returns 9_865 ELXR, and a flash swap cannot take more
+        assert_noop!(flash(BOB, 0, 9_866, 10_000, 0, remark()), Error::<Test>::FlashSwapNotRepaid);
+        assert_ok!(flash(BOB, 0, 9_865, 10_000, 0, remark()));
+
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)), (1_009_993, 990_135));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 10_000);
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE + 9_865);
+    });
+}
+
+#[test]
+fn flash_swap_dispatches_callback_as_borrower() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let charlie = Assets::balance(ELXR, CHARLIE);
+
+        // Pass the borrowed ELXR on, then repay it with BOB's own funds
+        let call = Box::new(RuntimeCall::Assets(pallet_assets::Call::transfer {
+            id: ELXR,
+            target: CHARLIE,
+            amount: 5_000,
+        }));
+        assert_ok!(flash(BOB, 0, 5_000, 0, 5_019, call));
+
+        assert_eq!(Assets::balance(ELXR, CHARLIE), charlie + 5_000);
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE - 5_019);
+    });
+}
+
+#[test]
+fn flash_swap_locks_pool_during_callback() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+
+        let reenter = Box::new(RuntimeCall::LiquidityPool(crate::Call::swap {
+            asset_in: NRSH,
+            asset_out: ELXR,
+            amount_in: 1_000,
+            min_amount_out: 0,
+            deadline: None,
+        }));
+        assert_noop!(flash(BOB, 10_000, 0, 10_037, 0, reenter), Error::<Test>::PoolLocked);
+
+        // Other pools stay usable, and the lock is gone afterwards
+        let other = Box::new(RuntimeCall::LiquidityPool(crate::Call::swap {
+            asset_in: ELXR,
+            asset_out: USDT,
+            amount_in: 1_000,
+            min_amount_out: 0,
+            deadline: None,
+        }));
+        assert_ok!(flash(BOB, 10_000, 0, 10_037, 0, other));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None));
+    });
+}
+
+#[test]
+fn flash_swap_rejects_invalid_requests() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+
+        assert_noop!(flash(BOB, 0, 0, 1_000, 0, remark()), Error::<Test>::InvalidAmount);
+        assert_noop!(flash(BOB, 1_000_000, 0, 2_000_000, 0, remark()), Error::<Test>::InsufficientLiquidity);
+
+        assert_ok!(LiquidityPool::create_concentrated_pool(RuntimeOrigin::signed(ALICE), ELXR, USDT, 10, 0));
+        assert_noop!(
+            dispatch(
+                BOB,
+                crate::Call::flash_swap {
+                    asset_a: ELXR,
+                    asset_b: USDT,
+                    amount_a_out: 1_000,
+                    amount_b_out: 0,
+                    amount_a_in: 1_004,
+                    amount_b_in: 0,
+                    call: remark(),
+                }
+            ),
+            Error::<Test>::WrongPoolKind
+        );
+    });
+}
+
+fn place(
+    who: AccountId,
+    asset_in: AssetId,
+    asset_out: AssetId,
+    amount_in: Balance,
+    min_amount_out: Balance,
+    kind: OrderKind,
+) -> u64 {
+    let order_id = crate::NextOrderId::<Test>::get();
+    assert_ok!(LiquidityPool::place_order(
+        RuntimeOrigin::signed(who),
+        asset_in,
+        asset_out,
+        amount_in,
+        min_amount_out,
+        kind,
+        None
+    ));
+    order_id
+}
+
+fn run_on_idle() {
+    LiquidityPool::on_idle(System::block_number(), Weight::MAX);
+}
+
+#[test]
+fn limit_order_escrows_and_fills_once_the_price_crosses() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+
+        // 10_000 NRSH quote 9_865 ELXR today, short of the 11_000 asked for
+        let order_id = place(BOB, NRSH, ELXR, 10_000, 11_000, OrderKind::Limit);
+        System::assert_last_event(crate::Event::<Test>::OrderPlaced(order_id, BOB, NRSH, ELXR, 10_000, 11_000).into());
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE - 10_000);
+        assert_eq!(Assets::balance(NRSH, LiquidityPool::order_account_id()), 10_000);
+        assert_eq!(Balances::reserved_balance(BOB), 100);
+
+        run_on_idle();
+        assert!(Orders::<Test>::contains_key(order_id));
+
+        // Buying NRSH lifts its price until the order pays 11_920 ELXR
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(CHARLIE), ELXR, NRSH, 100_000, 0, None));
+        run_on_idle();
+        System::assert_last_event(crate::Event::<Test>::OrderFilled(order_id, BOB, 10_000, 11_920).into());
+
+        assert!(!Orders::<Test>::contains_key(order_id));
+        assert!(PoolOrders::<Test>::get((NRSH, ELXR)).is_empty());
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE + 11_920);
+        assert_eq!(Assets::balance(NRSH, LiquidityPool::order_account_id()), 0);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        // The fill traded against the pool like a swap, protocol fee included
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)), (909_396 + 10_000 - 7, 1_099_927 - 11_920));
+    });
+}
+
+#[test]
+fn stop_loss_fills_once_the_price_falls_to_its_trigger() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+
+        let trigger = FixedU128::from_rational(9, 10);
+        let order_id = place(BOB, NRSH, ELXR, 10_000, 1, OrderKind::StopLoss(trigger));
+        run_on_idle();
+        assert!(Orders::<Test>::contains_key(order_id));
+
+        // Selling NRSH drops its price to about 0.83 ELXR
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(CHARLIE), NRSH, ELXR, 100_000, 0, None));
+        let order = Orders::<Test>::get(order_id).unwrap();
+        let amount_out = LiquidityPool::order_quote(&order).unwrap();
+        run_on_idle();
+
+        System::assert_last_event(crate::Event::<Test>::OrderFilled(order_id, BOB, 10_000, amount_out).into());
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE + amount_out);
+    });
+}
+
+#[test]
+fn cancel_order_refunds_owner() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let order_id = place(BOB, ELXR, NRSH, 5_000, 50_000, OrderKind::Limit);
+
+        assert_noop!(LiquidityPool::cancel_order(RuntimeOrigin::signed(CHARLIE), order_id), Error::<Test>::NotOrderOwner);
+        assert_ok!(LiquidityPool::cancel_order(RuntimeOrigin::signed(BOB), order_id));
+        System::assert_last_event(crate::Event::<Test>::OrderCancelled(order_id, BOB).into());
+
+        assert_eq!(Assets::balance(ELXR, BOB), INITIAL_BALANCE);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert!(PoolOrders::<Test>::get((NRSH, ELXR)).is_empty());
+        assert_noop!(LiquidityPool::cancel_order(RuntimeOrigin::signed(BOB), order_id), Error::<Test>::OrderNotFound);
+    });
+}
+
+#[test]
+fn orders_expire_unfilled() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_noop!(
+            LiquidityPool::place_order(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 50_000, OrderKind::Limit, Some(0)),
+            Error::<Test>::Expired
+        );
+
+        let order_id = crate::NextOrderId::<Test>::get();
+        assert_ok!(LiquidityPool::place_order(
+            RuntimeOrigin::signed(BOB),
+            NRSH,
+            ELXR,
+            1_000,
+            50_000,
+            OrderKind::Limit,
+            Some(5)
+        ));
+
+        // Still resting in its last block
+        System::set_block_number(5);
+        run_on_idle();
+        assert!(Orders::<Test>::contains_key(order_id));
+
+        System::set_block_number(6);
+        run_on_idle();
+        System::assert_last_event(crate::Event::<Test>::OrderExpired(order_id, BOB).into());
+        assert!(!Orders::<Test>::contains_key(order_id));
+        assert_eq!(Assets::balance(NRSH, BOB), INITIAL_BALANCE);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+    });
+}
+
+#[test]
+fn place_order_checks_pool_and_book() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert_ok!(LiquidityPool::create_concentrated_pool(RuntimeOrigin::signed(ALICE), ELXR, USDT, 10, 0));
+
+        assert_noop!(
+            LiquidityPool::place_order(RuntimeOrigin::signed(BOB), NRSH, ELXR, 0, 1, OrderKind::Limit, None),
+            Error::<Test>::InvalidAmount
+        );
+        assert_noop!(
+            LiquidityPool::place_order(RuntimeOrigin::signed(BOB), NRSH, USDT, 1_000, 1, OrderKind::Limit, None),
+            Error::<Test>::PoolDoesNotExist
+        );
+        assert_noop!(
+            LiquidityPool::place_order(RuntimeOrigin::signed(BOB), ELXR, USDT, 1_000, 1, OrderKind::Limit, None),
+            Error::<Test>::WrongPoolKind
+        );
+
+        for _ in 0..4 {
+            place(BOB, NRSH, ELXR, 1_000, 50_000, OrderKind::Limit);
+        }
+        assert_noop!(
+            LiquidityPool::place_order(RuntimeOrigin::signed(CHARLIE), ELXR, NRSH, 1_000, 50_000, OrderKind::Limit, None),
+            Error::<Test>::TooManyOrders
+        );
+    });
+}
+
+#[test]
+fn orders_on_paused_pools_wait() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        let order_id = place(BOB, NRSH, ELXR, 1_000, 1, OrderKind::Limit);
+
+        assert_ok!(LiquidityPool::pause_pool(RuntimeOrigin::root(), NRSH, ELXR));
+        run_on_idle();
+        assert!(Orders::<Test>::contains_key(order_id));
+        assert_eq!(Assets::balance(NRSH, LiquidityPool::order_account_id()), 1_000);
+
+        assert_ok!(LiquidityPool::unpause_pool(RuntimeOrigin::root(), NRSH, ELXR));
+        run_on_idle();
+        assert!(!Orders::<Test>::contains_key(order_id));
+    });
+}
+
+#[test]
+fn order_matching_resumes_where_weight_ran_out() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+        let first = place(BOB, NRSH, ELXR, 1_000, 1, OrderKind::Limit);
+        let second = place(BOB, ELXR, USDT, 1_000, 1, OrderKind::Limit);
+
+        // Enough weight for one book of one order per block
+        let weight = <() as WeightInfo>::fill_order();
+        assert_eq!(LiquidityPool::on_idle(1, weight), weight);
+        assert_eq!(Orders::<Test>::contains_key(first) as u8 + Orders::<Test>::contains_key(second) as u8, 1);
+
+        LiquidityPool::on_idle(2, weight);
+        assert!(!Orders::<Test>::contains_key(first));
+        assert!(!Orders::<Test>::contains_key(second));
+
+        // Too little weight to start any book does nothing
+        let third = place(BOB, NRSH, ELXR, 1_000, 1, OrderKind::Limit);
+        assert_eq!(LiquidityPool::on_idle(3, weight.saturating_sub(Weight::from_ref_time(1))), Weight::zero());
+        assert!(Orders::<Test>::contains_key(third));
+    });
+}
+
+#[test]
+fn order_matching_works_through_a_book_longer_than_a_block() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        seed_pool(ELXR, USDT, 1_000_000, 1_000_000);
+        let book: Vec<_> = (0..3).map(|_| place(BOB, NRSH, ELXR, 1_000, 1, OrderKind::Limit)).collect();
+        let other = place(BOB, ELXR, USDT, 1_000, 1, OrderKind::Limit);
+
+        // Every block has room for one order only, yet each one checks the next order
+        let weight = <() as WeightInfo>::fill_order();
+        for block in 1..=4 {
+            assert_eq!(LiquidityPool::on_idle(block, weight), weight);
+            let left = book.iter().chain([&other]).filter(|id| Orders::<Test>::contains_key(**id)).count();
+            assert_eq!(left, 4 - block as usize);
+        }
+        assert!(PoolOrders::<Test>::get((NRSH, ELXR)).is_empty());
+        assert!(PoolOrders::<Test>::get((ELXR, USDT)).is_empty());
+    });
+}
+
+#[test]
+fn events_report_post_trade_reserves() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        System::assert_last_event(
+            crate::Event::<Test>::LiquidityAdded {
+                who: ALICE,
+                asset_a: NRSH,
+                asset_b: ELXR,
+                amount_a: 1_000_000,
+                amount_b: 1_000_000,
+                liquidity: 999_000,
+                reserve_a: 1_000_000,
+                reserve_b: 1_000_000,
+            }
+            .into(),
+        );
+
+        // The protocol's 7 of the 36 fee leave the reserves
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, 0, None));
+        System::assert_has_event(
+            crate::Event::<Test>::Swap {
+                who: BOB,
+                asset_in: NRSH,
+                asset_out: ELXR,
+                amount_in: 10_000,
+                amount_out: 9_865,
+                fee_amount: 36,
+                reserve_in: 1_009_993,
+                reserve_out: 990_135,
+            }
+            .into(),
+        );
+
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(ALICE), ELXR, NRSH, 99_900, 0, 0, None));
+        let (reserve_a, reserve_b) = LiquidityPools::<Test>::get((NRSH, ELXR));
+        System::assert_last_event(
+            crate::Event::<Test>::LiquidityRemoved {
+                who: ALICE,
+                asset_a: NRSH,
+                asset_b: ELXR,
+                amount_a: 1_009_993 - reserve_a,
+                amount_b: 990_135 - reserve_b,
+                liquidity: 99_900,
+                reserve_a,
+                reserve_b,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn swaps_aggregate_into_epoch_stats() {
+    new_test_ext().execute_with(|| {
+        seed_pool(NRSH, ELXR, 1_000_000, 1_000_000);
+        assert!(PoolStats::<Test>::get((NRSH, ELXR)).is_empty());
+
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 10_000, 0, None));
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), ELXR, NRSH, 1_000, 0, None));
+        assert_eq!(
+            PoolStats::<Test>::get((NRSH, ELXR)).into_inner(),
+            vec![EpochStats { epoch: 0, volume_a: 10_000 + 1_015, volume_b: 9_865 + 1_000, fees_a: 36, fees_b: 3, trades: 2 }]
+        );
+
+        // Queried the other way round, the sides swap
+        assert_eq!(
+            LiquidityPool::epoch_stats(ELXR, NRSH),
+            vec![EpochStats { epoch: 0, volume_a: 10_865, volume_b: 11_015, fees_a: 3, fees_b: 36, trades: 2 }]
+        );
+
+        // Only the last three epochs with trades are kept
+        for block in [15, 45, 55] {
+            System::set_block_number(block);
+            assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, 1_000, 0, None));
+        }
+        let epochs: Vec<u32> = PoolStats::<Test>::get((NRSH, ELXR)).iter().map(|stats| stats.epoch).collect();
+        assert_eq!(epochs, vec![1, 4, 5]);
+        assert!(PoolStats::<Test>::get((NRSH, ELXR)).iter().all(|stats| stats.trades == 1 && stats.fees_a == 3));
+    });
+}
+
+/// Operations the invariant tests apply to a NRSH/ELXR pool, in any order
+#[derive(Clone, Debug)]
+enum Op {
+    Add { who: AccountId, amount_a: Balance, amount_b: Balance },
+    Remove { who: AccountId, per_mille: Balance },
+    Swap { who: AccountId, a_to_b: bool, amount: Balance },
+    Claim,
+}
+
+fn account() -> impl Strategy<Value = AccountId> {
+    prop_oneof![Just(ALICE), Just(BOB), Just(CHARLIE)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (account(), 1..1_000_000_000u128, 1..1_000_000_000u128)
+            .prop_map(|(who, amount_a, amount_b)| Op::Add { who, amount_a, amount_b }),
+        (account(), 1..=1_000u128).prop_map(|(who, per_mille)| Op::Remove { who, per_mille }),
+        (account(), any::<bool>(), 1..1_000_000_000u128)
+            .prop_map(|(who, a_to_b, amount)| Op::Swap { who, a_to_b, amount }),
+        Just(Op::Claim),
+    ]
+}
+
+/// Dispatch through the runtime so failed calls are rolled back like real extrinsics
+fn dispatch(who: AccountId, call: crate::Call<Test>) -> DispatchResultWithPostInfo {
+    RuntimeCall::LiquidityPool(call).dispatch(RuntimeOrigin::signed(who))
+}
+
+fn apply(op: &Op) -> DispatchResultWithPostInfo {
+    match *op {
+        Op::Add { who, amount_a, amount_b } => dispatch(
+            who,
+            crate::Call::add_liquidity {
+                asset_a: NRSH,
+                asset_b: ELXR,
+                amount_a,
+                amount_b,
+                min_liquidity: 0,
+                deadline: None,
+            },
+        ),
+        Op::Remove { who, per_mille } => {
+            let liquidity = lp_balance(NRSH, ELXR, who) * per_mille / 1_000;
+            dispatch(
+                who,
+                crate::Call::remove_liquidity {
+                    asset_a: NRSH,
+                    asset_b: ELXR,
+                    liquidity,
+                    min_amount_a: 0,
+                    min_amount_b: 0,
+                    deadline: None,
+                },
+            )
+        },
+        Op::Swap { who, a_to_b, amount } => {
+            let (asset_in, asset_out) = if a_to_b { (NRSH, ELXR) } else { (ELXR, NRSH) };
+            dispatch(who, crate::Call::swap { asset_in, asset_out, amount_in: amount, min_amount_out: 0, deadline: None })
+        },
+        Op::Claim => dispatch(ALICE, crate::Call::claim_protocol_fees { asset_a: NRSH, asset_b: ELXR }),
+    }
+}
+
+fn product((reserve_a, reserve_b): (Balance, Balance)) -> sp_core::U256 {
+    sp_core::U256::from(reserve_a) * sp_core::U256::from(reserve_b)
+}
+
+#[test]
+fn migration_merges_reversed_pools_into_canonical_ones() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<LiquidityPool>();
+        let no_provider: AccountId = 77;
+
+        // v0 pools for one pair under both key orders, and one only under the reversed key
+        LiquidityPools::<Test>::insert((NRSH, ELXR), (10_000, 40_000));
+        TotalLiquidity::<Test>::insert((NRSH, ELXR), 20_000);
+        LiquidityTokens::<Test>::insert((NRSH, ELXR, ALICE), 20_000);
+        LiquidityPools::<Test>::insert((ELXR, NRSH), (20_000, 5_000));
+        TotalLiquidity::<Test>::insert((ELXR, NRSH), 10_000);
+        LiquidityTokens::<Test>::insert((ELXR, NRSH, BOB), 6_000);
+        LiquidityTokens::<Test>::insert((ELXR, NRSH, no_provider), 4_000);
+        LiquidityPools::<Test>::insert((USDT, ELXR), (300, 700));
+        TotalLiquidity::<Test>::insert((USDT, ELXR), 500);
+        LiquidityTokens::<Test>::insert((USDT, ELXR, CHARLIE), 500);
+
+        MigrateToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(LiquidityPool::on_chain_storage_version(), 1);
+
+        // Reserves are merged in canonical order. The reversed pool holds half the
+        // canonical one's reserves, so its 10_000 shares become 10_000 canonical ones.
+        assert_eq!(LiquidityPools::<Test>::get((NRSH, ELXR)), (15_000, 60_000));
+        assert!(!LiquidityPools::<Test>::contains_key((ELXR, NRSH)));
+        assert!(!TotalLiquidity::<Test>::contains_key((ELXR, NRSH)));
+        assert_eq!(lp_balance(NRSH, ELXR, ALICE), 20_000);
+        assert_eq!(lp_balance(NRSH, ELXR, BOB), 6_000);
+
+        // Shares the LP asset can't be minted to are held by the pallet account
+        assert_eq!(lp_balance(NRSH, ELXR, no_provider), 0);
+        assert_eq!(lp_balance(NRSH, ELXR, LiquidityPool::account_id()), 4_000);
+        assert_eq!(HeldShares::<Test>::get((NRSH, ELXR, no_provider)), 4_000);
+
+        assert_eq!(LiquidityPools::<Test>::get((ELXR, USDT)), (700, 300));
+        assert_eq!(lp_balance(ELXR, USDT, CHARLIE), 500);
+
+        assert_eq!(LiquidityTokens::<Test>::iter().count(), 0);
+        for (pair, pool) in Pools::<Test>::iter() {
+            assert_eq!(TotalLiquidity::<Test>::get(pair), Assets::total_issuance(pool.lp_asset));
+        }
+        assert_eq!(TotalLiquidity::<Test>::get((NRSH, ELXR)), 30_000);
+    });
+}
+
+#[test]
+fn held_shares_are_released_once_their_owner_can_hold_them() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<LiquidityPool>();
+        let no_provider: AccountId = 77;
+        LiquidityPools::<Test>::insert((NRSH, ELXR), (10_000, 40_000));
+        TotalLiquidity::<Test>::insert((NRSH, ELXR), 20_000);
+        LiquidityTokens::<Test>::insert((NRSH, ELXR, no_provider), 20_000);
+        MigrateToV1::<Test>::on_runtime_upgrade();
+        assert_eq!(HeldShares::<Test>::get((NRSH, ELXR, no_provider)), 20_000);
+
+        assert_noop!(
+            LiquidityPool::release_held_shares(RuntimeOrigin::signed(ALICE), NRSH, ELXR, no_provider),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            LiquidityPool::release_held_shares(RuntimeOrigin::root(), NRSH, ELXR, ALICE),
+            Error::<Test>::NoHeldShares
+        );
+        // The owner still has no provider, so the shares stay held
+        assert!(LiquidityPool::release_held_shares(RuntimeOrigin::root(), NRSH, ELXR, no_provider).is_err());
+        assert_eq!(HeldShares::<Test>::get((NRSH, ELXR, no_provider)), 20_000);
+
+        Balances::make_free_balance_be(&no_provider, 1_000);
+        assert_ok!(LiquidityPool::release_held_shares(RuntimeOrigin::root(), ELXR, NRSH, no_provider));
+        System::assert_last_event(crate::Event::<Test>::HeldSharesReleased(no_provider, NRSH, ELXR, 20_000).into());
+        assert_eq!(lp_balance(NRSH, ELXR, no_provider), 20_000);
+        assert_eq!(lp_balance(NRSH, ELXR, LiquidityPool::account_id()), 0);
+        assert!(!HeldShares::<Test>::contains_key((NRSH, ELXR, no_provider)));
+    });
+}
+
+fn assert_invariants() {
+    let pair = (NRSH, ELXR);
+    let pallet = LiquidityPool::account_id();
+
+    // Every LP share is held by a provider or locked in the pallet account
+    let holders = [ALICE, BOB, CHARLIE, pallet];
+    let shares: Balance = holders.iter().map(|who| lp_balance(NRSH, ELXR, *who)).sum();
+    assert_eq!(shares, TotalLiquidity::<Test>::get(pair));
+    assert_eq!(Assets::total_issuance(lp_asset(NRSH, ELXR)), TotalLiquidity::<Test>::get(pair));
+
+    // The pallet account holds exactly the reserves plus the unclaimed protocol fees
+    let (reserve_a, reserve_b) = LiquidityPools::<Test>::get(pair);
+    assert_eq!(Assets::balance(NRSH, pallet), reserve_a + ProtocolFees::<Test>::get(pair, NRSH));
+    assert_eq!(Assets::balance(ELXR, pallet), reserve_b + ProtocolFees::<Test>::get(pair, ELXR));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn pool_invariants_hold_for_any_sequence_of_operations(ops in prop::collection::vec(op(), 1..40)) {
+        new_test_ext().execute_with(|| {
+            seed_pool(NRSH, ELXR, 1_000_000_000, 1_000_000_000);
+            assert_invariants();
+
+            for (block, op) in ops.iter().enumerate() {
+                System::set_block_number(block as u64 + 2);
+                let k_before = product(LiquidityPools::<Test>::get((NRSH, ELXR)));
+                let result = apply(op);
+                if matches!(op, Op::Swap { .. }) && result.is_ok() {
+                    assert!(product(LiquidityPools::<Test>::get((NRSH, ELXR))) >= k_before, "k decreased after {:?}", op);
+                }
+                assert_invariants();
+            }
+        });
+    }
+
+    #[test]
+    fn swap_never_decreases_k(
+        reserve_a in 1_001..1_000_000_000_000u128,
+        reserve_b in 1_001..1_000_000_000_000u128,
+        amount in 1..1_000_000_000_000u128,
+        a_to_b in any::<bool>(),
+    ) {
+        new_test_ext().execute_with(|| {
+            let ceiling = INITIAL_BALANCE / 2;
+            seed_pool(NRSH, ELXR, reserve_a.min(ceiling), reserve_b.min(ceiling));
+            let k_before = product(LiquidityPools::<Test>::get((NRSH, ELXR)));
+
+            let (asset_in, asset_out) = if a_to_b { (NRSH, ELXR) } else { (ELXR, NRSH) };
+            let amount = amount.min(INITIAL_BALANCE / 2);
+            if LiquidityPool::swap(RuntimeOrigin::signed(BOB), asset_in, asset_out, amount, 0, None).is_ok() {
+                prop_assert!(product(LiquidityPools::<Test>::get((NRSH, ELXR))) >= k_before);
+            }
+            assert_invariants();
+            Ok(())
+        })?;
+    }
+}
+
+#[test]
+fn pools_near_the_balance_limit_keep_trading() {
+    new_test_ext().execute_with(|| {
+        // Products of these amounts overflow 128 bits long before the reserves do
+        let reserve: Balance = 1 << 100;
+        let amount: Balance = 1 << 90;
+        for who in [ALICE, BOB] {
+            assert_ok!(Assets::mint_into(NRSH, &who, reserve));
+            assert_ok!(Assets::mint_into(ELXR, &who, reserve));
+        }
+        seed_pool(NRSH, ELXR, reserve, reserve);
+
+        assert_ok!(LiquidityPool::swap(RuntimeOrigin::signed(BOB), NRSH, ELXR, amount, 0, None));
+        assert_ok!(LiquidityPool::swap_tokens_for_exact_tokens(
+            RuntimeOrigin::signed(BOB),
+            path(&[ELXR, NRSH]),
+            amount,
+            Balance::MAX,
+            None
+        ));
+        assert_ok!(LiquidityPool::add_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, amount, amount, 0, None));
+        let liquidity = lp_balance(NRSH, ELXR, BOB);
+        assert_ok!(LiquidityPool::remove_liquidity(RuntimeOrigin::signed(BOB), NRSH, ELXR, liquidity, 0, 0, None));
+        assert_ok!(LiquidityPool::add_liquidity_single_asset(RuntimeOrigin::signed(BOB), NRSH, ELXR, amount, 0, None));
+        assert_invariants();
+    });
+}
+
+/// Arbitrary-precision reference implementations of the pool math
+mod reference {
+    use num_bigint::BigUint;
+    use sp_runtime::{PerThing, Perquintill};
+
+    fn big(value: u128) -> BigUint {
+        BigUint::from(value)
+    }
+
+    fn narrow(value: BigUint) -> Option<u128> {
+        u128::try_from(value).ok()
+    }
+
+    fn after_fee(amount_in: u128, fee: Perquintill) -> BigUint {
+        let fee_amount = big(amount_in) * big(fee.deconstruct() as u128) / big(Perquintill::ACCURACY as u128);
+        big(amount_in) - fee_amount
+    }
+
+    pub fn amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+        let amount_in_after_fee = after_fee(amount_in, fee);
+        let denominator = big(reserve_in) + &amount_in_after_fee;
+        if denominator == BigUint::from(0u8) {
+            return None;
+        }
+        narrow(amount_in_after_fee * big(reserve_out) / denominator)
+    }
+
+    pub fn amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128, fee: Perquintill) -> Option<u128> {
+        let retained = big(fee.left_from_one().deconstruct() as u128);
+        if amount_out >= reserve_out || retained == BigUint::from(0u8) {
+            return None;
+        }
+        let amount_in_after_fee = narrow(big(reserve_in) * big(amount_out) / big(reserve_out - amount_out) + 1u8)?;
+        let scaled = big(amount_in_after_fee) * big(Perquintill::ACCURACY as u128);
+        narrow((scaled + &retained - 1u8) / retained)
+    }
+
+    pub fn initial_shares(amount_a: u128, amount_b: u128) -> u128 {
+        narrow((big(amount_a) * big(amount_b)).sqrt()).expect("the root of a 256-bit product fits")
+    }
+
+    pub fn shares_for_deposit(a: u128, b: u128, reserve_a: u128, reserve_b: u128, total: u128) -> Option<u128> {
+        if reserve_a == 0 || reserve_b == 0 {
+            return None;
+        }
+        let shares_a = narrow(big(a) * big(total) / big(reserve_a))?;
+        let shares_b = narrow(big(b) * big(total) / big(reserve_b))?;
+        Some(shares_a.min(shares_b))
+    }
+
+    pub fn withdrawal_for_shares(shares: u128, reserve_a: u128, reserve_b: u128, total: u128) -> Option<(u128, u128)> {
+        if total == 0 {
+            return None;
+        }
+        let amount_a = narrow(big(shares) * big(reserve_a) / big(total))?;
+        let amount_b = narrow(big(shares) * big(reserve_b) / big(total))?;
+        Some((amount_a, amount_b))
+    }
+}
+
+fn any_fee() -> impl Strategy<Value = Perquintill> {
+    prop_oneof![
+        Just(Perquintill::zero()),
+        Just(crate::NOURISH_CHAIN_FEE),
+        (0..=Perquintill::ACCURACY).prop_map(Perquintill::from_parts),
+    ]
+}
+
+/// Amounts biased towards the top of the range, where the products overflow 128 bits
+fn any_amount() -> impl Strategy<Value = Balance> {
+    prop_oneof![any::<u128>(), (u128::MAX - 1_000_000)..=u128::MAX, (1u128 << 64)..(1u128 << 100), 0..1_000_000u128]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1_000))]
+
+    #[test]
+    fn amount_out_matches_reference(
+        amount_in in any_amount(),
+        reserve_in in any_amount(),
+        reserve_out in any_amount(),
+        fee in any_fee(),
+    ) {
+        let amount_out = crate::math::amount_out(amount_in, reserve_in, reserve_out, fee);
+        prop_assert_eq!(amount_out, reference::amount_out(amount_in, reserve_in, reserve_out, fee));
+
+        // The trade never lowers the pool's constant product
+        if let Some(amount_out) = amount_out {
+            let amount_in_after_fee = amount_in - fee.mul_floor(amount_in);
+            let k_after = (sp_core::U512::from(reserve_in) + sp_core::U512::from(amount_in_after_fee))
+                * sp_core::U512::from(reserve_out - amount_out);
+            prop_assert!(k_after >= sp_core::U512::from(reserve_in) * sp_core::U512::from(reserve_out));
+        }
+    }
+
+    #[test]
+    fn amount_in_matches_reference_and_covers_the_output(
+        amount_out in any_amount(),
+        reserve_in in any_amount(),
+        reserve_out in any_amount(),
+        fee in any_fee(),
+    ) {
+        let amount_in = crate::math::amount_in(amount_out, reserve_in, reserve_out, fee);
+        prop_assert_eq!(amount_in, reference::amount_in(amount_out, reserve_in, reserve_out, fee));
+
+        if let Some(amount_in) = amount_in {
+            let received = crate::math::amount_out(amount_in, reserve_in, reserve_out, fee);
+            prop_assert!(received.map_or(false, |received| received >= amount_out));
+        }
+    }
+
+    #[test]
+    fn share_math_matches_reference(
+        amount_a in any_amount(),
+        amount_b in any_amount(),
+        reserve_a in any_amount(),
+        reserve_b in any_amount(),
+        total in any_amount(),
+    ) {
+        prop_assert_eq!(crate::math::initial_shares(amount_a, amount_b), reference::initial_shares(amount_a, amount_b));
+        prop_assert_eq!(
+            crate::math::shares_for_deposit(amount_a, amount_b, reserve_a, reserve_b, total),
+            reference::shares_for_deposit(amount_a, amount_b, reserve_a, reserve_b, total)
+        );
+        prop_assert_eq!(
+            crate::math::withdrawal_for_shares(amount_a, reserve_a, reserve_b, total),
+            reference::withdrawal_for_shares(amount_a, reserve_a, reserve_b, total)
+        );
+    }
+
+    #[test]
+    fn zap_amount_is_defined_for_any_pool(
+        reserve_in in any_amount(),
+        amount_in in any_amount(),
+        fee in any_fee(),
+        protocol_share in any_fee(),
+    ) {
+        let swap = crate::math::optimal_zap_amount(reserve_in, amount_in, fee, protocol_share);
+        // Only a fee that takes the whole input has no answer, and the swap never exceeds the input
+        prop_assert_eq!(swap.is_none(), fee.left_from_one().deconstruct() < Perquintill::ACCURACY / 1_000_000);
+        if let Some(swap) = swap {
+            prop_assert!(swap <= amount_in);
+        }
+    }
+}
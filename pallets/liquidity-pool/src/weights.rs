@@ -0,0 +1,720 @@
+//! Weights for pallet_liquidity_pool
+//!
+//! PLACEHOLDER WEIGHTS: this file was not generated by `benchmark pallet`. Its execution
+//! times are hand-picked estimates; only the storage read/write counts follow the
+//! benchmarks in `benchmarking.rs`. Runtimes must not rely on these numbers. Until the file
+//! is regenerated on reference hardware, every weight below is an estimate, not a
+//! measurement. To regenerate it, run the node's `benchmark pallet` subcommand:
+//!
+//! benchmark pallet
+//! --chain=dev
+//! --steps=50
+//! --repeat=20
+//! --pallet=pallet_liquidity_pool
+//! --extrinsic=*
+//! --execution=wasm
+//! --wasm-execution=compiled
+//! --output=./pallets/liquidity-pool/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_liquidity_pool.
+pub trait WeightInfo {
+	fn create_pool() -> Weight;
+	fn add_liquidity() -> Weight;
+	fn remove_liquidity() -> Weight;
+	fn swap() -> Weight;
+	fn swap_exact_tokens_for_tokens(n: u32, ) -> Weight;
+	fn swap_tokens_for_exact_tokens(n: u32, ) -> Weight;
+	fn claim_protocol_fees() -> Weight;
+	fn create_concentrated_pool() -> Weight;
+	fn open_position() -> Weight;
+	fn increase_liquidity() -> Weight;
+	fn decrease_liquidity() -> Weight;
+	fn collect_fees() -> Weight;
+	fn create_stable_pool() -> Weight;
+	fn add_stable_liquidity() -> Weight;
+	fn remove_stable_liquidity() -> Weight;
+	fn stable_swap() -> Weight;
+	fn ramp_amplification() -> Weight;
+	fn stop_amplification_ramp() -> Weight;
+	fn claim_stable_protocol_fees() -> Weight;
+	fn add_liquidity_single_asset() -> Weight;
+	fn remove_liquidity_single_asset() -> Weight;
+	fn pause_pool() -> Weight;
+	fn unpause_pool() -> Weight;
+	fn set_pool_fee() -> Weight;
+	fn pause_stable_pool() -> Weight;
+	fn unpause_stable_pool() -> Weight;
+	fn set_stable_pool_fee() -> Weight;
+	fn allow_asset() -> Weight;
+	fn deny_asset() -> Weight;
+	fn set_reward_schedule() -> Weight;
+	fn stake() -> Weight;
+	fn unstake() -> Weight;
+	fn claim_rewards() -> Weight;
+	fn flash_swap() -> Weight;
+	fn place_order() -> Weight;
+	fn cancel_order() -> Weight;
+	fn fill_order() -> Weight;
+	fn release_held_shares() -> Weight;
+	fn concentrated_swap_hop(t: u32, ) -> Weight;
+}
+
+/// Weights for pallet_liquidity_pool using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: LiquidityPool Pools (r:1 w:1)
+	// Storage: LiquidityPool NextLpAssetId (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn create_pool() -> Weight {
+		Weight::from_ref_time(48_000_000)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(4))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool TotalLiquidity (r:1 w:1)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: Assets Asset (r:3 w:3)
+	// Storage: Assets Account (r:4 w:3)
+	// Storage: System Account (r:2 w:0)
+	fn add_liquidity() -> Weight {
+		Weight::from_ref_time(112_000_000)
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool TotalLiquidity (r:1 w:1)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: Assets Asset (r:3 w:3)
+	// Storage: Assets Account (r:4 w:3)
+	// Storage: System Account (r:2 w:0)
+	fn remove_liquidity() -> Weight {
+		Weight::from_ref_time(108_000_000)
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool FlashLocks (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:1)
+	fn swap() -> Weight {
+		Weight::from_ref_time(86_000_000)
+			.saturating_add(T::DbWeight::get().reads(16))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: LiquidityPool FlashLocks (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:1)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_exact_tokens_for_tokens(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_000_000)
+			.saturating_add(Weight::from_ref_time(78_000_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().reads((15_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+	// Storage: LiquidityPool FlashLocks (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:1)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_tokens_for_exact_tokens(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_000_000)
+			.saturating_add(Weight::from_ref_time(80_000_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().reads((15_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ProtocolFees (r:2 w:2)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:2)
+	fn claim_protocol_fees() -> Weight {
+		Weight::from_ref_time(74_000_000)
+			.saturating_add(T::DbWeight::get().reads(9))
+			.saturating_add(T::DbWeight::get().writes(6))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:1)
+	// Storage: LiquidityPool NextLpAssetId (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: LiquidityPool ConcentratedPools (r:0 w:1)
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_ref_time(52_000_000)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:1)
+	// Storage: LiquidityPool NextPositionId (r:1 w:1)
+	// Storage: LiquidityPool Ticks (r:4 w:2)
+	// Storage: LiquidityPool InitializedTicks (r:1 w:1)
+	// Storage: LiquidityPool TickDeposits (r:0 w:2)
+	// Storage: LiquidityPool Positions (r:0 w:1)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	// Storage: System Account (r:1 w:1)
+	fn open_position() -> Weight {
+		Weight::from_ref_time(181_000_000)
+			.saturating_add(T::DbWeight::get().reads(18))
+			.saturating_add(T::DbWeight::get().writes(18))
+	}
+	// Storage: LiquidityPool Positions (r:1 w:1)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:1)
+	// Storage: LiquidityPool Ticks (r:4 w:2)
+	// Storage: LiquidityPool InitializedTicks (r:1 w:1)
+	// Storage: LiquidityPool TickDeposits (r:0 w:2)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	// Storage: System Account (r:1 w:1)
+	fn increase_liquidity() -> Weight {
+		Weight::from_ref_time(154_000_000)
+			.saturating_add(T::DbWeight::get().reads(17))
+			.saturating_add(T::DbWeight::get().writes(17))
+	}
+	// Storage: LiquidityPool Positions (r:1 w:1)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:1)
+	// Storage: LiquidityPool Ticks (r:4 w:2)
+	// Storage: LiquidityPool InitializedTicks (r:1 w:1)
+	// Storage: LiquidityPool TickDeposits (r:2 w:2)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	// Storage: System Account (r:2 w:2)
+	fn decrease_liquidity() -> Weight {
+		Weight::from_ref_time(163_000_000)
+			.saturating_add(T::DbWeight::get().reads(20))
+			.saturating_add(T::DbWeight::get().writes(18))
+	}
+	// Storage: LiquidityPool Positions (r:1 w:1)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:1)
+	// Storage: LiquidityPool Ticks (r:2 w:0)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	fn collect_fees() -> Weight {
+		Weight::from_ref_time(97_000_000)
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: LiquidityPool NextStablePoolId (r:1 w:1)
+	// Storage: LiquidityPool NextLpAssetId (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: LiquidityPool StablePools (r:0 w:1)
+	fn create_stable_pool() -> Weight {
+		Weight::from_ref_time(50_000_000)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	// Storage: LiquidityPool PausedStablePools (r:1 w:0)
+	// Storage: LiquidityPool StableProtocolFees (r:4 w:4)
+	// Storage: Assets Asset (r:5 w:5)
+	// Storage: Assets Account (r:4 w:3)
+	fn add_stable_liquidity() -> Weight {
+		Weight::from_ref_time(238_000_000)
+			.saturating_add(T::DbWeight::get().reads(15))
+			.saturating_add(T::DbWeight::get().writes(13))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	// Storage: Assets Asset (r:5 w:5)
+	// Storage: Assets Account (r:8 w:4)
+	fn remove_stable_liquidity() -> Weight {
+		Weight::from_ref_time(158_000_000)
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	// Storage: LiquidityPool PausedStablePools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool StableProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:2)
+	fn stable_swap() -> Weight {
+		Weight::from_ref_time(124_000_000)
+			.saturating_add(T::DbWeight::get().reads(12))
+			.saturating_add(T::DbWeight::get().writes(7))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	fn ramp_amplification() -> Weight {
+		Weight::from_ref_time(21_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	fn stop_amplification_ramp() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:0)
+	// Storage: LiquidityPool StableProtocolFees (r:4 w:4)
+	// Storage: Assets Asset (r:4 w:4)
+	// Storage: Assets Account (r:4 w:4)
+	fn claim_stable_protocol_fees() -> Weight {
+		Weight::from_ref_time(112_000_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(12))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool TotalLiquidity (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:2)
+	fn add_liquidity_single_asset() -> Weight {
+		Weight::from_ref_time(134_000_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool TotalLiquidity (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:3 w:2)
+	fn remove_liquidity_single_asset() -> Weight {
+		Weight::from_ref_time(131_000_000)
+			.saturating_add(T::DbWeight::get().reads(13))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:0 w:1)
+	fn pause_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:0 w:1)
+	fn unpause_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:1)
+	fn set_pool_fee() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:0)
+	// Storage: LiquidityPool PausedStablePools (r:0 w:1)
+	fn pause_stable_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:0)
+	// Storage: LiquidityPool PausedStablePools (r:0 w:1)
+	fn unpause_stable_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool StablePools (r:1 w:1)
+	fn set_stable_pool_fee() -> Weight {
+		Weight::from_ref_time(20_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool DeniedAssets (r:0 w:1)
+	fn allow_asset() -> Weight {
+		Weight::from_ref_time(13_000_000)
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool DeniedAssets (r:0 w:1)
+	fn deny_asset() -> Weight {
+		Weight::from_ref_time(13_000_000)
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool Farms (r:1 w:1)
+	// Storage: LiquidityPool TotalStaked (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn set_reward_schedule() -> Weight {
+		Weight::from_ref_time(34_000_000)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool Farms (r:1 w:1)
+	// Storage: LiquidityPool TotalStaked (r:1 w:1)
+	// Storage: LiquidityPool Stakes (r:1 w:1)
+	// Storage: LiquidityPool StakerRewards (r:3 w:3)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn stake() -> Weight {
+		Weight::from_ref_time(95_000_000)
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(10))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool Farms (r:1 w:1)
+	// Storage: LiquidityPool TotalStaked (r:1 w:1)
+	// Storage: LiquidityPool Stakes (r:1 w:1)
+	// Storage: LiquidityPool StakerRewards (r:3 w:3)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn unstake() -> Weight {
+		Weight::from_ref_time(90_000_000)
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(9))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool Farms (r:1 w:1)
+	// Storage: LiquidityPool TotalStaked (r:1 w:0)
+	// Storage: LiquidityPool Stakes (r:1 w:0)
+	// Storage: LiquidityPool StakerRewards (r:3 w:3)
+	// Storage: Assets Asset (r:3 w:3)
+	// Storage: Assets Account (r:6 w:6)
+	fn claim_rewards() -> Weight {
+		Weight::from_ref_time(128_000_000)
+			.saturating_add(T::DbWeight::get().reads(16))
+			.saturating_add(T::DbWeight::get().writes(13))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool FlashLocks (r:1 w:1)
+	// Storage: LiquidityPool PausedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:2 w:2)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	fn flash_swap() -> Weight {
+		Weight::from_ref_time(141_000_000)
+			.saturating_add(T::DbWeight::get().reads(18))
+			.saturating_add(T::DbWeight::get().writes(13))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool NextOrderId (r:1 w:1)
+	// Storage: LiquidityPool PoolOrders (r:1 w:1)
+	// Storage: LiquidityPool Orders (r:0 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn place_order() -> Weight {
+		Weight::from_ref_time(67_000_000)
+			.saturating_add(T::DbWeight::get().reads(11))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: LiquidityPool Orders (r:1 w:1)
+	// Storage: LiquidityPool PoolOrders (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn cancel_order() -> Weight {
+		Weight::from_ref_time(52_000_000)
+			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().writes(6))
+	}
+	// Storage: LiquidityPool Orders (r:1 w:1)
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool FlashLocks (r:1 w:0)
+	// Storage: LiquidityPool PausedPools (r:1 w:0)
+	// Storage: LiquidityPool DeniedAssets (r:2 w:0)
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:0)
+	// Storage: LiquidityPool LiquidityPools (r:1 w:1)
+	// Storage: LiquidityPool ProtocolFees (r:1 w:1)
+	// Storage: LiquidityPool PriceCumulatives (r:1 w:1)
+	// Storage: LiquidityPool PriceObservations (r:1 w:1)
+	// Storage: LiquidityPool PoolStats (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Asset (r:2 w:2)
+	// Storage: Assets Account (r:4 w:4)
+	fn fill_order() -> Weight {
+		Weight::from_ref_time(118_000_000)
+			.saturating_add(T::DbWeight::get().reads(19))
+			.saturating_add(T::DbWeight::get().writes(12))
+	}
+	// Storage: LiquidityPool Pools (r:1 w:0)
+	// Storage: LiquidityPool HeldShares (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn release_held_shares() -> Weight {
+		Weight::from_ref_time(46_000_000)
+			.saturating_add(T::DbWeight::get().reads(6))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	// Storage: LiquidityPool ConcentratedPools (r:1 w:1)
+	// Storage: LiquidityPool InitializedTicks (r:1 w:0)
+	// Storage: LiquidityPool Ticks (r:62 w:62)
+	/// The range of component `t` is `[0, 62]`.
+	fn concentrated_swap_hop(t: u32, ) -> Weight {
+		Weight::from_ref_time(24_000_000)
+			.saturating_add(Weight::from_ref_time(9_000_000).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(t.into())))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_pool() -> Weight {
+		Weight::from_ref_time(48_000_000)
+			.saturating_add(RocksDbWeight::get().reads(4))
+			.saturating_add(RocksDbWeight::get().writes(4))
+	}
+	fn add_liquidity() -> Weight {
+		Weight::from_ref_time(112_000_000)
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn remove_liquidity() -> Weight {
+		Weight::from_ref_time(108_000_000)
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn swap() -> Weight {
+		Weight::from_ref_time(86_000_000)
+			.saturating_add(RocksDbWeight::get().reads(16))
+			.saturating_add(RocksDbWeight::get().writes(8))
+	}
+	fn swap_exact_tokens_for_tokens(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_000_000)
+			.saturating_add(Weight::from_ref_time(78_000_000).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().reads((15_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1))
+			.saturating_add(RocksDbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+	fn swap_tokens_for_exact_tokens(n: u32, ) -> Weight {
+		Weight::from_ref_time(12_000_000)
+			.saturating_add(Weight::from_ref_time(80_000_000).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().reads((15_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1))
+			.saturating_add(RocksDbWeight::get().writes((7_u64).saturating_mul(n.into())))
+	}
+	fn claim_protocol_fees() -> Weight {
+		Weight::from_ref_time(74_000_000)
+			.saturating_add(RocksDbWeight::get().reads(9))
+			.saturating_add(RocksDbWeight::get().writes(6))
+	}
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_ref_time(52_000_000)
+			.saturating_add(RocksDbWeight::get().reads(4))
+			.saturating_add(RocksDbWeight::get().writes(5))
+	}
+	fn open_position() -> Weight {
+		Weight::from_ref_time(181_000_000)
+			.saturating_add(RocksDbWeight::get().reads(18))
+			.saturating_add(RocksDbWeight::get().writes(18))
+	}
+	fn increase_liquidity() -> Weight {
+		Weight::from_ref_time(154_000_000)
+			.saturating_add(RocksDbWeight::get().reads(17))
+			.saturating_add(RocksDbWeight::get().writes(17))
+	}
+	fn decrease_liquidity() -> Weight {
+		Weight::from_ref_time(163_000_000)
+			.saturating_add(RocksDbWeight::get().reads(20))
+			.saturating_add(RocksDbWeight::get().writes(18))
+	}
+	fn collect_fees() -> Weight {
+		Weight::from_ref_time(97_000_000)
+			.saturating_add(RocksDbWeight::get().reads(10))
+			.saturating_add(RocksDbWeight::get().writes(8))
+	}
+	fn create_stable_pool() -> Weight {
+		Weight::from_ref_time(50_000_000)
+			.saturating_add(RocksDbWeight::get().reads(4))
+			.saturating_add(RocksDbWeight::get().writes(5))
+	}
+	fn add_stable_liquidity() -> Weight {
+		Weight::from_ref_time(238_000_000)
+			.saturating_add(RocksDbWeight::get().reads(15))
+			.saturating_add(RocksDbWeight::get().writes(13))
+	}
+	fn remove_stable_liquidity() -> Weight {
+		Weight::from_ref_time(158_000_000)
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn stable_swap() -> Weight {
+		Weight::from_ref_time(124_000_000)
+			.saturating_add(RocksDbWeight::get().reads(12))
+			.saturating_add(RocksDbWeight::get().writes(7))
+	}
+	fn ramp_amplification() -> Weight {
+		Weight::from_ref_time(21_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn stop_amplification_ramp() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn claim_stable_protocol_fees() -> Weight {
+		Weight::from_ref_time(112_000_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(12))
+	}
+	fn add_liquidity_single_asset() -> Weight {
+		Weight::from_ref_time(134_000_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn remove_liquidity_single_asset() -> Weight {
+		Weight::from_ref_time(131_000_000)
+			.saturating_add(RocksDbWeight::get().reads(13))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn pause_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn unpause_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn set_pool_fee() -> Weight {
+		Weight::from_ref_time(19_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn pause_stable_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn unpause_stable_pool() -> Weight {
+		Weight::from_ref_time(18_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn set_stable_pool_fee() -> Weight {
+		Weight::from_ref_time(20_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn allow_asset() -> Weight {
+		Weight::from_ref_time(13_000_000)
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn deny_asset() -> Weight {
+		Weight::from_ref_time(13_000_000)
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn set_reward_schedule() -> Weight {
+		Weight::from_ref_time(34_000_000)
+			.saturating_add(RocksDbWeight::get().reads(4))
+			.saturating_add(RocksDbWeight::get().writes(2))
+	}
+	fn stake() -> Weight {
+		Weight::from_ref_time(95_000_000)
+			.saturating_add(RocksDbWeight::get().reads(11))
+			.saturating_add(RocksDbWeight::get().writes(10))
+	}
+	fn unstake() -> Weight {
+		Weight::from_ref_time(90_000_000)
+			.saturating_add(RocksDbWeight::get().reads(10))
+			.saturating_add(RocksDbWeight::get().writes(9))
+	}
+	fn claim_rewards() -> Weight {
+		Weight::from_ref_time(128_000_000)
+			.saturating_add(RocksDbWeight::get().reads(16))
+			.saturating_add(RocksDbWeight::get().writes(13))
+	}
+	fn flash_swap() -> Weight {
+		Weight::from_ref_time(141_000_000)
+			.saturating_add(RocksDbWeight::get().reads(18))
+			.saturating_add(RocksDbWeight::get().writes(13))
+	}
+	fn place_order() -> Weight {
+		Weight::from_ref_time(67_000_000)
+			.saturating_add(RocksDbWeight::get().reads(11))
+			.saturating_add(RocksDbWeight::get().writes(8))
+	}
+	fn cancel_order() -> Weight {
+		Weight::from_ref_time(52_000_000)
+			.saturating_add(RocksDbWeight::get().reads(6))
+			.saturating_add(RocksDbWeight::get().writes(6))
+	}
+	fn fill_order() -> Weight {
+		Weight::from_ref_time(118_000_000)
+			.saturating_add(RocksDbWeight::get().reads(19))
+			.saturating_add(RocksDbWeight::get().writes(12))
+	}
+	fn release_held_shares() -> Weight {
+		Weight::from_ref_time(46_000_000)
+			.saturating_add(RocksDbWeight::get().reads(6))
+			.saturating_add(RocksDbWeight::get().writes(5))
+	}
+	fn concentrated_swap_hop(t: u32, ) -> Weight {
+		Weight::from_ref_time(24_000_000)
+			.saturating_add(Weight::from_ref_time(9_000_000).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads(2))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(RocksDbWeight::get().writes(1))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(t.into())))
+	}
+}
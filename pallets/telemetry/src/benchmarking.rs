@@ -0,0 +1,179 @@
+//! Benchmarks for `pallet-telemetry`.
+
+use super::*;
+use frame_benchmarking::{account, benchmarks_instance_pallet, whitelisted_caller, BenchmarkError};
+use frame_support::{
+    assert_ok,
+    traits::{EnsureOrigin, Get, UnixTime},
+    BoundedVec,
+};
+use frame_system::RawOrigin;
+use sp_std::{prelude::*, vec};
+
+const SEED: u32 = 0;
+
+/// A device id of the maximum length
+fn device_id<T: Config<I>, I: 'static>() -> DeviceIdOf<T, I> {
+    BoundedVec::truncate_from(vec![7u8; T::MaxDeviceIdLength::get() as usize])
+}
+
+/// The Dilithium key of the runtime's test vector. Dilithium is the costliest scheme to
+/// verify, so every device is benchmarked with it.
+fn device_key<T: Config<I>, I: 'static>() -> DeviceKey {
+    DeviceKey::Dilithium(T::BenchmarkHelper::dilithium_public_key())
+}
+
+/// The test vector's signature over `payload`
+fn sign<T: Config<I>, I: 'static>(payload: &[u8]) -> Result<SignatureOf<T, I>, BenchmarkError> {
+    let signature = T::BenchmarkHelper::dilithium_signature(payload)
+        .ok_or(BenchmarkError::Stop("the Dilithium test vector has no signature over the payload"))?;
+    SignatureOf::<T, I>::try_from(signature)
+        .map_err(|_| BenchmarkError::Stop("MaxSignatureLength is below DILITHIUM_SIGNATURE_BYTES"))
+}
+
+/// Register a device with the test vector's key for `owner`
+fn register<T: Config<I>, I: 'static>(
+    owner: &T::AccountId,
+    device_id: &DeviceIdOf<T, I>,
+) -> Result<(), BenchmarkError> {
+    let proof = sign::<T, I>(&Pallet::<T, I>::key_proof_payload(device_id, owner))?;
+    assert_ok!(Pallet::<T, I>::authorize_device(
+        RawOrigin::Signed(owner.clone()).into(),
+        device_id.clone(),
+        device_key::<T, I>(),
+        proof
+    ));
+    Ok(())
+}
+
+/// A stamp for the first reading of a device, taken now
+fn stamp<T: Config<I>, I: 'static>() -> ReadingStamp {
+    ReadingStamp { sequence: 1, timestamp: T::UnixTime::now().as_secs() }
+}
+
+/// A stored reading of `device_id`, with a signature of the maximum length
+fn record<T: Config<I>, I: 'static>(owner: &T::AccountId, device_id: &DeviceIdOf<T, I>) -> TelemetryRecordOf<T, I> {
+    TelemetryRecord {
+        device_id: device_id.clone(),
+        timestamp: frame_system::Pallet::<T>::block_number(),
+        stamp: ReadingStamp::default(),
+        reading: ReadingOf::<T, I>::default(),
+        reporter: owner.clone(),
+        signature: BoundedVec::truncate_from(vec![0u8; T::MaxSignatureLength::get() as usize]),
+    }
+}
+
+/// Fill the readings buffer of `device_id`, so recording another reading prunes its oldest.
+/// Returns the id of the newest reading.
+fn fill_readings<T: Config<I>, I: 'static>(owner: &T::AccountId, device_id: &DeviceIdOf<T, I>) -> T::TelemetryId {
+    let max = T::MaxReadingsPerDevice::get();
+    let ids: Vec<T::TelemetryId> = (0..max).map(Into::into).collect();
+    for id in &ids {
+        Telemetry::<T, I>::insert(id, record::<T, I>(owner, device_id));
+    }
+    DeviceReadings::<T, I>::insert(device_id, BoundedVec::truncate_from(ids));
+    NextTelemetryId::<T, I>::put(T::TelemetryId::from(max));
+    T::TelemetryId::from(max.saturating_sub(1))
+}
+
+/// A range for every metric of the schema that `reading` falls outside of, so every check
+/// reports an anomaly
+fn missed_ranges<T: Config<I>, I: 'static>(reading: &ReadingOf<T, I>) -> Vec<(MetricOf<T, I>, u32, u32)> {
+    T::Schema::metrics(reading)
+        .into_iter()
+        .map(|(metric, value)| (metric, value.saturating_add(1), value.saturating_add(2)))
+        .collect()
+}
+
+benchmarks_instance_pallet! {
+    submit_telemetry {
+        let caller: T::AccountId = whitelisted_caller();
+        let device_id = device_id::<T, I>();
+        register::<T, I>(&caller, &device_id)?;
+        fill_readings::<T, I>(&caller, &device_id);
+        let reading = ReadingOf::<T, I>::default();
+        let origin = T::RangeOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        assert_ok!(Pallet::<T, I>::update_optimal_ranges(origin, missed_ranges::<T, I>(&reading)));
+        let stamp = stamp::<T, I>();
+        let signature = sign::<T, I>(&Pallet::<T, I>::signing_payload(&device_id, &reading, &stamp, 0))?;
+    }: _(RawOrigin::Signed(caller), device_id.clone(), reading, stamp, 0, signature)
+    verify {
+        assert!(DeviceLatestTelemetry::<T, I>::contains_key(&device_id));
+        assert!(!Telemetry::<T, I>::contains_key(T::TelemetryId::from(0u32)));
+        assert_eq!(DeviceNonces::<T, I>::get(&device_id), 1);
+    }
+
+    authorize_device {
+        let caller: T::AccountId = whitelisted_caller();
+        let device_id = device_id::<T, I>();
+        // Rotating the key of a registered device reads and writes the same entry as a
+        // first registration
+        let proof = sign::<T, I>(&Pallet::<T, I>::key_proof_payload(&device_id, &caller))?;
+    }: _(RawOrigin::Signed(caller.clone()), device_id.clone(), device_key::<T, I>(), proof)
+    verify {
+        assert_eq!(AuthorizedDevices::<T, I>::get(&device_id).map(|device| device.owner), Some(caller));
+    }
+
+    update_optimal_ranges {
+        let n in 1 .. T::Schema::METRICS;
+        let origin = T::RangeOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        let mut ranges = missed_ranges::<T, I>(&ReadingOf::<T, I>::default());
+        ranges.truncate(n as usize);
+    }: _<T::RuntimeOrigin>(origin, ranges)
+    verify {
+        assert_eq!(OptimalRanges::<T, I>::iter().count() as u32, n);
+    }
+
+    revoke_device {
+        let caller: T::AccountId = whitelisted_caller();
+        let device_id = device_id::<T, I>();
+        register::<T, I>(&caller, &device_id)?;
+    }: _(RawOrigin::Signed(caller), device_id.clone())
+    verify {
+        assert!(!AuthorizedDevices::<T, I>::contains_key(&device_id));
+    }
+
+    transfer_device {
+        let caller: T::AccountId = whitelisted_caller();
+        let new_owner: T::AccountId = account("new_owner", 0, SEED);
+        let device_id = device_id::<T, I>();
+        register::<T, I>(&caller, &device_id)?;
+    }: _(RawOrigin::Signed(caller), device_id.clone(), new_owner.clone())
+    verify {
+        assert_eq!(AuthorizedDevices::<T, I>::get(&device_id).map(|device| device.owner), Some(new_owner));
+    }
+
+    submit_telemetry_unsigned {
+        let device_id = device_id::<T, I>();
+        let owner: T::AccountId = whitelisted_caller();
+        register::<T, I>(&owner, &device_id)?;
+        fill_readings::<T, I>(&owner, &device_id);
+        let reading = ReadingOf::<T, I>::default();
+        let origin = T::RangeOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+        assert_ok!(Pallet::<T, I>::update_optimal_ranges(origin, missed_ranges::<T, I>(&reading)));
+        let stamp = stamp::<T, I>();
+        let signature = sign::<T, I>(&Pallet::<T, I>::signing_payload(&device_id, &reading, &stamp, 0))?;
+    }: _(RawOrigin::None, device_id.clone(), reading, stamp, 0, signature)
+    verify {
+        assert!(LastSubmission::<T, I>::contains_key(&device_id));
+        assert!(!Telemetry::<T, I>::contains_key(T::TelemetryId::from(0u32)));
+    }
+
+    prune_telemetry {
+        let owner: T::AccountId = whitelisted_caller();
+        let device_id = device_id::<T, I>();
+        // The newest reading of a full buffer is the last one found in it, and is the
+        // device's latest
+        let telemetry_id = fill_readings::<T, I>(&owner, &device_id);
+        DeviceLatestTelemetry::<T, I>::insert(&device_id, telemetry_id);
+        let record = Telemetry::<T, I>::get(telemetry_id).ok_or(BenchmarkError::Weightless)?;
+    }: {
+        Pallet::<T, I>::prune_record(telemetry_id, &record);
+    }
+    verify {
+        assert!(!Telemetry::<T, I>::contains_key(telemetry_id));
+        assert!(!DeviceLatestTelemetry::<T, I>::contains_key(&device_id));
+    }
+
+    impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
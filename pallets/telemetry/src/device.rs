@@ -0,0 +1,93 @@
+//! Device keys for `pallet-telemetry`.
+//!
+//! Every device is registered with the public key its firmware signs readings with.
+//! Dilithium is the default for new hardware; sr25519 and ed25519 keys are accepted for
+//! devices whose microcontroller cannot fit a post-quantum signer. A key is only registered
+//! with its own signature over the device id and owner, and every signature a device makes
+//! is bound to the chain, the pallet instance and the device.
+//!
+//! `pqc_dilithium` does not build for the runtime, so Dilithium signatures are verified
+//! natively through the [`dilithium`] runtime interface. Nodes must register
+//! `dilithium::HostFunctions` with their executor.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::{ed25519, sr25519};
+use sp_runtime::{traits::Verify, RuntimeDebug};
+use sp_runtime_interface::runtime_interface;
+
+/// Size of a Dilithium public key in bytes
+pub const DILITHIUM_PUBLIC_KEY_BYTES: usize = 1952;
+
+/// Size of a Dilithium signature in bytes. Instances that register Dilithium devices need a
+/// `MaxSignatureLength` of at least this.
+pub const DILITHIUM_SIGNATURE_BYTES: usize = 3293;
+
+// The sizes above are those of Dilithium3, the parameter set `pqc_dilithium` is built with
+#[cfg(feature = "std")]
+const _: () = assert!(
+    DILITHIUM_PUBLIC_KEY_BYTES == pqc_dilithium::PUBLICKEYBYTES && DILITHIUM_SIGNATURE_BYTES == pqc_dilithium::SIGNBYTES
+);
+
+/// Dilithium signature verification, run by the node
+#[runtime_interface]
+pub trait Dilithium {
+    /// Whether `signature` is the Dilithium signature of `public` over `message`
+    fn verify(signature: &[u8], message: &[u8], public: &[u8]) -> bool {
+        pqc_dilithium::verify(signature, message, public).is_ok()
+    }
+}
+
+/// Context a device's reading signatures start with
+pub const READING_CONTEXT: [u8; 17] = *b"telemetry/reading";
+
+/// Context a device key's proof of possession starts with, so that no reading signature
+/// can pass for one
+pub const KEY_PROOF_CONTEXT: [u8; 19] = *b"telemetry/key-proof";
+
+/// Public key a device signs its readings with
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum DeviceKey {
+    /// A Dilithium post-quantum key
+    Dilithium([u8; DILITHIUM_PUBLIC_KEY_BYTES]),
+    /// An sr25519 key
+    Sr25519(sr25519::Public),
+    /// An ed25519 key
+    Ed25519(ed25519::Public),
+}
+
+impl DeviceKey {
+    /// Whether `signature` is this key's signature over `message`
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            DeviceKey::Dilithium(public) => dilithium::verify(signature, message, public),
+            DeviceKey::Sr25519(public) => {
+                sr25519::Signature::from_slice(signature).map_or(false, |signature| signature.verify(message, public))
+            },
+            DeviceKey::Ed25519(public) => {
+                ed25519::Signature::from_slice(signature).map_or(false, |signature| signature.verify(message, public))
+            },
+        }
+    }
+}
+
+/// A registered device: the account it reports through, its signing key and when it was
+/// registered
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct DeviceInfo<AccountId, BlockNumber> {
+    /// The account that registered the device and may revoke or transfer it
+    pub owner: AccountId,
+    /// The key the device signs its readings with
+    pub key: DeviceKey,
+    /// The block the device was registered in. Rotating its key or transferring it keeps it.
+    pub registered_at: BlockNumber,
+}
+
+/// Device-side sequence number and clock of a reading, signed along with it
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+pub struct ReadingStamp {
+    /// Counter the device increases with every reading it takes
+    pub sequence: u64,
+    /// The device clock when the reading was taken, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
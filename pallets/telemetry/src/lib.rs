@@ -0,0 +1,775 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Sensor telemetry for Nourish Chain.
+//!
+//! Devices are registered with the public key their firmware signs readings with, proven
+//! by a signature of that key. Their signed readings are stored on chain and checked against
+//! the optimal cultivation ranges.
+//! Devices that cannot pay fees hand their signed readings to any relayer, which submits them
+//! unsigned; the pool validates them by the device's signature, nonce and rate limit.
+//! What a reading contains is fixed by the instance's [`SensorSchema`], so NRSH spirulina
+//! and ELXR kombucha telemetry are two instances of this pallet:
+//!
+//! ```ignore
+//! impl pallet_telemetry::Config<pallet_telemetry::Instance1> for Runtime {
+//!     type Schema = pallet_telemetry::Spirulina<ConstU32<32>>;
+//!     ..
+//! }
+//! impl pallet_telemetry::Config<pallet_telemetry::Instance2> for Runtime {
+//!     type Schema = pallet_telemetry::Kombucha;
+//!     ..
+//! }
+//! ```
+//!
+//! Storage stays bounded: each device keeps its `MaxReadingsPerDevice` most recent readings,
+//! and `on_idle` prunes readings older than `RetentionPeriod` blocks. Every pruned reading
+//! is handed to the instance's [`OnTelemetryPruned`] hook first, so it can be exported for
+//! off-chain archival.
+//!
+//! Dilithium signatures are verified by the node through a host function, so nodes must
+//! register `device::dilithium::HostFunctions` with their executor.
+
+pub use pallet::*;
+
+pub mod device;
+pub mod schema;
+pub mod weights;
+pub use device::{DeviceInfo, DeviceKey, ReadingStamp};
+pub use schema::{Kombucha, KombuchaMetric, KombuchaReading, SensorSchema, Spirulina, SpirulinaMetric, SpirulinaReading};
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use frame_support::{
+        pallet_prelude::*,
+        sp_runtime::{
+            traits::{AtLeast32BitUnsigned, CheckedAdd, Member, One, Saturating, Zero},
+            SaturatedConversion,
+        },
+        traits::{PalletInfoAccess, UnixTime},
+        Parameter,
+    };
+    use frame_system::pallet_prelude::*;
+    use sp_std::prelude::*;
+    use crate::{
+        device::{DeviceInfo, DeviceKey, ReadingStamp, KEY_PROOF_CONTEXT, READING_CONTEXT},
+        schema::SensorSchema,
+        weights::WeightInfo,
+    };
+
+    /// Configure the pallet by specifying the parameters and types on which it depends.
+    #[pallet::config]
+    pub trait Config<I: 'static = ()>: frame_system::Config {
+        /// Because this pallet emits events, it depends on the runtime's definition of an event.
+        type RuntimeEvent: From<Event<Self, I>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// What the readings recorded by this instance contain
+        type Schema: SensorSchema;
+
+        /// Telemetry record ID type
+        type TelemetryId: Parameter + Member + AtLeast32BitUnsigned + Default + Copy + MaxEncodedLen;
+
+        /// The maximum length of a device ID in bytes
+        #[pallet::constant]
+        type MaxDeviceIdLength: Get<u32>;
+
+        /// The maximum length of a reading signature in bytes. Must be at least
+        /// `DILITHIUM_SIGNATURE_BYTES` for Dilithium devices to report.
+        #[pallet::constant]
+        type MaxSignatureLength: Get<u32>;
+
+        /// Origin allowed to set the optimal ranges readings are checked against
+        type RangeOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Base priority of unsigned readings in the transaction pool. A reading gains one
+        /// more for every block its device has been silent beyond `UnsignedInterval`.
+        #[pallet::constant]
+        type UnsignedPriority: Get<TransactionPriority>;
+
+        /// The minimum number of blocks between two unsigned readings of a device
+        #[pallet::constant]
+        type UnsignedInterval: Get<Self::BlockNumber>;
+
+        /// Block time, which device clocks are checked against
+        type UnixTime: UnixTime;
+
+        /// How far, in seconds, a reading's device timestamp may be from the block time
+        #[pallet::constant]
+        type MaxClockDrift: Get<u64>;
+
+        /// The number of most recent readings kept per device. Recording another one prunes
+        /// the device's oldest.
+        #[pallet::constant]
+        type MaxReadingsPerDevice: Get<u32>;
+
+        /// The number of blocks a reading is kept for before `on_idle` prunes it
+        #[pallet::constant]
+        type RetentionPeriod: Get<Self::BlockNumber>;
+
+        /// Called with every reading before it is pruned, e.g. to export it for off-chain
+        /// archival through the offchain index. Its `max_weight` is charged on top of the
+        /// pruning weights, and submissions that prune nothing are refunded it.
+        type OnPruned: OnTelemetryPruned<Self::TelemetryId, TelemetryRecordOf<Self, I>>;
+
+        /// Weight information for extrinsics in this pallet
+        type WeightInfo: WeightInfo;
+
+        /// Provides the Dilithium test vector for benchmarks
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper;
+    }
+
+    /// Benchmark setup hooks that depend on the runtime
+    #[cfg(feature = "runtime-benchmarks")]
+    pub trait BenchmarkHelper {
+        /// The public key of a Dilithium test vector. Benchmarks cannot make Dilithium
+        /// signatures in the runtime, so they register their devices with this key.
+        fn dilithium_public_key() -> [u8; crate::device::DILITHIUM_PUBLIC_KEY_BYTES];
+
+        /// The test vector's signature over `payload`, if it has one. Benchmarks need the
+        /// key proof of the device `[7; MaxDeviceIdLength]` for the whitelisted caller, and
+        /// the signature of its default reading with stamp `(1, UnixTime::now())` and nonce 0.
+        fn dilithium_signature(payload: &[u8]) -> Option<Vec<u8>>;
+    }
+
+    /// Identifier a device reports under
+    pub type DeviceIdOf<T, I = ()> = BoundedVec<u8, <T as Config<I>>::MaxDeviceIdLength>;
+
+    /// Signature of a device over a reading, its stamp and its nonce
+    pub type SignatureOf<T, I = ()> = BoundedVec<u8, <T as Config<I>>::MaxSignatureLength>;
+
+    /// A registered device of the runtime's accounts
+    pub type DeviceInfoOf<T> =
+        DeviceInfo<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+    /// A reading of the instance's schema
+    pub type ReadingOf<T, I = ()> = <<T as Config<I>>::Schema as SensorSchema>::Reading;
+
+    /// A range-checked metric of the instance's schema
+    pub type MetricOf<T, I = ()> = <<T as Config<I>>::Schema as SensorSchema>::Metric;
+
+    /// A batch id of the instance's schema
+    pub type BatchIdOf<T, I = ()> = <<T as Config<I>>::Schema as SensorSchema>::BatchId;
+
+    /// A stored reading with the device and account that reported it
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub struct TelemetryRecord<AccountId, BlockNumber, DeviceId, Reading, Signature> {
+        /// The device that took the reading
+        pub device_id: DeviceId,
+        /// Block in which the reading was recorded
+        pub timestamp: BlockNumber,
+        /// The device's sequence number and clock for the reading
+        pub stamp: ReadingStamp,
+        /// The reading itself
+        pub reading: Reading,
+        /// The account that submitted the reading
+        pub reporter: AccountId,
+        /// The device's signature over the reading, its stamp and its nonce
+        pub signature: Signature,
+    }
+
+    pub type TelemetryRecordOf<T, I = ()> = TelemetryRecord<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::BlockNumber,
+        DeviceIdOf<T, I>,
+        ReadingOf<T, I>,
+        SignatureOf<T, I>,
+    >;
+
+    /// Hook for readings about to be removed from storage
+    pub trait OnTelemetryPruned<TelemetryId, Record> {
+        /// The most weight `on_pruned` uses for one reading
+        fn max_weight() -> Weight;
+
+        /// `record`, stored under `telemetry_id`, is about to be pruned. Returns the weight
+        /// used, at most `max_weight`.
+        fn on_pruned(telemetry_id: TelemetryId, record: &Record) -> Weight;
+    }
+
+    impl<TelemetryId, Record> OnTelemetryPruned<TelemetryId, Record> for () {
+        fn max_weight() -> Weight {
+            Weight::zero()
+        }
+
+        fn on_pruned(_telemetry_id: TelemetryId, _record: &Record) -> Weight {
+            Weight::zero()
+        }
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T, I = ()>(PhantomData<(T, I)>);
+
+    /// Recorded readings. Maps telemetry id -> TelemetryRecord
+    #[pallet::storage]
+    #[pallet::getter(fn telemetry)]
+    pub type Telemetry<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, T::TelemetryId, TelemetryRecordOf<T, I>, OptionQuery>;
+
+    /// Latest reading of each device. Maps device id -> telemetry id
+    #[pallet::storage]
+    #[pallet::getter(fn device_latest_telemetry)]
+    pub type DeviceLatestTelemetry<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, DeviceIdOf<T, I>, T::TelemetryId, OptionQuery>;
+
+    /// Ids of the most recent readings of each device, oldest first. Maps device id ->
+    /// telemetry ids
+    #[pallet::storage]
+    #[pallet::getter(fn device_readings)]
+    pub type DeviceReadings<T: Config<I>, I: 'static = ()> = StorageMap<
+        _,
+        Blake2_128Concat,
+        DeviceIdOf<T, I>,
+        BoundedVec<T::TelemetryId, T::MaxReadingsPerDevice>,
+        ValueQuery
+    >;
+
+    /// The id the next reading is recorded under
+    #[pallet::storage]
+    #[pallet::getter(fn next_telemetry_id)]
+    pub type NextTelemetryId<T: Config<I>, I: 'static = ()> = StorageValue<_, T::TelemetryId, ValueQuery>;
+
+    /// The lowest telemetry id `on_idle` has not checked for pruning yet. Readings are
+    /// recorded in id order, so every lower id has been pruned.
+    #[pallet::storage]
+    pub type PruneCursor<T: Config<I>, I: 'static = ()> = StorageValue<_, T::TelemetryId, ValueQuery>;
+
+    /// Registered devices. Maps device id -> DeviceInfo
+    #[pallet::storage]
+    #[pallet::getter(fn authorized_devices)]
+    pub type AuthorizedDevices<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, DeviceIdOf<T, I>, DeviceInfoOf<T>, OptionQuery>;
+
+    /// The nonce each device signs its next reading with. Kept when a device is revoked, so
+    /// its old signatures stay spent if it is registered again.
+    #[pallet::storage]
+    #[pallet::getter(fn device_nonce)]
+    pub type DeviceNonces<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, DeviceIdOf<T, I>, u64, ValueQuery>;
+
+    /// Block of each device's latest reading. Maps device id -> block number
+    #[pallet::storage]
+    #[pallet::getter(fn last_submission)]
+    pub type LastSubmission<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, DeviceIdOf<T, I>, T::BlockNumber, OptionQuery>;
+
+    /// Stamp of each device's latest accepted reading. Later readings must carry a higher
+    /// sequence number and no earlier timestamp.
+    #[pallet::storage]
+    #[pallet::getter(fn last_stamp)]
+    pub type LastStamp<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, DeviceIdOf<T, I>, ReadingStamp, OptionQuery>;
+
+    /// Optimal (min, max) of each metric. Metrics without a range are not checked.
+    #[pallet::storage]
+    #[pallet::getter(fn optimal_ranges)]
+    pub type OptimalRanges<T: Config<I>, I: 'static = ()> =
+        StorageMap<_, Blake2_128Concat, MetricOf<T, I>, (u32, u32), OptionQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config<I>, I: 'static = ()> {
+        /// New telemetry data recorded. [device_id, telemetry_id]
+        TelemetryRecorded(DeviceIdOf<T, I>, T::TelemetryId),
+
+        /// Device authorization updated. [device_id, account_id]
+        DeviceAuthorizationUpdated(DeviceIdOf<T, I>, T::AccountId),
+
+        /// Optimal ranges updated
+        OptimalRangesUpdated,
+
+        /// A reading shows the culture is ready to harvest or done fermenting, with the batch
+        /// it belongs to if the schema tracks batches. [device_id, batch_id]
+        CultureReady(DeviceIdOf<T, I>, Option<BatchIdOf<T, I>>),
+
+        /// A metric of a reading is outside its optimal range. [device_id, metric]
+        AnomalyDetected(DeviceIdOf<T, I>, MetricOf<T, I>),
+
+        /// A device was revoked by its owner. [device_id, owner]
+        DeviceRevoked(DeviceIdOf<T, I>, T::AccountId),
+
+        /// A device was handed to a new owner. [device_id, from, to]
+        DeviceTransferred(DeviceIdOf<T, I>, T::AccountId, T::AccountId),
+    }
+
+    #[pallet::error]
+    pub enum Error<T, I = ()> {
+        /// The device is not authorized for the submitting account
+        DeviceNotAuthorized,
+
+        /// The reading signature is invalid
+        InvalidSignature,
+
+        /// An optimal range does not have its minimum below its maximum
+        InvalidRange,
+
+        /// More ranges were given than the schema has metrics
+        TooManyRanges,
+
+        /// No telemetry ids are left
+        TelemetryIdOverflow,
+
+        /// The device id is registered to another account
+        DeviceAlreadyRegistered,
+
+        /// The device id is not registered
+        UnknownDevice,
+
+        /// Only the device's owner may revoke or transfer it
+        NotDeviceOwner,
+
+        /// The reading is not signed with the device's next nonce
+        InvalidNonce,
+
+        /// The device's previous unsigned reading was less than `UnsignedInterval` blocks ago
+        SubmittedTooSoon,
+
+        /// The reading carries the sequence number of the device's latest accepted reading
+        ReplayedReading,
+
+        /// The reading is older than the device's latest accepted reading: its sequence
+        /// number is lower or its timestamp is earlier
+        OutOfOrderReading,
+
+        /// The reading's timestamp is more than `MaxClockDrift` from the block time
+        ClockDrift,
+
+        /// The key's proof of possession is not its signature over the device id and owner
+        InvalidKeyProof,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config<I>, I: 'static> Hooks<BlockNumberFor<T>> for Pallet<T, I> {
+        /// Prune readings older than `RetentionPeriod` with the weight left over at the end
+        /// of the block
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::prune_expired(now, remaining_weight)
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config<I>, I: 'static> Pallet<T, I> {
+        /// Submit a reading of a device owned by the caller, signed by the device over its
+        /// [`Pallet::signing_payload`] with the device's next nonce
+        #[pallet::weight(T::WeightInfo::submit_telemetry().saturating_add(T::OnPruned::max_weight()))]
+        pub fn submit_telemetry(
+            origin: OriginFor<T>,
+            device_id: DeviceIdOf<T, I>,
+            reading: ReadingOf<T, I>,
+            stamp: ReadingStamp,
+            nonce: u64,
+            signature: SignatureOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            // Ensure the device reports through this account
+            let device = AuthorizedDevices::<T, I>::get(&device_id).ok_or(Error::<T, I>::DeviceNotAuthorized)?;
+            ensure!(device.owner == who, Error::<T, I>::DeviceNotAuthorized);
+
+            let pruned_weight = Self::record_reading(device_id, device, reading, stamp, nonce, signature)?;
+
+            Ok(Some(T::WeightInfo::submit_telemetry().saturating_add(pruned_weight)).into())
+        }
+
+        /// Submit a reading without a fee, relayed by anyone. The reading is authorized by
+        /// the device's signature alone, checked with the same nonce as `submit_telemetry`,
+        /// and recorded as reported by the device's owner. A device may report this way
+        /// once every `UnsignedInterval` blocks.
+        #[pallet::weight(T::WeightInfo::submit_telemetry_unsigned().saturating_add(T::OnPruned::max_weight()))]
+        pub fn submit_telemetry_unsigned(
+            origin: OriginFor<T>,
+            device_id: DeviceIdOf<T, I>,
+            reading: ReadingOf<T, I>,
+            stamp: ReadingStamp,
+            nonce: u64,
+            signature: SignatureOf<T, I>,
+        ) -> DispatchResultWithPostInfo {
+            ensure_none(origin)?;
+
+            let device = AuthorizedDevices::<T, I>::get(&device_id).ok_or(Error::<T, I>::DeviceNotAuthorized)?;
+            ensure!(!Self::unsigned_rate_limited(&device_id), Error::<T, I>::SubmittedTooSoon);
+
+            let pruned_weight = Self::record_reading(device_id, device, reading, stamp, nonce, signature)?;
+
+            Ok(Some(T::WeightInfo::submit_telemetry_unsigned().saturating_add(pruned_weight)).into())
+        }
+
+        /// Register a device with the key it signs readings with, to report through the
+        /// calling account. `proof` is the key's signature over [`Pallet::key_proof_payload`],
+        /// so only a holder of the key can register it. The owner of a registered device may
+        /// call this again to rotate its key.
+        #[pallet::weight(T::WeightInfo::authorize_device())]
+        pub fn authorize_device(
+            origin: OriginFor<T>,
+            device_id: DeviceIdOf<T, I>,
+            key: DeviceKey,
+            proof: SignatureOf<T, I>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Ensure no one else has registered the device
+            let existing = AuthorizedDevices::<T, I>::get(&device_id);
+            if let Some(device) = &existing {
+                ensure!(device.owner == who, Error::<T, I>::DeviceAlreadyRegistered);
+            }
+
+            // Ensure the caller holds the key, for this device and this account
+            ensure!(
+                key.verify(&Self::key_proof_payload(&device_id, &who), &proof),
+                Error::<T, I>::InvalidKeyProof
+            );
+
+            // Register the device authorization, keeping the registration block on a rotation
+            let registered_at = existing
+                .map_or_else(frame_system::Pallet::<T>::block_number, |device| device.registered_at);
+            AuthorizedDevices::<T, I>::insert(&device_id, DeviceInfo { owner: who.clone(), key, registered_at });
+
+            // Emit an event
+            Self::deposit_event(Event::DeviceAuthorizationUpdated(device_id, who));
+
+            Ok(())
+        }
+
+        /// Revoke a device owned by the caller. Its readings stay recorded until they are
+        /// pruned, but it can no longer submit until it is registered again.
+        #[pallet::weight(T::WeightInfo::revoke_device())]
+        pub fn revoke_device(origin: OriginFor<T>, device_id: DeviceIdOf<T, I>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::ensure_device_owner(&device_id, &who)?;
+            AuthorizedDevices::<T, I>::remove(&device_id);
+
+            Self::deposit_event(Event::DeviceRevoked(device_id, who));
+
+            Ok(())
+        }
+
+        /// Hand a device owned by the caller, with its key, to `new_owner`
+        #[pallet::weight(T::WeightInfo::transfer_device())]
+        pub fn transfer_device(
+            origin: OriginFor<T>,
+            device_id: DeviceIdOf<T, I>,
+            new_owner: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut device = Self::ensure_device_owner(&device_id, &who)?;
+            device.owner = new_owner.clone();
+            AuthorizedDevices::<T, I>::insert(&device_id, device);
+
+            Self::deposit_event(Event::DeviceTransferred(device_id, who, new_owner));
+
+            Ok(())
+        }
+
+        /// Set the optimal (min, max) range of each given metric
+        #[pallet::weight(T::WeightInfo::update_optimal_ranges(ranges.len() as u32))]
+        pub fn update_optimal_ranges(
+            origin: OriginFor<T>,
+            ranges: Vec<(MetricOf<T, I>, u32, u32)>,
+        ) -> DispatchResult {
+            T::RangeOrigin::ensure_origin(origin)?;
+
+            // Validate the ranges
+            ensure!(ranges.len() as u32 <= T::Schema::METRICS, Error::<T, I>::TooManyRanges);
+            ensure!(ranges.iter().all(|(_, min, max)| min < max), Error::<T, I>::InvalidRange);
+
+            // Update the optimal ranges
+            for (metric, min, max) in ranges {
+                OptimalRanges::<T, I>::insert(metric, (min, max));
+            }
+
+            // Emit an event
+            Self::deposit_event(Event::OptimalRangesUpdated);
+
+            Ok(())
+        }
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config<I>, I: 'static> ValidateUnsigned for Pallet<T, I> {
+        type Call = Call<T, I>;
+
+        fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            match call {
+                Call::submit_telemetry_unsigned { device_id, reading, stamp, nonce, signature } => {
+                    Self::validate_unsigned_reading(device_id, reading, stamp, *nonce, signature, false)
+                },
+                _ => InvalidTransaction::Call.into(),
+            }
+        }
+
+        fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+            match call {
+                Call::submit_telemetry_unsigned { device_id, reading, stamp, nonce, signature } => {
+                    Self::validate_unsigned_reading(device_id, reading, stamp, *nonce, signature, true).map(|_| ())
+                },
+                _ => Err(InvalidTransaction::Call.into()),
+            }
+        }
+    }
+
+    impl<T: Config<I>, I: 'static> Pallet<T, I> {
+        /// Check a device's signed reading and record it as reported by the device's owner.
+        /// Returns the weight `OnPruned` used if the device's oldest reading made room for it.
+        fn record_reading(
+            device_id: DeviceIdOf<T, I>,
+            device: DeviceInfoOf<T>,
+            reading: ReadingOf<T, I>,
+            stamp: ReadingStamp,
+            nonce: u64,
+            signature: SignatureOf<T, I>,
+        ) -> Result<Weight, DispatchError> {
+            // Ensure the device signed this reading with its next nonce
+            ensure!(nonce == DeviceNonces::<T, I>::get(&device_id), Error::<T, I>::InvalidNonce);
+            ensure!(
+                device.key.verify(&Self::signing_payload(&device_id, &reading, &stamp, nonce), &signature),
+                Error::<T, I>::InvalidSignature
+            );
+            Self::ensure_fresh(&device_id, &stamp)?;
+            DeviceNonces::<T, I>::insert(&device_id, nonce.saturating_add(1));
+            LastStamp::<T, I>::insert(&device_id, stamp);
+
+            // Get the next telemetry id
+            let telemetry_id = NextTelemetryId::<T, I>::get();
+            let next_id = telemetry_id.checked_add(&One::one()).ok_or(Error::<T, I>::TelemetryIdOverflow)?;
+
+            // Check the reading before it moves into the record
+            let anomalies = Self::anomalies(&reading);
+            let ready = T::Schema::is_ready(&reading).then(|| T::Schema::batch_id(&reading));
+
+            // Store the reading
+            let now = frame_system::Pallet::<T>::block_number();
+            let record = TelemetryRecord {
+                device_id: device_id.clone(),
+                timestamp: now,
+                stamp,
+                reading,
+                reporter: device.owner,
+                signature,
+            };
+            Telemetry::<T, I>::insert(telemetry_id, record);
+            let mut pruned_weight = Weight::zero();
+            DeviceReadings::<T, I>::mutate(&device_id, |readings| {
+                // Make room by pruning the device's oldest reading
+                if !readings.is_empty() && readings.len() as u32 >= T::MaxReadingsPerDevice::get() {
+                    let oldest = readings.remove(0);
+                    if let Some(record) = Telemetry::<T, I>::get(oldest) {
+                        pruned_weight = Self::remove_record(oldest, &record);
+                    }
+                }
+                let _ = readings.try_push(telemetry_id);
+            });
+            DeviceLatestTelemetry::<T, I>::insert(&device_id, telemetry_id);
+            LastSubmission::<T, I>::insert(&device_id, now);
+            NextTelemetryId::<T, I>::put(next_id);
+
+            // Report anomalies and readiness
+            for metric in anomalies {
+                Self::deposit_event(Event::AnomalyDetected(device_id.clone(), metric));
+            }
+            if let Some(batch_id) = ready {
+                Self::deposit_event(Event::CultureReady(device_id.clone(), batch_id));
+            }
+
+            // Emit an event
+            Self::deposit_event(Event::TelemetryRecorded(device_id, telemetry_id));
+
+            Ok(pruned_weight)
+        }
+
+        /// Prune readings older than `RetentionPeriod`, oldest first, as far as
+        /// `remaining_weight` allows. Resumes at `PruneCursor` next time.
+        pub(crate) fn prune_expired(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+            let mut used = T::DbWeight::get().reads_writes(2, 1);
+            if used.any_gt(remaining_weight) {
+                return Weight::zero();
+            }
+
+            let next_id = NextTelemetryId::<T, I>::get();
+            let mut cursor = PruneCursor::<T, I>::get();
+            while cursor < next_id {
+                // Budget for the hook's worst case, but only charge what it used
+                let needed = T::WeightInfo::prune_telemetry();
+                if used.saturating_add(needed).saturating_add(T::OnPruned::max_weight()).any_gt(remaining_weight) {
+                    break;
+                }
+                used = used.saturating_add(needed);
+
+                // Ids the device buffers already pruned are skipped
+                if let Some(record) = Telemetry::<T, I>::get(cursor) {
+                    // Later readings were recorded no earlier, so none of them has expired
+                    if now < record.timestamp.saturating_add(T::RetentionPeriod::get()) {
+                        break;
+                    }
+                    used = used.saturating_add(Self::prune_record(cursor, &record));
+                }
+                cursor = cursor.saturating_add(One::one());
+            }
+            PruneCursor::<T, I>::put(cursor);
+
+            used
+        }
+
+        /// Prune an expired reading and drop it from its device's readings. Returns the weight
+        /// `OnPruned` used.
+        pub(crate) fn prune_record(telemetry_id: T::TelemetryId, record: &TelemetryRecordOf<T, I>) -> Weight {
+            DeviceReadings::<T, I>::mutate_exists(&record.device_id, |readings| {
+                if let Some(ids) = readings {
+                    ids.retain(|id| *id != telemetry_id);
+                    if ids.is_empty() {
+                        *readings = None;
+                    }
+                }
+            });
+            if DeviceLatestTelemetry::<T, I>::get(&record.device_id) == Some(telemetry_id) {
+                DeviceLatestTelemetry::<T, I>::remove(&record.device_id);
+            }
+            Self::remove_record(telemetry_id, record)
+        }
+
+        /// Hand a reading to the `OnPruned` hook, then remove it. Returns the weight the hook
+        /// used, capped at its `max_weight`.
+        fn remove_record(telemetry_id: T::TelemetryId, record: &TelemetryRecordOf<T, I>) -> Weight {
+            let weight = T::OnPruned::on_pruned(telemetry_id, record).min(T::OnPruned::max_weight());
+            Telemetry::<T, I>::remove(telemetry_id);
+            weight
+        }
+
+        /// Ensure a reading follows the device's latest accepted one and was taken close to
+        /// the block time
+        fn ensure_fresh(device_id: &DeviceIdOf<T, I>, stamp: &ReadingStamp) -> Result<(), Error<T, I>> {
+            if let Some(last) = LastStamp::<T, I>::get(device_id) {
+                ensure!(stamp.sequence != last.sequence, Error::<T, I>::ReplayedReading);
+                ensure!(
+                    stamp.sequence > last.sequence && stamp.timestamp >= last.timestamp,
+                    Error::<T, I>::OutOfOrderReading
+                );
+            }
+
+            let now = T::UnixTime::now().as_secs();
+            let drift = if stamp.timestamp > now { stamp.timestamp - now } else { now - stamp.timestamp };
+            ensure!(drift <= T::MaxClockDrift::get(), Error::<T, I>::ClockDrift);
+            Ok(())
+        }
+
+        /// Whether the device's previous reading was too recent for an unsigned one
+        fn unsigned_rate_limited(device_id: &DeviceIdOf<T, I>) -> bool {
+            let now = frame_system::Pallet::<T>::block_number();
+            LastSubmission::<T, I>::get(device_id)
+                .map_or(false, |last| now < last.saturating_add(T::UnsignedInterval::get()))
+        }
+
+        /// Pool validity of an unsigned reading. Readings signed with a later nonce than the
+        /// device's next one are valid in the pool, queued behind their predecessors, unless
+        /// `exact_nonce` is set as it is for inclusion in a block.
+        fn validate_unsigned_reading(
+            device_id: &DeviceIdOf<T, I>,
+            reading: &ReadingOf<T, I>,
+            stamp: &ReadingStamp,
+            nonce: u64,
+            signature: &SignatureOf<T, I>,
+            exact_nonce: bool,
+        ) -> TransactionValidity {
+            let device = AuthorizedDevices::<T, I>::get(device_id).ok_or(InvalidTransaction::BadSigner)?;
+
+            let next_nonce = DeviceNonces::<T, I>::get(device_id);
+            if nonce < next_nonce {
+                return InvalidTransaction::Stale.into();
+            }
+            if exact_nonce && nonce > next_nonce {
+                return InvalidTransaction::Future.into();
+            }
+            if !device.key.verify(&Self::signing_payload(device_id, reading, stamp, nonce), signature) {
+                return InvalidTransaction::BadProof.into();
+            }
+            match Self::ensure_fresh(device_id, stamp) {
+                Ok(()) => {},
+                // A device clock ahead of the block time may be valid in a later block
+                Err(Error::ClockDrift) if stamp.timestamp > T::UnixTime::now().as_secs() => {
+                    return InvalidTransaction::Future.into()
+                },
+                Err(_) => return InvalidTransaction::Stale.into(),
+            }
+            if Self::unsigned_rate_limited(device_id) {
+                return InvalidTransaction::ExhaustsResources.into();
+            }
+
+            // Devices that have been silent the longest go first. A device that has not
+            // reported yet has been silent since it was registered.
+            let now = frame_system::Pallet::<T>::block_number();
+            let silent = LastSubmission::<T, I>::get(device_id).map_or_else(
+                || now.saturating_sub(device.registered_at),
+                |last| now.saturating_sub(last.saturating_add(T::UnsignedInterval::get())),
+            );
+            let priority = T::UnsignedPriority::get().saturating_add(silent.saturated_into::<u64>());
+
+            // Device ids are only unique within an instance, so the tags name the instance too
+            let instance = <Self as PalletInfoAccess>::index() as u32;
+            let mut validity = ValidTransaction::with_tag_prefix("Telemetry")
+                .priority(priority)
+                .and_provides((instance, device_id, nonce))
+                .longevity(T::UnsignedInterval::get().saturated_into::<u64>().max(1))
+                .propagate(true);
+            if nonce > next_nonce {
+                validity = validity.and_requires((instance, device_id, nonce - 1));
+            }
+            validity.build()
+        }
+
+        /// The chain's genesis hash and this instance's index in the runtime. Every payload a
+        /// device signs starts with them, so its signatures are only valid on this chain and
+        /// this instance.
+        pub fn signing_domain() -> (T::Hash, u32) {
+            let genesis_hash = frame_system::Pallet::<T>::block_hash(T::BlockNumber::zero());
+            (genesis_hash, <Self as PalletInfoAccess>::index() as u32)
+        }
+
+        /// The bytes a device signs for a reading: the SCALE encoded
+        /// `(READING_CONTEXT, signing_domain, device_id, reading, stamp, nonce)`
+        pub fn signing_payload(
+            device_id: &DeviceIdOf<T, I>,
+            reading: &ReadingOf<T, I>,
+            stamp: &ReadingStamp,
+            nonce: u64,
+        ) -> Vec<u8> {
+            (READING_CONTEXT, Self::signing_domain(), device_id, reading, stamp, nonce).encode()
+        }
+
+        /// The bytes a device key signs to prove it may be registered for `device_id` by
+        /// `owner`: the SCALE encoded `(KEY_PROOF_CONTEXT, signing_domain, device_id, owner)`
+        pub fn key_proof_payload(device_id: &DeviceIdOf<T, I>, owner: &T::AccountId) -> Vec<u8> {
+            (KEY_PROOF_CONTEXT, Self::signing_domain(), device_id, owner).encode()
+        }
+
+        /// The registration of `device_id`, if `who` owns it
+        fn ensure_device_owner(
+            device_id: &DeviceIdOf<T, I>,
+            who: &T::AccountId,
+        ) -> Result<DeviceInfoOf<T>, DispatchError> {
+            let device = AuthorizedDevices::<T, I>::get(device_id).ok_or(Error::<T, I>::UnknownDevice)?;
+            ensure!(device.owner == *who, Error::<T, I>::NotDeviceOwner);
+            Ok(device)
+        }
+
+        /// Metrics of `reading` outside their optimal range
+        pub fn anomalies(reading: &ReadingOf<T, I>) -> Vec<MetricOf<T, I>> {
+            T::Schema::metrics(reading)
+                .into_iter()
+                .filter(|(metric, value)| {
+                    OptimalRanges::<T, I>::get(metric).map_or(false, |(min, max)| *value < min || *value > max)
+                })
+                .map(|(metric, _)| metric)
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Test runtime for `pallet-telemetry`.
+
+use crate as pallet_telemetry;
+use crate::{OnTelemetryPruned, Telemetry, TelemetryRecordOf};
+use frame_support::{
+    traits::{ConstU16, ConstU32, ConstU64},
+    weights::Weight,
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub type AccountId = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+
+/// Block time of the test externalities, in seconds since the Unix epoch
+pub const NOW: u64 = 1_700_000_000;
+
+/// Long enough for every supported signature scheme
+pub type MaxSignatureLength = ConstU32<{ pallet_telemetry::device::DILITHIUM_SIGNATURE_BYTES as u32 }>;
+
+thread_local! {
+    static PRUNED: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Ids of the spirulina readings pruned so far, in order
+pub fn pruned() -> Vec<u64> {
+    PRUNED.with(|pruned| pruned.borrow().clone())
+}
+
+/// Notes which spirulina readings were pruned
+pub struct RecordPruned;
+
+/// Weight `RecordPruned` uses for every reading
+pub const ON_PRUNED_WEIGHT: Weight = Weight::from_ref_time(1_000_000);
+
+impl OnTelemetryPruned<u64, TelemetryRecordOf<Test>> for RecordPruned {
+    fn max_weight() -> Weight {
+        ON_PRUNED_WEIGHT
+    }
+
+    fn on_pruned(telemetry_id: u64, record: &TelemetryRecordOf<Test>) -> Weight {
+        // The reading must still be stored for the hook to export it
+        assert_eq!(Telemetry::<Test>::get(telemetry_id).as_ref(), Some(record));
+        PRUNED.with(|pruned| pruned.borrow_mut().push(telemetry_id));
+        ON_PRUNED_WEIGHT
+    }
+}
+
+/// A Dilithium3 key with its signatures over the payloads the spirulina benchmarks build: the
+/// key proof of device `[7; 32]` for the whitelisted caller, and the reading signature of the
+/// default reading with stamp `(1, NOW)` and nonce 0, in the genesis state of this runtime
+#[cfg(feature = "runtime-benchmarks")]
+pub struct DilithiumTestVector;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_telemetry::BenchmarkHelper for DilithiumTestVector {
+    fn dilithium_public_key() -> [u8; pallet_telemetry::device::DILITHIUM_PUBLIC_KEY_BYTES] {
+        *include_bytes!("../test-vectors/dilithium3.pub")
+    }
+
+    fn dilithium_signature(payload: &[u8]) -> Option<Vec<u8>> {
+        let public = Self::dilithium_public_key();
+        [
+            &include_bytes!("../test-vectors/dilithium3-key-proof.sig")[..],
+            &include_bytes!("../test-vectors/dilithium3-reading.sig")[..],
+        ]
+        .into_iter()
+        .find(|signature| pallet_telemetry::device::dilithium::verify(signature, payload, &public))
+        .map(|signature| signature.to_vec())
+    }
+}
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Timestamp: pallet_timestamp,
+        SpirulinaTelemetry: pallet_telemetry,
+        KombuchaTelemetry: pallet_telemetry::<Instance1>,
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<1>;
+    type WeightInfo = ();
+}
+
+impl pallet_telemetry::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Schema = pallet_telemetry::Spirulina<ConstU32<16>>;
+    type TelemetryId = u64;
+    type MaxDeviceIdLength = ConstU32<32>;
+    type MaxSignatureLength = MaxSignatureLength;
+    type RangeOrigin = EnsureRoot<AccountId>;
+    type UnsignedPriority = ConstU64<{ 1 << 20 }>;
+    type UnsignedInterval = ConstU64<5>;
+    type UnixTime = Timestamp;
+    type MaxClockDrift = ConstU64<300>;
+    type MaxReadingsPerDevice = ConstU32<3>;
+    type RetentionPeriod = ConstU64<100>;
+    type OnPruned = RecordPruned;
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = DilithiumTestVector;
+}
+
+impl pallet_telemetry::Config<pallet_telemetry::Instance1> for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Schema = pallet_telemetry::Kombucha;
+    type TelemetryId = u64;
+    type MaxDeviceIdLength = ConstU32<32>;
+    type MaxSignatureLength = MaxSignatureLength;
+    type RangeOrigin = EnsureRoot<AccountId>;
+    type UnsignedPriority = ConstU64<{ 1 << 20 }>;
+    type UnsignedInterval = ConstU64<5>;
+    type UnixTime = Timestamp;
+    type MaxClockDrift = ConstU64<300>;
+    type MaxReadingsPerDevice = ConstU32<3>;
+    type RetentionPeriod = ConstU64<100>;
+    type OnPruned = ();
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = DilithiumTestVector;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    let mut ext = sp_io::TestExternalities::new(storage);
+    PRUNED.with(|pruned| pruned.borrow_mut().clear());
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        Timestamp::set_timestamp(NOW * 1_000);
+    });
+    ext
+}
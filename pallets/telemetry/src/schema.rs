@@ -0,0 +1,173 @@
+//! Sensor schemas for `pallet-telemetry`.
+//!
+//! A schema fixes what one device reading contains and which of its values are checked
+//! against the optimal ranges set on chain. Each instance of the pallet records readings of
+//! one schema: NRSH spirulina photobioreactors report [`Spirulina`] readings, ELXR kombucha
+//! fermenters report [`Kombucha`] readings.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{pallet_prelude::*, Parameter};
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Member, RuntimeDebug};
+use sp_std::{marker::PhantomData, prelude::*, vec};
+
+/// What a device reading contains and how it is checked
+pub trait SensorSchema {
+    /// A metric of a reading that can be checked against an optimal range
+    type Metric: Parameter + Member + MaxEncodedLen + Copy;
+
+    /// One reading of a device, as submitted and stored
+    type Reading: Parameter + Member + MaxEncodedLen + Default;
+
+    /// Identifies the cultivation or fermentation batch a reading belongs to
+    type BatchId: Parameter + Member + MaxEncodedLen;
+
+    /// The number of distinct metrics
+    const METRICS: u32;
+
+    /// Every range-checked metric of `reading` with its value
+    fn metrics(reading: &Self::Reading) -> Vec<(Self::Metric, u32)>;
+
+    /// Whether `reading` shows the culture is ready, i.e. the spirulina batch can be
+    /// harvested or the kombucha has finished fermenting
+    fn is_ready(reading: &Self::Reading) -> bool;
+
+    /// The batch `reading` belongs to, if the schema tracks batches
+    fn batch_id(reading: &Self::Reading) -> Option<Self::BatchId>;
+}
+
+/// Range-checked metrics of a spirulina photobioreactor
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum SpirulinaMetric {
+    Ph,
+    Temperature,
+    Light,
+    Density,
+    DissolvedOxygen,
+    Nitrate,
+    Salinity,
+}
+
+/// One reading of a spirulina photobioreactor
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+pub struct SpirulinaReading<BatchId> {
+    /// The cultivation batch the reading belongs to
+    pub batch_id: BatchId,
+    /// pH, scaled by 100
+    pub ph: u32,
+    /// Temperature in °C, scaled by 100
+    pub temperature: u32,
+    /// Light intensity, scaled by 10
+    pub light: u32,
+    /// Culture density, scaled by 1000
+    pub density: u32,
+    /// Dissolved oxygen, scaled by 100
+    pub dissolved_oxygen: u32,
+    /// Nitrate, scaled by 10
+    pub nitrate: u32,
+    /// Salinity, scaled by 10
+    pub salinity: u32,
+    /// Battery level, scaled by 10
+    pub battery: u32,
+    /// The device's overall health score, scaled by 10
+    pub overall_health: u32,
+    /// Whether the device considers the batch ready to harvest
+    pub harvest_ready: bool,
+}
+
+/// NRSH spirulina readings, with batch ids of at most `MaxBatchIdLength` bytes
+pub struct Spirulina<MaxBatchIdLength>(PhantomData<MaxBatchIdLength>);
+
+impl<MaxBatchIdLength: Get<u32> + 'static> SensorSchema for Spirulina<MaxBatchIdLength> {
+    type Metric = SpirulinaMetric;
+    type Reading = SpirulinaReading<BoundedVec<u8, MaxBatchIdLength>>;
+    type BatchId = BoundedVec<u8, MaxBatchIdLength>;
+
+    const METRICS: u32 = 7;
+
+    fn metrics(reading: &Self::Reading) -> Vec<(SpirulinaMetric, u32)> {
+        vec![
+            (SpirulinaMetric::Ph, reading.ph),
+            (SpirulinaMetric::Temperature, reading.temperature),
+            (SpirulinaMetric::Light, reading.light),
+            (SpirulinaMetric::Density, reading.density),
+            (SpirulinaMetric::DissolvedOxygen, reading.dissolved_oxygen),
+            (SpirulinaMetric::Nitrate, reading.nitrate),
+            (SpirulinaMetric::Salinity, reading.salinity),
+        ]
+    }
+
+    fn is_ready(reading: &Self::Reading) -> bool {
+        reading.harvest_ready
+    }
+
+    fn batch_id(reading: &Self::Reading) -> Option<Self::BatchId> {
+        Some(reading.batch_id.clone())
+    }
+}
+
+/// Range-checked metrics of a kombucha fermenter
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum KombuchaMetric {
+    Ph,
+    Temperature,
+    Light,
+    Density,
+    Co2,
+    Fermentation,
+}
+
+/// One reading of a kombucha fermenter
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen, Default)]
+pub struct KombuchaReading {
+    /// pH, scaled by 100
+    pub ph: u32,
+    /// Temperature in °C, scaled by 100
+    pub temperature: u32,
+    /// Light intensity, scaled by 10
+    pub light: u32,
+    /// Liquid density, scaled by 1000
+    pub density: u32,
+    /// CO2, scaled by 10
+    pub co2: u32,
+    /// Fermentation progress, scaled by 1000
+    pub fermentation: u32,
+    /// Battery level, scaled by 10
+    pub battery: u32,
+}
+
+/// ELXR kombucha readings
+pub struct Kombucha;
+
+impl Kombucha {
+    /// Fermentation progress from which a batch counts as complete (80%, scaled by 1000)
+    pub const FERMENTATION_COMPLETE: u32 = 800;
+}
+
+impl SensorSchema for Kombucha {
+    type Metric = KombuchaMetric;
+    type Reading = KombuchaReading;
+    /// Kombucha readings carry no batch id
+    type BatchId = ();
+
+    const METRICS: u32 = 6;
+
+    fn metrics(reading: &KombuchaReading) -> Vec<(KombuchaMetric, u32)> {
+        vec![
+            (KombuchaMetric::Ph, reading.ph),
+            (KombuchaMetric::Temperature, reading.temperature),
+            (KombuchaMetric::Light, reading.light),
+            (KombuchaMetric::Density, reading.density),
+            (KombuchaMetric::Co2, reading.co2),
+            (KombuchaMetric::Fermentation, reading.fermentation),
+        ]
+    }
+
+    fn is_ready(reading: &KombuchaReading) -> bool {
+        reading.fermentation >= Self::FERMENTATION_COMPLETE
+    }
+
+    fn batch_id(_reading: &KombuchaReading) -> Option<()> {
+        None
+    }
+}
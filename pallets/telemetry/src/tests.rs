@@ -0,0 +1,807 @@
+//! Unit tests for `pallet-telemetry`.
+
+use crate::{
+    mock::*, weights::WeightInfo, AuthorizedDevices, DeviceIdOf, DeviceInfo, DeviceKey, DeviceLatestTelemetry,
+    DeviceNonces, DeviceReadings, Error, Instance1, KombuchaMetric, KombuchaReading, LastStamp, NextTelemetryId,
+    OptimalRanges, PruneCursor, ReadingStamp, SignatureOf, SpirulinaMetric, SpirulinaReading, Telemetry,
+    TelemetryRecord,
+};
+use frame_support::{
+    assert_noop, assert_ok,
+    dispatch::{DispatchResultWithPostInfo, GetDispatchInfo},
+    traits::Hooks,
+    weights::Weight,
+    BoundedVec,
+};
+use sp_core::{ed25519, sr25519, Pair};
+use sp_runtime::{
+    traits::ValidateUnsigned,
+    transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidity},
+    DispatchError,
+};
+
+type SpirulinaReadingOf = crate::ReadingOf<Test>;
+type KombuchaEvent = crate::Event<Test, Instance1>;
+
+fn device(id: &[u8]) -> DeviceIdOf<Test> {
+    BoundedVec::truncate_from(id.to_vec())
+}
+
+/// The key every test device signs with unless a test says otherwise
+fn device_pair() -> sr25519::Pair {
+    sr25519::Pair::from_seed(&[1u8; 32])
+}
+
+fn device_key() -> DeviceKey {
+    DeviceKey::Sr25519(device_pair().public())
+}
+
+fn sign(pair: &sr25519::Pair, payload: &[u8]) -> SignatureOf<Test> {
+    BoundedVec::truncate_from(pair.sign(payload).0.to_vec())
+}
+
+/// The test device key's proof that `who` may register it as device `id`
+fn key_proof(who: AccountId, id: &[u8]) -> SignatureOf<Test> {
+    sign(&device_pair(), &SpirulinaTelemetry::key_proof_payload(&device(id), &who))
+}
+
+/// A reading's stamp, taken at the block time. Tests number readings from 1, one ahead of
+/// the nonce they are signed with.
+fn stamp(sequence: u64) -> ReadingStamp {
+    ReadingStamp { sequence, timestamp: NOW }
+}
+
+fn spirulina_reading() -> SpirulinaReadingOf {
+    SpirulinaReading {
+        batch_id: BoundedVec::truncate_from(b"batch-1".to_vec()),
+        ph: 1_000,
+        temperature: 3_500,
+        light: 250,
+        density: 1_200,
+        dissolved_oxygen: 800,
+        nitrate: 120,
+        salinity: 150,
+        battery: 950,
+        overall_health: 90,
+        harvest_ready: false,
+    }
+}
+
+fn kombucha_reading(fermentation: u32) -> KombuchaReading {
+    KombuchaReading { ph: 320, temperature: 2_400, light: 10, density: 1_010, co2: 50, fermentation, battery: 900 }
+}
+
+fn authorize(who: AccountId, id: &[u8]) {
+    assert_ok!(SpirulinaTelemetry::authorize_device(
+        RuntimeOrigin::signed(who),
+        device(id),
+        device_key(),
+        key_proof(who, id)
+    ));
+}
+
+/// Submit `reading` signed by the test device key with the device's next nonce
+fn submit(who: AccountId, id: &[u8], reading: SpirulinaReadingOf) -> DispatchResultWithPostInfo {
+    let nonce = DeviceNonces::<Test>::get(device(id));
+    submit_stamped(who, id, reading, stamp(nonce + 1))
+}
+
+fn submit_stamped(
+    who: AccountId,
+    id: &[u8],
+    reading: SpirulinaReadingOf,
+    stamp: ReadingStamp,
+) -> DispatchResultWithPostInfo {
+    let nonce = DeviceNonces::<Test>::get(device(id));
+    let signature = sign(&device_pair(), &SpirulinaTelemetry::signing_payload(&device(id), &reading, &stamp, nonce));
+    SpirulinaTelemetry::submit_telemetry(RuntimeOrigin::signed(who), device(id), reading, stamp, nonce, signature)
+}
+
+fn submit_kombucha(who: AccountId, id: &[u8], reading: KombuchaReading) -> DispatchResultWithPostInfo {
+    let nonce = DeviceNonces::<Test, Instance1>::get(device(id));
+    let stamp = stamp(nonce + 1);
+    let signature = sign(&device_pair(), &KombuchaTelemetry::signing_payload(&device(id), &reading, &stamp, nonce));
+    KombuchaTelemetry::submit_telemetry(RuntimeOrigin::signed(who), device(id), reading, stamp, nonce, signature)
+}
+
+/// An unsigned submission of `reading` signed by the test device key with `nonce`
+fn unsigned_call(id: &[u8], reading: SpirulinaReadingOf, nonce: u64) -> crate::Call<Test> {
+    stamped_unsigned_call(id, reading, stamp(nonce + 1), nonce)
+}
+
+fn stamped_unsigned_call(id: &[u8], reading: SpirulinaReadingOf, stamp: ReadingStamp, nonce: u64) -> crate::Call<Test> {
+    let signature = sign(&device_pair(), &SpirulinaTelemetry::signing_payload(&device(id), &reading, &stamp, nonce));
+    crate::Call::submit_telemetry_unsigned { device_id: device(id), reading, stamp, nonce, signature }
+}
+
+fn validate(call: &crate::Call<Test>) -> TransactionValidity {
+    SpirulinaTelemetry::validate_unsigned(TransactionSource::External, call)
+}
+
+fn submit_unsigned(id: &[u8], nonce: u64) -> DispatchResultWithPostInfo {
+    let reading = spirulina_reading();
+    let stamp = stamp(nonce + 1);
+    let signature = sign(&device_pair(), &SpirulinaTelemetry::signing_payload(&device(id), &reading, &stamp, nonce));
+    SpirulinaTelemetry::submit_telemetry_unsigned(RuntimeOrigin::none(), device(id), reading, stamp, nonce, signature)
+}
+
+fn anomalies() -> Vec<SpirulinaMetric> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            RuntimeEvent::SpirulinaTelemetry(crate::Event::AnomalyDetected(_, metric)) => Some(metric),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn readings_of_authorized_devices_are_recorded() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        System::assert_last_event(crate::Event::<Test>::DeviceAuthorizationUpdated(device(b"pbr-1"), ALICE).into());
+
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+
+        assert_eq!(
+            Telemetry::<Test>::get(0),
+            Some(TelemetryRecord {
+                device_id: device(b"pbr-1"),
+                timestamp: 1,
+                stamp: stamp(1),
+                reading: spirulina_reading(),
+                reporter: ALICE,
+                signature: sign(
+                    &device_pair(),
+                    &SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &spirulina_reading(), &stamp(1), 0)
+                ),
+            })
+        );
+        assert_eq!(DeviceLatestTelemetry::<Test>::get(device(b"pbr-1")), Some(0));
+        assert_eq!(DeviceNonces::<Test>::get(device(b"pbr-1")), 1);
+        System::assert_last_event(crate::Event::<Test>::TelemetryRecorded(device(b"pbr-1"), 0).into());
+    });
+}
+
+#[test]
+fn only_the_device_owner_can_submit() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+
+        assert_noop!(submit(BOB, b"pbr-1", spirulina_reading()), Error::<Test>::DeviceNotAuthorized);
+        assert_noop!(submit(ALICE, b"pbr-2", spirulina_reading()), Error::<Test>::DeviceNotAuthorized);
+    });
+}
+
+#[test]
+fn readings_must_be_signed_by_the_device_key_with_its_next_nonce() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        let reading = spirulina_reading();
+        let payload = SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &reading, &stamp(1), 0);
+        let submit_signed = |nonce, signature| {
+            SpirulinaTelemetry::submit_telemetry(
+                RuntimeOrigin::signed(ALICE),
+                device(b"pbr-1"),
+                spirulina_reading(),
+                stamp(1),
+                nonce,
+                signature,
+            )
+        };
+
+        // Another key, another reading, garbage and the wrong nonce are all rejected
+        let other_pair = sr25519::Pair::from_seed(&[2u8; 32]);
+        assert_noop!(submit_signed(0, sign(&other_pair, &payload)), Error::<Test>::InvalidSignature);
+        let other_reading = SpirulinaReading { ph: 1, ..spirulina_reading() };
+        let other_payload = SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &other_reading, &stamp(1), 0);
+        assert_noop!(submit_signed(0, sign(&device_pair(), &other_payload)), Error::<Test>::InvalidSignature);
+        assert_noop!(submit_signed(0, BoundedVec::truncate_from(vec![7u8; 64])), Error::<Test>::InvalidSignature);
+        assert_noop!(submit_signed(1, sign(&device_pair(), &payload)), Error::<Test>::InvalidNonce);
+
+        // A valid submission can't be replayed
+        assert_ok!(submit_signed(0, sign(&device_pair(), &payload)));
+        assert_noop!(submit_signed(0, sign(&device_pair(), &payload)), Error::<Test>::InvalidNonce);
+    });
+}
+
+#[test]
+fn ed25519_and_dilithium_keys_are_verified() {
+    new_test_ext().execute_with(|| {
+        let reading = spirulina_reading();
+
+        let ed25519_pair = ed25519::Pair::from_seed(&[3u8; 32]);
+        let proof = SpirulinaTelemetry::key_proof_payload(&device(b"ed"), &ALICE);
+        assert_ok!(SpirulinaTelemetry::authorize_device(
+            RuntimeOrigin::signed(ALICE),
+            device(b"ed"),
+            DeviceKey::Ed25519(ed25519_pair.public()),
+            BoundedVec::truncate_from(ed25519_pair.sign(&proof).0.to_vec())
+        ));
+        let payload = SpirulinaTelemetry::signing_payload(&device(b"ed"), &reading, &stamp(1), 0);
+        assert_ok!(SpirulinaTelemetry::submit_telemetry(
+            RuntimeOrigin::signed(ALICE),
+            device(b"ed"),
+            reading.clone(),
+            stamp(1),
+            0,
+            BoundedVec::truncate_from(ed25519_pair.sign(&payload).0.to_vec())
+        ));
+
+        let dilithium_keys = pqc_dilithium::Keypair::generate();
+        let proof = SpirulinaTelemetry::key_proof_payload(&device(b"pq"), &ALICE);
+        assert_ok!(SpirulinaTelemetry::authorize_device(
+            RuntimeOrigin::signed(ALICE),
+            device(b"pq"),
+            DeviceKey::Dilithium(dilithium_keys.public),
+            BoundedVec::truncate_from(dilithium_keys.sign(&proof).to_vec())
+        ));
+        let payload = SpirulinaTelemetry::signing_payload(&device(b"pq"), &reading, &stamp(1), 0);
+        assert_noop!(
+            SpirulinaTelemetry::submit_telemetry(
+                RuntimeOrigin::signed(ALICE),
+                device(b"pq"),
+                reading.clone(),
+                stamp(1),
+                0,
+                sign(&device_pair(), &payload)
+            ),
+            Error::<Test>::InvalidSignature
+        );
+        assert_ok!(SpirulinaTelemetry::submit_telemetry(
+            RuntimeOrigin::signed(ALICE),
+            device(b"pq"),
+            reading,
+            stamp(1),
+            0,
+            BoundedVec::truncate_from(dilithium_keys.sign(&payload).to_vec())
+        ));
+    });
+}
+
+#[test]
+fn registered_devices_cannot_be_claimed_by_another_account() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+
+        assert_noop!(
+            SpirulinaTelemetry::authorize_device(
+                RuntimeOrigin::signed(BOB),
+                device(b"pbr-1"),
+                device_key(),
+                key_proof(BOB, b"pbr-1")
+            ),
+            Error::<Test>::DeviceAlreadyRegistered
+        );
+
+        // The owner may rotate the key, proving possession of the new one
+        let rotated_pair = ed25519::Pair::from_seed(&[3u8; 32]);
+        let rotated = DeviceKey::Ed25519(rotated_pair.public());
+        let proof = SpirulinaTelemetry::key_proof_payload(&device(b"pbr-1"), &ALICE);
+        assert_ok!(SpirulinaTelemetry::authorize_device(
+            RuntimeOrigin::signed(ALICE),
+            device(b"pbr-1"),
+            rotated.clone(),
+            BoundedVec::truncate_from(rotated_pair.sign(&proof).0.to_vec())
+        ));
+        assert_eq!(
+            AuthorizedDevices::<Test>::get(device(b"pbr-1")),
+            Some(DeviceInfo { owner: ALICE, key: rotated, registered_at: 1 })
+        );
+    });
+}
+
+#[test]
+fn keys_are_only_registered_with_a_proof_of_possession() {
+    new_test_ext().execute_with(|| {
+        let authorize_with = |who: AccountId, id: &[u8], proof| {
+            SpirulinaTelemetry::authorize_device(RuntimeOrigin::signed(who), device(id), device_key(), proof)
+        };
+
+        // Someone who only knows a device's public key can't claim it, and a proof made for
+        // another account, another device, another instance or a reading doesn't carry over
+        let other_pair = sr25519::Pair::from_seed(&[2u8; 32]);
+        let kombucha_proof = KombuchaTelemetry::key_proof_payload(&device(b"pbr-1"), &BOB);
+        let reading_payload =
+            SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &spirulina_reading(), &stamp(1), 0);
+        for proof in [
+            sign(&other_pair, &SpirulinaTelemetry::key_proof_payload(&device(b"pbr-1"), &BOB)),
+            key_proof(ALICE, b"pbr-1"),
+            key_proof(BOB, b"pbr-2"),
+            sign(&device_pair(), &kombucha_proof),
+            sign(&device_pair(), &reading_payload),
+            BoundedVec::truncate_from(vec![7u8; 64]),
+        ] {
+            assert_noop!(authorize_with(BOB, b"pbr-1", proof), Error::<Test>::InvalidKeyProof);
+        }
+
+        assert_ok!(authorize_with(BOB, b"pbr-1", key_proof(BOB, b"pbr-1")));
+        assert_eq!(AuthorizedDevices::<Test>::get(device(b"pbr-1")).map(|device| device.owner), Some(BOB));
+    });
+}
+
+#[test]
+fn readings_are_bound_to_their_device_and_instance() {
+    new_test_ext().execute_with(|| {
+        // Two devices sharing a key, both at nonce 0
+        authorize(ALICE, b"pbr-1");
+        authorize(ALICE, b"pbr-2");
+        let payload = SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &spirulina_reading(), &stamp(1), 0);
+        let signature = sign(&device_pair(), &payload);
+
+        // A reading of one can't be replayed as a reading of the other, signed or unsigned
+        assert_noop!(
+            SpirulinaTelemetry::submit_telemetry(
+                RuntimeOrigin::signed(ALICE),
+                device(b"pbr-2"),
+                spirulina_reading(),
+                stamp(1),
+                0,
+                signature.clone()
+            ),
+            Error::<Test>::InvalidSignature
+        );
+        let replayed = crate::Call::submit_telemetry_unsigned {
+            device_id: device(b"pbr-2"),
+            reading: spirulina_reading(),
+            stamp: stamp(1),
+            nonce: 0,
+            signature: signature.clone(),
+        };
+        assert_eq!(validate(&replayed), InvalidTransaction::BadProof.into());
+        assert_ok!(SpirulinaTelemetry::submit_telemetry(
+            RuntimeOrigin::signed(ALICE),
+            device(b"pbr-1"),
+            spirulina_reading(),
+            stamp(1),
+            0,
+            signature
+        ));
+
+        // Every payload is bound to the chain's genesis hash and the instance
+        let genesis_hash = System::block_hash(0);
+        assert_eq!(SpirulinaTelemetry::signing_domain(), (genesis_hash, 2));
+        assert_eq!(KombuchaTelemetry::signing_domain(), (genesis_hash, 3));
+    });
+}
+
+#[test]
+fn owners_revoke_devices_and_their_nonce_survives() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+
+        assert_noop!(
+            SpirulinaTelemetry::revoke_device(RuntimeOrigin::signed(BOB), device(b"pbr-1")),
+            Error::<Test>::NotDeviceOwner
+        );
+        assert_ok!(SpirulinaTelemetry::revoke_device(RuntimeOrigin::signed(ALICE), device(b"pbr-1")));
+        System::assert_last_event(crate::Event::<Test>::DeviceRevoked(device(b"pbr-1"), ALICE).into());
+
+        assert_noop!(submit(ALICE, b"pbr-1", spirulina_reading()), Error::<Test>::DeviceNotAuthorized);
+        assert_noop!(
+            SpirulinaTelemetry::revoke_device(RuntimeOrigin::signed(ALICE), device(b"pbr-1")),
+            Error::<Test>::UnknownDevice
+        );
+
+        // A new owner can register the id, but the spent nonce stays spent
+        authorize(BOB, b"pbr-1");
+        assert_eq!(DeviceNonces::<Test>::get(device(b"pbr-1")), 1);
+    });
+}
+
+#[test]
+fn transferred_devices_report_through_the_new_owner() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+
+        assert_noop!(
+            SpirulinaTelemetry::transfer_device(RuntimeOrigin::signed(BOB), device(b"pbr-1"), BOB),
+            Error::<Test>::NotDeviceOwner
+        );
+        assert_ok!(SpirulinaTelemetry::transfer_device(RuntimeOrigin::signed(ALICE), device(b"pbr-1"), BOB));
+        System::assert_last_event(crate::Event::<Test>::DeviceTransferred(device(b"pbr-1"), ALICE, BOB).into());
+
+        assert_noop!(submit(ALICE, b"pbr-1", spirulina_reading()), Error::<Test>::DeviceNotAuthorized);
+        assert_ok!(submit(BOB, b"pbr-1", spirulina_reading()));
+    });
+}
+
+#[test]
+fn unsigned_readings_are_recorded_for_the_device_owner() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+
+        assert_noop!(
+            SpirulinaTelemetry::submit_telemetry_unsigned(
+                RuntimeOrigin::signed(BOB),
+                device(b"pbr-1"),
+                spirulina_reading(),
+                stamp(1),
+                0,
+                sign(
+                    &device_pair(),
+                    &SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &spirulina_reading(), &stamp(1), 0)
+                )
+            ),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(submit_unsigned(b"pbr-1", 0));
+
+        assert_eq!(Telemetry::<Test>::get(0).map(|record| record.reporter), Some(ALICE));
+        assert_eq!(DeviceNonces::<Test>::get(device(b"pbr-1")), 1);
+
+        // Signed readings share the nonce
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        assert_eq!(DeviceNonces::<Test>::get(device(b"pbr-1")), 2);
+    });
+}
+
+#[test]
+fn unsigned_readings_are_validated_against_the_device_key_and_nonce() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(validate(&unsigned_call(b"pbr-1", spirulina_reading(), 0)), InvalidTransaction::BadSigner.into());
+
+        authorize(ALICE, b"pbr-1");
+        let valid = validate(&unsigned_call(b"pbr-1", spirulina_reading(), 0)).unwrap();
+        assert!(valid.requires.is_empty());
+        assert_ok!(SpirulinaTelemetry::pre_dispatch(&unsigned_call(b"pbr-1", spirulina_reading(), 0)));
+
+        // A reading signed for another one
+        let payload = SpirulinaTelemetry::signing_payload(&device(b"pbr-1"), &spirulina_reading(), &stamp(1), 0);
+        let signature = sign(&device_pair(), &payload);
+        let forged = crate::Call::submit_telemetry_unsigned {
+            device_id: device(b"pbr-1"),
+            reading: SpirulinaReading { harvest_ready: true, ..spirulina_reading() },
+            stamp: stamp(1),
+            nonce: 0,
+            signature,
+        };
+        assert_eq!(validate(&forged), InvalidTransaction::BadProof.into());
+
+        // Later nonces wait in the pool for their predecessor, but can't be included yet
+        let queued = validate(&unsigned_call(b"pbr-1", spirulina_reading(), 1)).unwrap();
+        assert_eq!(queued.requires, valid.provides);
+        assert_eq!(
+            SpirulinaTelemetry::pre_dispatch(&unsigned_call(b"pbr-1", spirulina_reading(), 1)),
+            Err(InvalidTransaction::Future.into())
+        );
+
+        // Spent nonces are stale
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        assert_eq!(validate(&unsigned_call(b"pbr-1", spirulina_reading(), 0)), InvalidTransaction::Stale.into());
+    });
+}
+
+#[test]
+fn unsigned_readings_are_rate_limited_and_prioritized_by_silence() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        authorize(ALICE, b"pbr-2");
+        System::set_block_number(20);
+        assert_ok!(submit_unsigned(b"pbr-1", 0));
+        assert_ok!(submit_unsigned(b"pbr-2", 0));
+
+        // Within the interval
+        System::set_block_number(24);
+        assert_eq!(
+            validate(&unsigned_call(b"pbr-1", spirulina_reading(), 1)),
+            InvalidTransaction::ExhaustsResources.into()
+        );
+        assert_noop!(submit_unsigned(b"pbr-1", 1), Error::<Test>::SubmittedTooSoon);
+
+        // The device that has been silent longer is preferred
+        System::set_block_number(27);
+        assert_ok!(submit_unsigned(b"pbr-2", 1));
+        System::set_block_number(33);
+        let quiet = validate(&unsigned_call(b"pbr-1", spirulina_reading(), 1)).unwrap();
+        let chatty = validate(&unsigned_call(b"pbr-2", spirulina_reading(), 2)).unwrap();
+        assert_eq!(quiet.priority, (1 << 20) + 8);
+        assert!(quiet.priority > chatty.priority);
+    });
+}
+
+#[test]
+fn devices_that_never_reported_are_silent_since_their_registration() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1_000_000);
+        authorize(ALICE, b"pbr-1");
+
+        // Silent for four blocks, not for the whole chain history
+        System::set_block_number(1_000_004);
+        let fresh = validate(&unsigned_call(b"pbr-1", spirulina_reading(), 0)).unwrap();
+        assert_eq!(fresh.priority, (1 << 20) + 4);
+
+        // Rotating the key does not reset the registration block
+        authorize(ALICE, b"pbr-1");
+        System::set_block_number(1_000_010);
+        let fresh = validate(&unsigned_call(b"pbr-1", spirulina_reading(), 0)).unwrap();
+        assert_eq!(fresh.priority, (1 << 20) + 10);
+    });
+}
+
+#[test]
+fn replayed_and_out_of_order_readings_are_rejected() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        let at = |sequence, timestamp| ReadingStamp { sequence, timestamp };
+
+        assert_ok!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(5, NOW)));
+        assert_eq!(LastStamp::<Test>::get(device(b"pbr-1")), Some(at(5, NOW)));
+
+        // Even re-signed with the next nonce
+        assert_noop!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(5, NOW)), Error::<Test>::ReplayedReading);
+        assert_noop!(
+            submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(4, NOW)),
+            Error::<Test>::OutOfOrderReading
+        );
+        assert_noop!(
+            submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(6, NOW - 1)),
+            Error::<Test>::OutOfOrderReading
+        );
+
+        // Sequence numbers may skip readings the device never delivered
+        assert_ok!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(9, NOW)));
+        let latest = DeviceLatestTelemetry::<Test>::get(device(b"pbr-1")).and_then(Telemetry::<Test>::get);
+        assert_eq!(latest.map(|record| record.stamp), Some(at(9, NOW)));
+
+        // The pool drops replays of unsigned readings
+        let nonce = DeviceNonces::<Test>::get(device(b"pbr-1"));
+        assert_eq!(
+            validate(&stamped_unsigned_call(b"pbr-1", spirulina_reading(), at(9, NOW), nonce)),
+            InvalidTransaction::Stale.into()
+        );
+    });
+}
+
+#[test]
+fn device_clocks_must_stay_close_to_block_time() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        let at = |timestamp| ReadingStamp { sequence: 1, timestamp };
+
+        assert_noop!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(NOW + 301)), Error::<Test>::ClockDrift);
+        assert_noop!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(NOW - 301)), Error::<Test>::ClockDrift);
+
+        // An unsigned reading from a clock running ahead may become valid later
+        assert_eq!(
+            validate(&stamped_unsigned_call(b"pbr-1", spirulina_reading(), at(NOW + 301), 0)),
+            InvalidTransaction::Future.into()
+        );
+        assert_eq!(
+            validate(&stamped_unsigned_call(b"pbr-1", spirulina_reading(), at(NOW - 301), 0)),
+            InvalidTransaction::Stale.into()
+        );
+
+        assert_ok!(submit_stamped(ALICE, b"pbr-1", spirulina_reading(), at(NOW + 300)));
+    });
+}
+
+#[test]
+fn only_metrics_outside_a_configured_range_are_anomalies() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        assert_ok!(SpirulinaTelemetry::update_optimal_ranges(
+            RuntimeOrigin::root(),
+            vec![(SpirulinaMetric::Ph, 850, 1_050), (SpirulinaMetric::Temperature, 3_000, 3_700)]
+        ));
+        System::assert_last_event(crate::Event::<Test>::OptimalRangesUpdated.into());
+
+        // In range, and no range for the other metrics
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        assert!(anomalies().is_empty());
+
+        let reading = SpirulinaReading { ph: 1_100, temperature: 2_900, ..spirulina_reading() };
+        assert_ok!(submit(ALICE, b"pbr-1", reading));
+        assert_eq!(anomalies(), vec![SpirulinaMetric::Ph, SpirulinaMetric::Temperature]);
+    });
+}
+
+#[test]
+fn harvest_ready_and_completed_fermentation_are_reported() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        let reading = SpirulinaReading { harvest_ready: true, ..spirulina_reading() };
+        assert_ok!(submit(ALICE, b"pbr-1", reading));
+        let batch_id = BoundedVec::truncate_from(b"batch-1".to_vec());
+        System::assert_has_event(crate::Event::<Test>::CultureReady(device(b"pbr-1"), Some(batch_id)).into());
+
+        assert_ok!(KombuchaTelemetry::authorize_device(
+            RuntimeOrigin::signed(ALICE),
+            device(b"vat-1"),
+            device_key(),
+            sign(&device_pair(), &KombuchaTelemetry::key_proof_payload(&device(b"vat-1"), &ALICE))
+        ));
+        for fermentation in [799, 800] {
+            assert_ok!(submit_kombucha(ALICE, b"vat-1", kombucha_reading(fermentation)));
+        }
+        let ready = System::events()
+            .into_iter()
+            .filter(|record| record.event == KombuchaEvent::CultureReady(device(b"vat-1"), None).into())
+            .count();
+        assert_eq!(ready, 1);
+    });
+}
+
+#[test]
+fn optimal_ranges_require_the_range_origin_and_ordered_bounds() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            SpirulinaTelemetry::update_optimal_ranges(RuntimeOrigin::signed(ALICE), vec![(SpirulinaMetric::Ph, 1, 2)]),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            SpirulinaTelemetry::update_optimal_ranges(
+                RuntimeOrigin::root(),
+                vec![(SpirulinaMetric::Ph, 1, 2), (SpirulinaMetric::Light, 5, 5)]
+            ),
+            Error::<Test>::InvalidRange
+        );
+        assert_noop!(
+            KombuchaTelemetry::update_optimal_ranges(RuntimeOrigin::root(), vec![(KombuchaMetric::Co2, 1, 2); 7]),
+            Error::<Test, Instance1>::TooManyRanges
+        );
+
+        assert_ok!(KombuchaTelemetry::update_optimal_ranges(RuntimeOrigin::root(), vec![(KombuchaMetric::Co2, 1, 2)]));
+        assert_eq!(OptimalRanges::<Test, Instance1>::get(KombuchaMetric::Co2), Some((1, 2)));
+    });
+}
+
+#[test]
+fn instances_keep_separate_devices() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"shared");
+
+        assert!(AuthorizedDevices::<Test>::contains_key(device(b"shared")));
+        assert!(!AuthorizedDevices::<Test, Instance1>::contains_key(device(b"shared")));
+        assert_noop!(
+            submit_kombucha(ALICE, b"shared", kombucha_reading(0)),
+            Error::<Test, Instance1>::DeviceNotAuthorized
+        );
+
+        // Unsigned readings of a device id registered in both instances don't displace each
+        // other in the transaction pool
+        assert_ok!(KombuchaTelemetry::authorize_device(
+            RuntimeOrigin::signed(ALICE),
+            device(b"shared"),
+            device_key(),
+            sign(&device_pair(), &KombuchaTelemetry::key_proof_payload(&device(b"shared"), &ALICE))
+        ));
+        let reading = kombucha_reading(0);
+        let payload = KombuchaTelemetry::signing_payload(&device(b"shared"), &reading, &stamp(1), 0);
+        let kombucha = crate::Call::<Test, Instance1>::submit_telemetry_unsigned {
+            device_id: device(b"shared"),
+            reading,
+            stamp: stamp(1),
+            nonce: 0,
+            signature: sign(&device_pair(), &payload),
+        };
+        let kombucha = KombuchaTelemetry::validate_unsigned(TransactionSource::External, &kombucha).unwrap();
+        let spirulina = validate(&unsigned_call(b"shared", spirulina_reading(), 0)).unwrap();
+        assert!(kombucha.provides.iter().all(|tag| !spirulina.provides.contains(tag)));
+    });
+}
+
+#[test]
+fn readings_are_recorded_under_increasing_ids() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        authorize(ALICE, b"pbr-2");
+
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        assert_ok!(submit(ALICE, b"pbr-2", spirulina_reading()));
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        System::assert_last_event(crate::Event::<Test>::TelemetryRecorded(device(b"pbr-1"), 2).into());
+
+        assert_eq!(Telemetry::<Test>::iter_keys().count(), 3);
+        assert_eq!(NextTelemetryId::<Test>::get(), 3);
+        assert_eq!(DeviceLatestTelemetry::<Test>::get(device(b"pbr-1")), Some(2));
+        assert_eq!(DeviceLatestTelemetry::<Test>::get(device(b"pbr-2")), Some(1));
+
+        NextTelemetryId::<Test>::put(u64::MAX);
+        assert_noop!(submit(ALICE, b"pbr-1", spirulina_reading()), Error::<Test>::TelemetryIdOverflow);
+    });
+}
+
+#[test]
+fn devices_keep_their_most_recent_readings() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        authorize(ALICE, b"pbr-2");
+        assert_ok!(submit(ALICE, b"pbr-2", spirulina_reading()));
+
+        for _ in 0..4 {
+            assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        }
+
+        // The oldest of pbr-1's four readings made room for the newest
+        assert_eq!(DeviceReadings::<Test>::get(device(b"pbr-1")).into_inner(), vec![2, 3, 4]);
+        assert!(!Telemetry::<Test>::contains_key(1));
+        assert_eq!(pruned(), vec![1]);
+        assert_eq!(DeviceReadings::<Test>::get(device(b"pbr-2")).into_inner(), vec![0]);
+        assert!(Telemetry::<Test>::contains_key(0));
+    });
+}
+
+#[test]
+fn submissions_are_charged_for_the_readings_they_prune() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        let base = <() as WeightInfo>::submit_telemetry();
+        let call = crate::Call::<Test>::submit_telemetry {
+            device_id: device(b"pbr-1"),
+            reading: spirulina_reading(),
+            stamp: stamp(1),
+            nonce: 0,
+            signature: Default::default(),
+        };
+        assert_eq!(call.get_dispatch_info().weight, base.saturating_add(ON_PRUNED_WEIGHT));
+
+        // Refunded the hook while the device's buffer has room
+        for _ in 0..3 {
+            assert_eq!(submit(ALICE, b"pbr-1", spirulina_reading()).unwrap().actual_weight, Some(base));
+        }
+
+        let full = submit(ALICE, b"pbr-1", spirulina_reading()).unwrap();
+        assert_eq!(pruned(), vec![0]);
+        assert_eq!(full.actual_weight, Some(base.saturating_add(ON_PRUNED_WEIGHT)));
+    });
+}
+
+#[test]
+fn expired_readings_are_pruned_on_idle() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        authorize(ALICE, b"pbr-2");
+        assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        assert_ok!(submit(ALICE, b"pbr-2", spirulina_reading()));
+        System::set_block_number(50);
+        assert_ok!(submit(ALICE, b"pbr-2", spirulina_reading()));
+
+        // Readings are kept for `RetentionPeriod` blocks
+        SpirulinaTelemetry::on_idle(100, Weight::MAX);
+        assert!(pruned().is_empty());
+
+        SpirulinaTelemetry::on_idle(101, Weight::MAX);
+        assert_eq!(pruned(), vec![0, 1]);
+        assert_eq!(PruneCursor::<Test>::get(), 2);
+        assert!(!DeviceReadings::<Test>::contains_key(device(b"pbr-1")));
+        assert_eq!(DeviceLatestTelemetry::<Test>::get(device(b"pbr-1")), None);
+        assert_eq!(DeviceReadings::<Test>::get(device(b"pbr-2")).into_inner(), vec![2]);
+        assert_eq!(DeviceLatestTelemetry::<Test>::get(device(b"pbr-2")), Some(2));
+
+        SpirulinaTelemetry::on_idle(150, Weight::MAX);
+        assert_eq!(pruned(), vec![0, 1, 2]);
+        assert_eq!(Telemetry::<Test>::iter_keys().count(), 0);
+        assert_eq!(PruneCursor::<Test>::get(), 3);
+    });
+}
+
+#[test]
+fn pruning_resumes_where_weight_ran_out() {
+    new_test_ext().execute_with(|| {
+        authorize(ALICE, b"pbr-1");
+        for _ in 0..5 {
+            assert_ok!(submit(ALICE, b"pbr-1", spirulina_reading()));
+        }
+        assert_eq!(pruned(), vec![0, 1]);
+
+        // Enough weight to check one id per block, keeping room for the hook. Ids pruned from
+        // a full buffer cost a check each too, but no hook.
+        let check = <() as WeightInfo>::prune_telemetry();
+        let weight = check.saturating_add(ON_PRUNED_WEIGHT);
+        assert_eq!(SpirulinaTelemetry::on_idle(101, weight), check);
+        assert_eq!(PruneCursor::<Test>::get(), 1);
+        SpirulinaTelemetry::on_idle(101, weight);
+        assert_eq!(SpirulinaTelemetry::on_idle(101, weight), weight);
+        assert_eq!(pruned(), vec![0, 1, 2]);
+        assert_eq!(PruneCursor::<Test>::get(), 3);
+
+        // Too little weight to check any id does nothing
+        assert_eq!(SpirulinaTelemetry::on_idle(101, weight.saturating_sub(Weight::from_ref_time(1))), Weight::zero());
+        assert_eq!(PruneCursor::<Test>::get(), 3);
+
+        SpirulinaTelemetry::on_idle(101, Weight::MAX);
+        assert_eq!(pruned(), vec![0, 1, 2, 3, 4]);
+    });
+}
@@ -0,0 +1,148 @@
+//! Weights for pallet_telemetry
+//!
+//! PLACEHOLDER WEIGHTS: this file was not generated by `benchmark pallet`. Its execution
+//! times are hand-picked estimates. Runtimes must not rely on these numbers.
+//!
+//! Storage read/write counts follow the benchmarks in `benchmarking.rs`, run against the
+//! spirulina instance, whose readings have the most range-checked metrics. Devices are
+//! registered with the Dilithium key of the runtime's `BenchmarkHelper` test vector, the
+//! costliest scheme to verify, for `authorize_device`'s proof of possession and for every
+//! reading signature. The submissions are measured with a full `DeviceReadings` buffer,
+//! whose oldest reading they prune, and `prune_telemetry` is the cost of pruning one
+//! expired reading in `on_idle`.
+//! The execution times are reference estimates until this file is regenerated on
+//! reference hardware with the node's `benchmark pallet` subcommand:
+//!
+//! benchmark pallet
+//! --chain=dev
+//! --steps=50
+//! --repeat=20
+//! --pallet=pallet_telemetry
+//! --extrinsic=*
+//! --execution=wasm
+//! --wasm-execution=compiled
+//! --output=./pallets/telemetry/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_telemetry.
+pub trait WeightInfo {
+	fn submit_telemetry() -> Weight;
+	fn authorize_device() -> Weight;
+	fn update_optimal_ranges(n: u32, ) -> Weight;
+	fn revoke_device() -> Weight;
+	fn transfer_device() -> Weight;
+	fn submit_telemetry_unsigned() -> Weight;
+	fn prune_telemetry() -> Weight;
+}
+
+/// Weights for pallet_telemetry using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: SpirulinaTelemetry AuthorizedDevices (r:1 w:0)
+	// Storage: SpirulinaTelemetry DeviceNonces (r:1 w:1)
+	// Storage: SpirulinaTelemetry NextTelemetryId (r:1 w:1)
+	// Storage: SpirulinaTelemetry OptimalRanges (r:7 w:0)
+	// Storage: SpirulinaTelemetry Telemetry (r:1 w:2)
+	// Storage: SpirulinaTelemetry DeviceReadings (r:1 w:1)
+	// Storage: SpirulinaTelemetry DeviceLatestTelemetry (r:0 w:1)
+	// Storage: SpirulinaTelemetry LastSubmission (r:0 w:1)
+	// Storage: SpirulinaTelemetry LastStamp (r:1 w:1)
+	// Storage: Timestamp Now (r:1 w:0)
+	fn submit_telemetry() -> Weight {
+		Weight::from_ref_time(109_000_000)
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: SpirulinaTelemetry AuthorizedDevices (r:1 w:1)
+	fn authorize_device() -> Weight {
+		Weight::from_ref_time(84_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: SpirulinaTelemetry OptimalRanges (r:0 w:7)
+	fn update_optimal_ranges(n: u32, ) -> Weight {
+		Weight::from_ref_time(14_000_000)
+			.saturating_add(Weight::from_ref_time(2_000_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	// Storage: SpirulinaTelemetry AuthorizedDevices (r:1 w:1)
+	fn revoke_device() -> Weight {
+		Weight::from_ref_time(20_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: SpirulinaTelemetry AuthorizedDevices (r:1 w:1)
+	fn transfer_device() -> Weight {
+		Weight::from_ref_time(21_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: SpirulinaTelemetry AuthorizedDevices (r:1 w:0)
+	// Storage: SpirulinaTelemetry LastSubmission (r:1 w:1)
+	// Storage: SpirulinaTelemetry DeviceNonces (r:1 w:1)
+	// Storage: SpirulinaTelemetry NextTelemetryId (r:1 w:1)
+	// Storage: SpirulinaTelemetry OptimalRanges (r:7 w:0)
+	// Storage: SpirulinaTelemetry Telemetry (r:1 w:2)
+	// Storage: SpirulinaTelemetry DeviceReadings (r:1 w:1)
+	// Storage: SpirulinaTelemetry DeviceLatestTelemetry (r:0 w:1)
+	// Storage: SpirulinaTelemetry LastStamp (r:1 w:1)
+	// Storage: Timestamp Now (r:1 w:0)
+	fn submit_telemetry_unsigned() -> Weight {
+		Weight::from_ref_time(111_000_000)
+			.saturating_add(T::DbWeight::get().reads(15))
+			.saturating_add(T::DbWeight::get().writes(8))
+	}
+	// Storage: SpirulinaTelemetry Telemetry (r:1 w:1)
+	// Storage: SpirulinaTelemetry DeviceReadings (r:1 w:1)
+	// Storage: SpirulinaTelemetry DeviceLatestTelemetry (r:1 w:1)
+	fn prune_telemetry() -> Weight {
+		Weight::from_ref_time(24_000_000)
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn submit_telemetry() -> Weight {
+		Weight::from_ref_time(109_000_000)
+			.saturating_add(RocksDbWeight::get().reads(14))
+			.saturating_add(RocksDbWeight::get().writes(8))
+	}
+	fn authorize_device() -> Weight {
+		Weight::from_ref_time(84_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn update_optimal_ranges(n: u32, ) -> Weight {
+		Weight::from_ref_time(14_000_000)
+			.saturating_add(Weight::from_ref_time(2_000_000).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn revoke_device() -> Weight {
+		Weight::from_ref_time(20_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn transfer_device() -> Weight {
+		Weight::from_ref_time(21_000_000)
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn submit_telemetry_unsigned() -> Weight {
+		Weight::from_ref_time(111_000_000)
+			.saturating_add(RocksDbWeight::get().reads(15))
+			.saturating_add(RocksDbWeight::get().writes(8))
+	}
+	fn prune_telemetry() -> Weight {
+		Weight::from_ref_time(24_000_000)
+			.saturating_add(RocksDbWeight::get().reads(3))
+			.saturating_add(RocksDbWeight::get().writes(3))
+	}
+}
@@ -10,6 +10,9 @@ use scale::{Decode, Encode};
 
 #[ink::contract]
 mod unified_liquidity_pool {
+    /// Denominator of `fee_rate` and `treasury_rate` (parts per billion, like `Perbill`)
+    pub const RATE_DENOMINATOR: Balance = 1_000_000_000;
+
     #[ink(storage)]
     #[derive(SpreadAllocate)]
     pub struct UnifiedLiquidityPool {
@@ -19,9 +22,11 @@ mod unified_liquidity_pool {
         shares: Mapping<(AccountId, TokenId), Balance>,
         // Post-quantum encrypted provider data
         provider_data: Mapping<AccountId, EncryptedData>,
-        // Treasury reserves
+        // Treasury reserves: protocol fees accrued per token and not yet claimed
         treasury: Mapping<TokenId, Balance>,
-        // Protocol parameters
+        // Protocol parameters, both in parts per billion.
+        // `fee_rate` is charged on every trade input; `treasury_rate` is the share *of that fee*
+        // routed to the treasury, matching `ProtocolFeeShare` in pallet-liquidity-pool.
         fee_rate: Balance,
         treasury_rate: Balance,
         // Quantum-resistant keys
@@ -46,6 +51,8 @@ mod unified_liquidity_pool {
     impl UnifiedLiquidityPool {
         #[ink(constructor)]
         pub fn new(fee_rate: Balance, treasury_rate: Balance) -> Self {
+            assert!(fee_rate <= RATE_DENOMINATOR, "fee_rate above 100%");
+            assert!(treasury_rate <= RATE_DENOMINATOR, "treasury_rate above 100%");
             ink_lang::utils::initialize_contract(|contract: &mut Self| {
                 contract.fee_rate = fee_rate;
                 contract.treasury_rate = treasury_rate;
@@ -134,6 +141,63 @@ mod unified_liquidity_pool {
             Ok(amount)
         }
 
+        /// Swap `amount_in` of `token_in` for `token_out` against the constant product of
+        /// their reserves. The fee is taken from the input and split by `collect_fee`: the
+        /// treasury share leaves the reserves, the rest stays with liquidity providers.
+        #[ink(message)]
+        pub fn swap(
+            &mut self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            min_amount_out: Balance,
+        ) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+
+            if token_in == token_out {
+                return Err(Error::InvalidTokenPair);
+            }
+
+            let reserve_in = self.reserves.get(token_in).unwrap_or(0);
+            let reserve_out = self.reserves.get(token_out).unwrap_or(0);
+            if reserve_in == 0 || reserve_out == 0 {
+                return Err(Error::InsufficientLiquidity);
+            }
+
+            // Quote the trade before anything is written
+            let (fee, _) = self.split_fee(amount_in)?;
+            let amount_in_after_fee = amount_in - fee;
+            let amount_out = amount_in_after_fee
+                .checked_mul(reserve_out)
+                .ok_or(Error::ArithmeticError)?
+                / reserve_in.checked_add(amount_in_after_fee).ok_or(Error::ArithmeticError)?;
+            if amount_out < min_amount_out {
+                return Err(Error::SlippageExceeded);
+            }
+
+            let (fee, treasury_share) = self.collect_fee(token_in, amount_in)?;
+            self.update_reserves(token_in, amount_in - treasury_share, true)?;
+            self.update_reserves(token_out, amount_out, false)?;
+
+            self.env().emit_event(Swapped {
+                trader: caller,
+                token_in,
+                token_out,
+                amount_in,
+                amount_out,
+                fee,
+                treasury_share,
+            });
+
+            Ok(amount_out)
+        }
+
+        /// Protocol fees accrued in `token_id` and not yet claimed
+        #[ink(message)]
+        pub fn treasury_balance(&self, token_id: TokenId) -> Balance {
+            self.treasury.get(token_id).unwrap_or(0)
+        }
+
         // Helper functions
         fn verify_human_handprint(&self, account: &AccountId) -> bool {
             // Integrate with Humanity Protocol for verification
@@ -150,6 +214,36 @@ mod unified_liquidity_pool {
             Ok(amount) // Simplified for example
         }
 
+        /// Charge the trading fee on `amount_in` and split it the same way as
+        /// pallet-liquidity-pool: the treasury share accrues to `treasury`, the rest stays
+        /// in the reserves for liquidity providers. Returns (fee, treasury_share).
+        fn collect_fee(
+            &mut self,
+            token_id: TokenId,
+            amount_in: Balance,
+        ) -> Result<(Balance, Balance), Error> {
+            let (fee, treasury_share) = self.split_fee(amount_in)?;
+
+            let accrued = self.treasury.get(token_id).unwrap_or(0);
+            let accrued = accrued.checked_add(treasury_share).ok_or(Error::ArithmeticError)?;
+            self.treasury.insert(token_id, &accrued);
+
+            Ok((fee, treasury_share))
+        }
+
+        /// Fee on `amount_in` and the treasury's share of it, both rounded down
+        fn split_fee(&self, amount_in: Balance) -> Result<(Balance, Balance), Error> {
+            let fee = amount_in
+                .checked_mul(self.fee_rate)
+                .ok_or(Error::ArithmeticError)?
+                / RATE_DENOMINATOR;
+            let treasury_share = fee
+                .checked_mul(self.treasury_rate)
+                .ok_or(Error::ArithmeticError)?
+                / RATE_DENOMINATOR;
+            Ok((fee, treasury_share))
+        }
+
         fn update_reserves(
             &mut self,
             token_id: TokenId,
@@ -189,6 +283,18 @@ mod unified_liquidity_pool {
         shares: Balance,
     }
 
+    #[ink(event)]
+    pub struct Swapped {
+        #[ink(topic)]
+        trader: AccountId,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        amount_out: Balance,
+        fee: Balance,
+        treasury_share: Balance,
+    }
+
     // Custom errors
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -196,6 +302,46 @@ mod unified_liquidity_pool {
         InsufficientShares,
         ArithmeticError,
         NotHuman,
+        InvalidTokenPair,
+        InsufficientLiquidity,
+        SlippageExceeded,
         // Add more error types as needed
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_lang as ink;
+
+        /// pallet-liquidity-pool's `NOURISH_CHAIN_FEE` (0.369%) and its test runtime's 20%
+        /// `ProtocolFeeShare`, in parts per billion
+        const FEE_RATE: Balance = 3_690_000;
+        const TREASURY_RATE: Balance = 200_000_000;
+
+        #[ink::test]
+        fn treasury_share_matches_the_pallet_protocol_fee_share() {
+            let mut pool = UnifiedLiquidityPool::new(FEE_RATE, TREASURY_RATE);
+            assert!(pool.add_liquidity(TokenId::NRSH, 1_000_000).is_ok());
+            assert!(pool.add_liquidity(TokenId::ELXR, 1_000_000).is_ok());
+
+            // The trade of the pallet's protocol fee test: 0.369% of 100_000 is 369, of
+            // which 20% (73) goes to the treasury and the rest stays in the reserves
+            assert_eq!(pool.swap(TokenId::NRSH, TokenId::ELXR, 100_000, 0), Ok(90_604));
+            assert_eq!(pool.treasury_balance(TokenId::NRSH), 73);
+            assert_eq!(pool.reserves.get(TokenId::NRSH), Some(1_100_000 - 73));
+            assert_eq!(pool.reserves.get(TokenId::ELXR), Some(1_000_000 - 90_604));
+        }
+
+        #[ink::test]
+        fn swaps_respect_min_amount_out_without_accruing_fees() {
+            let mut pool = UnifiedLiquidityPool::new(FEE_RATE, TREASURY_RATE);
+            assert!(pool.add_liquidity(TokenId::NRSH, 1_000_000).is_ok());
+            assert!(pool.add_liquidity(TokenId::ELXR, 1_000_000).is_ok());
+
+            assert_eq!(pool.swap(TokenId::NRSH, TokenId::ELXR, 100_000, 90_605), Err(Error::SlippageExceeded));
+            assert_eq!(pool.swap(TokenId::NRSH, TokenId::NRSH, 100_000, 0), Err(Error::InvalidTokenPair));
+            assert_eq!(pool.swap(TokenId::NRSH, TokenId::IMRT, 100_000, 0), Err(Error::InsufficientLiquidity));
+            assert_eq!(pool.treasury_balance(TokenId::NRSH), 0);
+        }
+    }
 }